@@ -1,10 +1,13 @@
-use std::ptr;
+use std::{ f64, ptr, str };
 use std::io::Write;
+use std::string::FromUtf8Error;
 use JsonValue;
 use number::Number;
+use object::Object;
 use std::io;
 
-use util::print_dec;
+use util::{ grisu3, print_dec, scan };
+use wtf8::{ Wtf8String, Piece };
 
 const QU: u8 = b'"';
 const BS: u8 = b'\\';
@@ -37,66 +40,109 @@ static ESCAPED: [u8; 256] = [
   __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, // F
 ];
 
+/// Generator of JSON, writing a `JsonValue` out with `write_json`, which
+/// goes through the hooks below for every piece of it. Implementing the
+/// trait, and overriding some of the hooks, allows for custom output
+/// without having to walk the `JsonValue` again.
+///
+/// Hooks that are overridden can't call their default implementation,
+/// so generators usually wrap one of the generators of this crate, and
+/// pass whatever they don't change on to it. Writing into the very same
+/// stream keeps the output in order:
+///
+/// ```
+/// # #[macro_use] extern crate json;
+/// # fn main() {
+/// use std::io;
+/// use json::{ Generator, DumpGenerator };
+///
+/// // Wrap strings, but not keys, in ANSI color codes
+/// struct Highlighter {
+///     inner: DumpGenerator,
+/// }
+///
+/// impl Generator for Highlighter {
+///     type T = Vec<u8>;
+///
+///     fn get_writer(&mut self) -> &mut Vec<u8> {
+///         self.inner.get_writer()
+///     }
+///
+///     fn write_min(&mut self, slice: &[u8], min: u8) -> io::Result<()> {
+///         self.inner.write_min(slice, min)
+///     }
+///
+///     fn write_key(&mut self, key: &str) -> io::Result<()> {
+///         self.inner.write_key(key)
+///     }
+///
+///     fn write_string(&mut self, string: &str) -> io::Result<()> {
+///         try!(self.write(b"\x1b[32m"));
+///         try!(self.inner.write_string(string));
+///         self.write(b"\x1b[0m")
+///     }
+/// }
+///
+/// let mut gen = Highlighter { inner: DumpGenerator::new() };
+///
+/// gen.write_json(&object!{ "name" => "Ferris", "legs" => 10 }).unwrap();
+///
+/// assert_eq!(gen.inner.consume().unwrap(), "{\"name\":\x1b[32m\"Ferris\"\x1b[0m,\"legs\":10}");
+/// # }
+/// ```
 pub trait Generator {
+    /// Stream the JSON is written into.
     type T: Write;
 
+    /// Get the stream the JSON is written into.
     fn get_writer(&mut self) -> &mut Self::T;
 
+    /// Write bytes as they are.
     #[inline(always)]
     fn write(&mut self, slice: &[u8]) -> io::Result<()> {
         self.get_writer().write_all(slice)
     }
 
+    /// Write a single byte as it is.
     #[inline(always)]
     fn write_char(&mut self, ch: u8) -> io::Result<()> {
         self.get_writer().write_all(&[ch])
     }
 
+    /// Write either the whole `slice` when pretty printing, or just the
+    /// `min` byte of it otherwise.
     fn write_min(&mut self, slice: &[u8], min: u8) -> io::Result<()>;
 
+    /// Start a new line, at the current indentation, when pretty printing.
     #[inline(always)]
     fn new_line(&mut self) -> io::Result<()> { Ok(()) }
 
+    /// Indent the lines that follow by one more level.
     #[inline(always)]
     fn indent(&mut self) {}
 
+    /// Indent the lines that follow by one less level.
     #[inline(always)]
     fn dedent(&mut self) {}
 
-    #[inline(never)]
-    fn write_string_complex(&mut self, string: &str, mut start: usize) -> io::Result<()> {
-        try!(self.write(string[ .. start].as_bytes()));
-
-        for (index, ch) in string.bytes().enumerate().skip(start) {
-            let escape = ESCAPED[ch as usize];
-            if escape > 0 {
-                try!(self.write(string[start .. index].as_bytes()));
-                try!(self.write(&[b'\\', escape]));
-                start = index + 1;
-            }
-            if escape == b'u' {
-                try!(write!(self.get_writer(), "{:04x}", ch));
-            }
-        }
-        try!(self.write(string[start ..].as_bytes()));
-
-        self.write_char(b'"')
+    /// Characters to escape in strings on top of those that always are.
+    #[inline(always)]
+    fn escape(&self) -> EscapeConfig {
+        EscapeConfig::new()
     }
 
+    /// Write a string, quoted and escaped. Unless `write_key` is overridden,
+    /// this is used for keys of objects too.
     #[inline(always)]
     fn write_string(&mut self, string: &str) -> io::Result<()> {
-        try!(self.write_char(b'"'));
-
-        for (index, ch) in string.bytes().enumerate() {
-            if ESCAPED[ch as usize] > 0 {
-                return self.write_string_complex(string, index)
-            }
-        }
+        let escape = self.escape();
 
-        try!(self.write(string.as_bytes()));
+        try!(self.write_char(b'"'));
+        try!(write_contents(self, string, escape));
         self.write_char(b'"')
     }
 
+    /// Write a number. `NaN` is written as `null`.
     #[inline(always)]
     fn write_number(&mut self, num: &Number) -> io::Result<()> {
         if num.is_nan() {
@@ -113,83 +159,617 @@ pub trait Generator {
         }
     }
 
+    /// Write a whole value, going through the other hooks for each of its
+    /// pieces.
     fn write_json(&mut self, json: &JsonValue) -> io::Result<()> {
-        match *json {
-            JsonValue::Null               => self.write(b"null"),
-            JsonValue::Short(ref short)   => self.write_string(short.as_str()),
-            JsonValue::String(ref string) => self.write_string(string),
-            JsonValue::Number(ref number) => self.write_number(number),
-            JsonValue::Boolean(true)      => self.write(b"true"),
-            JsonValue::Boolean(false)     => self.write(b"false"),
-            JsonValue::Array(ref array)   => {
-                try!(self.write_char(b'['));
-                let mut iter = array.iter();
-
-                if let Some(item) = iter.next() {
-                    self.indent();
-                    try!(self.new_line());
-                    try!(self.write_json(item));
-                } else {
-                    try!(self.write_char(b']'));
-                    return Ok(());
+        write_value(self, json)
+    }
+
+    /// Write the colon between a key and its value.
+    #[inline(always)]
+    fn write_colon(&mut self) -> io::Result<()> {
+        self.write_min(b": ", b':')
+    }
+
+    /// Write a key of an object, along with the colon after it.
+    #[inline(always)]
+    fn write_key(&mut self, key: &str) -> io::Result<()> {
+        try!(self.write_string(key));
+        self.write_colon()
+    }
+
+    /// Write a key of an object along with its value. Overriding this
+    /// allows for changing, or leaving out, values depending on their key.
+    #[inline(always)]
+    fn write_entry(&mut self, key: &str, value: &JsonValue) -> io::Result<()> {
+        try!(self.write_key(key));
+        self.write_json(value)
+    }
+
+    /// Write the opening bracket of an array.
+    #[inline(always)]
+    fn begin_array(&mut self) -> io::Result<()> {
+        self.write_char(b'[')
+    }
+
+    /// Write the closing bracket of an array.
+    #[inline(always)]
+    fn end_array(&mut self) -> io::Result<()> {
+        self.write_char(b']')
+    }
+
+    /// Write the opening brace of an object.
+    #[inline(always)]
+    fn begin_object(&mut self) -> io::Result<()> {
+        self.write_char(b'{')
+    }
+
+    /// Write the closing brace of an object.
+    #[inline(always)]
+    fn end_object(&mut self) -> io::Result<()> {
+        self.write_char(b'}')
+    }
+
+    /// Write a number exactly as it was in the source, see
+    /// `ParserOptions::lossless_numbers`.
+    #[inline(always)]
+    fn write_raw_number(&mut self, raw: &str) -> io::Result<()> {
+        self.write(raw.as_bytes())
+    }
+
+    /// Write an object, with each of its entries written by `write_entry`.
+    fn write_object(&mut self, object: &Object) -> io::Result<()> {
+        try!(self.begin_object());
+        let mut iter = object.iter();
+
+        if let Some((key, value)) = iter.next() {
+            self.indent();
+            try!(self.new_line());
+            try!(self.write_entry(key, value));
+        } else {
+            return self.end_object();
+        }
+
+        for (key, value) in iter {
+            try!(self.write_char(b','));
+            try!(self.new_line());
+            try!(self.write_entry(key, value));
+        }
+
+        self.dedent();
+        try!(self.new_line());
+        self.end_object()
+    }
+}
+
+// Write a whole value, as `Generator::write_json` does unless overridden.
+fn write_value<G: Generator + ?Sized>(gen: &mut G, json: &JsonValue) -> io::Result<()> {
+    match *json {
+        JsonValue::Null               => gen.write(b"null"),
+        JsonValue::Short(ref short)   => gen.write_string(short.as_str()),
+        JsonValue::String(ref string) => gen.write_string(string),
+        JsonValue::Wtf8(ref string)   => write_wtf8(gen, string),
+        JsonValue::Number(ref number) => gen.write_number(number),
+        JsonValue::RawNumber(ref raw) => gen.write_raw_number(raw.as_str()),
+        JsonValue::Boolean(true)      => gen.write(b"true"),
+        JsonValue::Boolean(false)     => gen.write(b"false"),
+        JsonValue::Array(ref array)   => {
+            try!(gen.begin_array());
+            let mut iter = array.iter();
+
+            if let Some(item) = iter.next() {
+                gen.indent();
+                try!(gen.new_line());
+                try!(gen.write_json(item));
+            } else {
+                return gen.end_array();
+            }
+
+            for item in iter {
+                try!(gen.write_char(b','));
+                try!(gen.new_line());
+                try!(gen.write_json(item));
+            }
+
+            gen.dedent();
+            try!(gen.new_line());
+            gen.end_array()
+        },
+        JsonValue::Object(ref object) => gen.write_object(object),
+    }
+}
+
+// Write an array or object on a single line, with a space after each
+// comma, as well as inside the braces of objects. Members and entries go
+// through `write_json` and `write_entry` same as always, and they fit on
+// the line if their container does.
+fn write_inline<G: Generator + ?Sized>(gen: &mut G, json: &JsonValue) -> io::Result<()> {
+    match *json {
+        JsonValue::Array(ref array) => {
+            try!(gen.begin_array());
+
+            for (index, item) in array.iter().enumerate() {
+                if index != 0 {
+                    try!(gen.write(b", "));
+                }
+
+                try!(gen.write_json(item));
+            }
+
+            gen.end_array()
+        },
+        JsonValue::Object(ref object) if !object.is_empty() => {
+            try!(gen.begin_object());
+            try!(gen.write_char(b' '));
+
+            for (index, (key, value)) in object.iter().enumerate() {
+                if index != 0 {
+                    try!(gen.write(b", "));
                 }
 
-                for item in iter {
-                    try!(self.write_char(b','));
-                    try!(self.new_line());
-                    try!(self.write_json(item));
+                try!(gen.write_entry(key, value));
+            }
+
+            try!(gen.write_char(b' '));
+            gen.end_object()
+        },
+        _ => write_value(gen, json),
+    }
+}
+
+// Write the contents of a string, escaped, without the quotes around.
+#[inline(always)]
+fn write_contents<G: Generator + ?Sized>(gen: &mut G, string: &str, escape: EscapeConfig) -> io::Result<()> {
+    if escape != EscapeConfig::new() {
+        return write_string_escaped(gen, string, escape);
+    }
+
+    let index = scan::string_run(string.as_bytes());
+
+    if index < string.len() {
+        return write_string_complex(gen, string, index);
+    }
+
+    gen.write(string.as_bytes())
+}
+
+// Write the rest of a string from `index`, the first byte that needs
+// escaping.
+#[inline(never)]
+fn write_string_complex<G: Generator + ?Sized>(gen: &mut G, string: &str, mut index: usize) -> io::Result<()> {
+    let bytes = string.as_bytes();
+
+    try!(gen.write(&bytes[ .. index]));
+
+    // Every byte at `index` needs escaping, the bytes up to the next
+    // one that does are skipped over in bulk.
+    while index < bytes.len() {
+        let ch = bytes[index];
+        let escape = ESCAPED[ch as usize];
+
+        try!(gen.write(&[b'\\', escape]));
+
+        if escape == b'u' {
+            try!(write!(gen.get_writer(), "{:04x}", ch));
+        }
+
+        let start = index + 1;
+
+        index = start + scan::string_run(&bytes[start ..]);
+
+        try!(gen.write(&bytes[start .. index]));
+    }
+
+    Ok(())
+}
+
+// Write a string escaping the characters set in the `EscapeConfig` on top
+// of those that always are.
+#[inline(never)]
+fn write_string_escaped<G: Generator + ?Sized>(gen: &mut G, string: &str, escape: EscapeConfig) -> io::Result<()> {
+    let bytes = string.as_bytes();
+    let mut start = 0;
+
+    for (index, ch) in string.char_indices() {
+        if !escape.escapes(ch) {
+            continue;
+        }
+
+        let code = ch as u32;
+
+        try!(gen.write(&bytes[start .. index]));
+
+        start = index + ch.len_utf8();
+
+        let short = if code < 0x80 { ESCAPED[code as usize] } else { 0 };
+
+        if short != 0 && short != b'u' {
+            try!(gen.write(&[b'\\', short]));
+        } else if ch == '/' {
+            try!(gen.write(b"\\/"));
+        } else {
+            let mut units = [0; 2];
+
+            for unit in ch.encode_utf16(&mut units).iter() {
+                try!(write!(gen.get_writer(), "\\u{:04x}", unit));
+            }
+        }
+    }
+
+    gen.write(&bytes[start ..])
+}
+
+// Write a string with lone surrogates, see `LoneSurrogates::Preserve`, each
+// of them escaped the way it was read. It can't go through `write_string`,
+// so it's escaped the same way regardless.
+fn write_wtf8<G: Generator + ?Sized>(gen: &mut G, string: &Wtf8String) -> io::Result<()> {
+    let escape = gen.escape();
+
+    try!(gen.write_char(b'"'));
+
+    for piece in string.pieces() {
+        match piece {
+            Piece::Str(text)       => try!(write_contents(gen, text, escape)),
+            Piece::Surrogate(unit) => try!(write!(gen.get_writer(), "\\u{:04x}", unit)),
+        }
+    }
+
+    gen.write_char(b'"')
+}
+
+/// Characters to escape in strings, on top of `"`, `\\` and control
+/// characters, which always are. Used with `JsonValue::dump_escaped`, or
+/// `PrettyConfig::escape`.
+///
+/// ```
+/// use json::EscapeConfig;
+///
+/// let data = json::parse(r#"["</script>", "café ☕ 😀"]"#).unwrap();
+///
+/// assert_eq!(data.dump_escaped(EscapeConfig::new().html(true)), r#"["\u003c/script\u003e","café ☕ 😀"]"#);
+/// assert_eq!(data.dump_escaped(EscapeConfig::new().ascii_only(true)), r#"["</script>","caf\u00e9 \u2615 \ud83d\ude00"]"#);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EscapeConfig {
+    ascii_only: bool,
+    html: bool,
+    slash: bool,
+}
+
+impl EscapeConfig {
+    /// Create a configuration escaping nothing more than has to be.
+    pub fn new() -> Self {
+        EscapeConfig {
+            ascii_only: false,
+            html: false,
+            slash: false,
+        }
+    }
+
+    /// Escape all characters outside of ASCII, those outside of the Basic
+    /// Multilingual Plane as surrogate pairs, for consumers that can't read
+    /// anything but ASCII. Defaults to `false`.
+    pub fn ascii_only(mut self, ascii_only: bool) -> Self {
+        self.ascii_only = ascii_only;
+        self
+    }
+
+    /// Escape `<`, `>`, `&`, U+2028 and U+2029, so that JSON can be safely
+    /// embedded in HTML, within a `<script>` tag. Defaults to `false`.
+    pub fn html(mut self, html: bool) -> Self {
+        self.html = html;
+        self
+    }
+
+    /// Escape `/` as `\\/`. Defaults to `false`.
+    pub fn slash(mut self, slash: bool) -> Self {
+        self.slash = slash;
+        self
+    }
+
+    // Does the character have to be escaped.
+    #[inline]
+    fn escapes(&self, ch: char) -> bool {
+        let code = ch as u32;
+
+        match ch {
+            '<' | '>' | '&' => self.html,
+            '/'             => self.slash,
+            '\u{2028}' | '\u{2029}' if self.html => true,
+            _ => {
+                (code < 0x80 && ESCAPED[code as usize] != 0) ||
+                (code >= 0x80 && self.ascii_only)
+            },
+        }
+    }
+}
+
+impl Default for EscapeConfig {
+    fn default() -> Self {
+        EscapeConfig::new()
+    }
+}
+
+/// Configuration of pretty printing, see `JsonValue::pretty_with`. Defaults
+/// are provided through `PrettyConfig::new()`, the same as used by
+/// `JsonValue::pretty(4)`, each of which can be changed with a chainable
+/// setter.
+///
+/// ```
+/// # #[macro_use] extern crate json;
+/// # fn main() {
+/// use json::PrettyConfig;
+///
+/// let data = object!{
+///     "name" => "route",
+///     "points" => array![array![0, 0], array![10.5, 20], array![30, 40]]
+/// };
+///
+/// let config = PrettyConfig::new()
+///     .tabs()
+///     .max_width(50)
+///     .trailing_newline(true);
+///
+/// assert_eq!(data.pretty_with(&config), "{\n\t\"name\": \"route\",\n\t\"points\": [[0, 0], [10.5, 20], [30, 40]]\n}\n");
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrettyConfig {
+    indent: String,
+    newline: String,
+    colon: &'static str,
+    max_width: usize,
+    trailing_newline: bool,
+    escape: EscapeConfig,
+}
+
+impl PrettyConfig {
+    /// Create a configuration with default values: indentation of 4 spaces,
+    /// `\n` for new lines, `"key": value` spacing, every non-empty array and
+    /// object spread over many lines, and no trailing new line.
+    pub fn new() -> Self {
+        PrettyConfig {
+            indent: "    ".into(),
+            newline: "\n".into(),
+            colon: ": ",
+            max_width: 0,
+            trailing_newline: false,
+            escape: EscapeConfig::new(),
+        }
+    }
+
+    /// Indent with the given number of spaces.
+    pub fn spaces(mut self, spaces: u16) -> Self {
+        self.indent = " ".repeat(spaces as usize);
+        self
+    }
+
+    /// Indent with a single tab.
+    pub fn tabs(mut self) -> Self {
+        self.indent = "\t".into();
+        self
+    }
+
+    /// Sequence to start new lines with, such as `"\r\n"`. Defaults to
+    /// `"\n"`. Anything other than whitespace makes for invalid JSON.
+    pub fn newline<S: Into<String>>(mut self, newline: S) -> Self {
+        self.newline = newline.into();
+        self
+    }
+
+    /// Put a space before the colon after object keys as well, as in
+    /// `"key" : value`. Defaults to `false`.
+    pub fn space_before_colon(mut self, space: bool) -> Self {
+        self.colon = if space { " : " } else { ": " };
+        self
+    }
+
+    /// Keep arrays and objects on a single line, as in `[1, 2, 3]` and
+    /// `{ "a": 1 }`, if the line is at most this many bytes long with them
+    /// on it, not counting a comma after them. Defaults to `0`, which puts
+    /// every non-empty array and object on many lines.
+    pub fn max_width(mut self, width: usize) -> Self {
+        self.max_width = width;
+        self
+    }
+
+    /// End the output with a new line. Defaults to `false`.
+    pub fn trailing_newline(mut self, trailing: bool) -> Self {
+        self.trailing_newline = trailing;
+        self
+    }
+
+    /// Characters to escape in strings, see `EscapeConfig`. Defaults to
+    /// `EscapeConfig::new()`.
+    pub fn escape(mut self, escape: EscapeConfig) -> Self {
+        self.escape = escape;
+        self
+    }
+
+    // Is the value an array or object that fits on the line it starts on
+    // at `column`.
+    fn fits(&self, column: usize, json: &JsonValue) -> bool {
+        match *json {
+            JsonValue::Array(_) | JsonValue::Object(_) => {
+                self.max_width > column && self.inline_width(json, self.max_width - column).is_some()
+            },
+            _ => false,
+        }
+    }
+
+    // Width of a value when written on a single line, or `None` if it's
+    // wider than the `budget`.
+    fn inline_width(&self, json: &JsonValue, budget: usize) -> Option<usize> {
+        let width = match *json {
+            JsonValue::Null               => 4,
+            JsonValue::Boolean(true)      => 4,
+            JsonValue::Boolean(false)     => 5,
+            JsonValue::Short(ref short)   => match self.string_width(short.as_str(), budget) {
+                Some(width) => width,
+                None        => return None,
+            },
+            JsonValue::String(ref string) => match self.string_width(string, budget) {
+                Some(width) => width,
+                None        => return None,
+            },
+            JsonValue::Wtf8(ref string)   => match self.wtf8_width(string, budget) {
+                Some(width) => width,
+                None        => return None,
+            },
+            JsonValue::RawNumber(ref raw) => raw.as_str().len(),
+            JsonValue::Number(ref number) => {
+                let mut code = Vec::with_capacity(24);
+
+                WriterGenerator::new(&mut code).write_number(number).expect("Can't fail");
+
+                code.len()
+            },
+            JsonValue::Array(ref array) => {
+                let mut width = 2 + 2 * array.len().saturating_sub(1);
+
+                for item in array {
+                    if width > budget {
+                        return None;
+                    }
+
+                    width += match self.inline_width(item, budget - width) {
+                        Some(width) => width,
+                        None        => return None,
+                    };
                 }
 
-                self.dedent();
-                try!(self.new_line());
-                self.write_char(b']')
+                width
             },
             JsonValue::Object(ref object) => {
-                try!(self.write_char(b'{'));
-                let mut iter = object.iter();
-
-                if let Some((key, value)) = iter.next() {
-                    self.indent();
-                    try!(self.new_line());
-                    try!(self.write_string(key));
-                    try!(self.write_min(b": ", b':'));
-                    try!(self.write_json(value));
-                } else {
-                    try!(self.write_char(b'}'));
-                    return Ok(());
+                if object.is_empty() {
+                    return if budget >= 2 { Some(2) } else { None };
                 }
 
-                for (key, value) in iter {
-                    try!(self.write_char(b','));
-                    try!(self.new_line());
-                    try!(self.write_string(key));
-                    try!(self.write_min(b": ", b':'));
-                    try!(self.write_json(value));
+                let mut width = 4 + (2 + self.colon.len()) * object.len() - 2;
+
+                for (key, value) in object.iter() {
+                    if width > budget {
+                        return None;
+                    }
+
+                    width += match self.string_width(key, budget - width) {
+                        Some(width) => width,
+                        None        => return None,
+                    };
+
+                    if width > budget {
+                        return None;
+                    }
+
+                    width += match self.inline_width(value, budget - width) {
+                        Some(width) => width,
+                        None        => return None,
+                    };
                 }
 
-                self.dedent();
-                try!(self.new_line());
-                self.write_char(b'}')
+                width
+            },
+        };
+
+        if width > budget { None } else { Some(width) }
+    }
+
+    // Width of a string once quoted and escaped, or `None` if it's wider
+    // than the `budget`.
+    fn string_width(&self, string: &str, budget: usize) -> Option<usize> {
+        if string.len() + 2 > budget {
+            return None;
+        }
+
+        let width = if self.escape == EscapeConfig::new() {
+            string.bytes().fold(2, |width, ch| width + match ESCAPED[ch as usize] {
+                0    => 1,
+                b'u' => 6,
+                _    => 2,
+            })
+        } else {
+            string.chars().fold(2, |width, ch| width + match ch as u32 {
+                _ if !self.escape.escapes(ch) => ch.len_utf8(),
+                0x10000 ... 0x10FFFF          => 12,
+                0x80 ... 0xFFFF               => 6,
+                code => match ESCAPED[code as usize] {
+                    0 if ch == '/' => 2,
+                    0 | b'u'       => 6,
+                    _              => 2,
+                },
+            })
+        };
+
+        if width > budget { None } else { Some(width) }
+    }
+
+    // Same as `string_width`, for strings with lone surrogates, each of
+    // which is escaped as six characters.
+    fn wtf8_width(&self, string: &Wtf8String, budget: usize) -> Option<usize> {
+        let mut width = 2;
+
+        for piece in string.pieces() {
+            width += match piece {
+                Piece::Str(text) => match self.string_width(text, budget - width + 2) {
+                    Some(text_width) => text_width - 2,
+                    None             => return None,
+                },
+                Piece::Surrogate(_) => 6,
+            };
+
+            if width > budget {
+                return None;
             }
         }
+
+        Some(width)
     }
 }
 
+impl Default for PrettyConfig {
+    fn default() -> Self {
+        PrettyConfig::new()
+    }
+}
+
+/// Generator of compact JSON into a `String`, as used by `JsonValue::dump`.
 pub struct DumpGenerator {
     code: Vec<u8>,
+    escape: EscapeConfig,
 }
 
 impl DumpGenerator {
+    /// Create a new `DumpGenerator`.
     pub fn new() -> Self {
+        DumpGenerator::with_escape(EscapeConfig::new())
+    }
+
+    /// Create a new `DumpGenerator`, escaping characters in strings as set
+    /// in the `EscapeConfig`.
+    pub fn with_escape(escape: EscapeConfig) -> Self {
         DumpGenerator {
             code: Vec::with_capacity(1024),
+            escape: escape,
         }
     }
 
-    pub fn consume(self) -> String {
-        // Original strings were unicode, numbers are all ASCII,
-        // therefore this is safe.
-        unsafe { String::from_utf8_unchecked(self.code) }
+    /// Get the JSON generated so far. Fails if anything that isn't valid
+    /// UTF-8 was written through `write` or `get_writer`.
+    pub fn consume(self) -> Result<String, FromUtf8Error> {
+        String::from_utf8(self.code)
+    }
+
+    // Same as `consume`, for generators that only wrote out whole values.
+    // Original strings were unicode, numbers are all ASCII, therefore this
+    // is safe.
+    pub(crate) unsafe fn consume_unchecked(self) -> String {
+        String::from_utf8_unchecked(self.code)
+    }
+}
+
+impl Default for DumpGenerator {
+    fn default() -> Self {
+        DumpGenerator::new()
     }
 }
 
@@ -217,25 +797,61 @@ impl Generator for DumpGenerator {
         self.code.push(min);
         Ok(())
     }
+
+    #[inline(always)]
+    fn escape(&self) -> EscapeConfig {
+        self.escape
+    }
 }
 
+/// Generator of pretty printed JSON into a `String`, as used by
+/// `JsonValue::pretty`.
 pub struct PrettyGenerator {
     code: Vec<u8>,
     dent: u16,
-    spaces_per_indent: u16,
+    config: PrettyConfig,
+
+    // Where the current line starts in the `code`
+    line_start: usize,
 }
 
 impl PrettyGenerator {
+    /// Create a new `PrettyGenerator`, indenting with the given number of
+    /// spaces.
     pub fn new(spaces: u16) -> Self {
+        PrettyGenerator::with_config(PrettyConfig::new().spaces(spaces))
+    }
+
+    /// Create a new `PrettyGenerator`, laid out as set in the `PrettyConfig`.
+    pub fn with_config(config: PrettyConfig) -> Self {
         PrettyGenerator {
             code: Vec::with_capacity(1024),
             dent: 0,
-            spaces_per_indent: spaces
+            config: config,
+            line_start: 0,
         }
     }
 
-    pub fn consume(self) -> String {
-        unsafe { String::from_utf8_unchecked(self.code) }
+    /// Write the trailing new line, if there is to be one, once the whole
+    /// value has been written.
+    pub fn finish(&mut self) -> io::Result<()> {
+        if self.config.trailing_newline {
+            extend_from_slice(&mut self.code, self.config.newline.as_bytes());
+        }
+        Ok(())
+    }
+
+    /// Get the JSON generated so far. Fails if anything that isn't valid
+    /// UTF-8 was written through `write` or `get_writer`.
+    pub fn consume(self) -> Result<String, FromUtf8Error> {
+        String::from_utf8(self.code)
+    }
+
+    // Same as `consume`, for generators that only wrote out whole values.
+    // Original strings were unicode, numbers are all ASCII, therefore this
+    // is safe.
+    pub(crate) unsafe fn consume_unchecked(self) -> String {
+        String::from_utf8_unchecked(self.code)
     }
 }
 
@@ -265,10 +881,29 @@ impl Generator for PrettyGenerator {
         Ok(())
     }
 
+    fn write_colon(&mut self) -> io::Result<()> {
+        extend_from_slice(&mut self.code, self.config.colon.as_bytes());
+        Ok(())
+    }
+
+    fn write_json(&mut self, json: &JsonValue) -> io::Result<()> {
+        if self.config.fits(self.code.len() - self.line_start, json) {
+            return write_inline(self, json);
+        }
+
+        write_value(self, json)
+    }
+
+    #[inline(always)]
+    fn escape(&self) -> EscapeConfig {
+        self.config.escape
+    }
+
     fn new_line(&mut self) -> io::Result<()> {
-        self.code.push(b'\n');
-        for _ in 0..(self.dent * self.spaces_per_indent) {
-            self.code.push(b' ');
+        extend_from_slice(&mut self.code, self.config.newline.as_bytes());
+        self.line_start = self.code.len();
+        for _ in 0..self.dent {
+            extend_from_slice(&mut self.code, self.config.indent.as_bytes());
         }
         Ok(())
     }
@@ -282,19 +917,34 @@ impl Generator for PrettyGenerator {
     }
 }
 
-pub struct WriterGenerator<'a, W: 'a + Write> {
-    writer: &'a mut W
+/// Generator of compact JSON into a stream, as used by `JsonValue::write`.
+pub struct WriterGenerator<W: Write> {
+    writer: W,
+    escape: EscapeConfig,
 }
 
-impl<'a, W> WriterGenerator<'a, W> where W: 'a + Write {
-    pub fn new(writer: &'a mut W) -> Self {
+impl<W> WriterGenerator<W> where W: Write {
+    /// Create a new `WriterGenerator` writing into the stream.
+    pub fn new(writer: W) -> Self {
+        WriterGenerator::with_escape(writer, EscapeConfig::new())
+    }
+
+    /// Create a new `WriterGenerator` writing into the stream, escaping
+    /// characters in strings as set in the `EscapeConfig`.
+    pub fn with_escape(writer: W, escape: EscapeConfig) -> Self {
         WriterGenerator {
-            writer: writer
+            writer: writer,
+            escape: escape,
         }
     }
+
+    /// Obtain the underlying stream back.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
 }
 
-impl<'a, W> Generator for WriterGenerator<'a, W> where W: Write {
+impl<W> Generator for WriterGenerator<W> where W: Write {
     type T = W;
 
     #[inline(always)]
@@ -306,30 +956,61 @@ impl<'a, W> Generator for WriterGenerator<'a, W> where W: Write {
     fn write_min(&mut self, _: &[u8], min: u8) -> io::Result<()> {
         self.writer.write_all(&[min])
     }
-}
 
+    #[inline(always)]
+    fn escape(&self) -> EscapeConfig {
+        self.escape
+    }
+}
 
-pub struct PrettyWriterGenerator<'a, W: 'a + Write> {
-    writer: &'a mut W,
+/// Generator of pretty printed JSON into a stream, as used by
+/// `JsonValue::write_pretty`.
+pub struct PrettyWriterGenerator<W: Write> {
+    writer: Column<W>,
     dent: u16,
-    spaces_per_indent: u16,
+    config: PrettyConfig,
 }
 
-impl<'a, W> PrettyWriterGenerator<'a, W> where W: 'a + Write {
-    pub fn new(writer: &'a mut W, spaces: u16) -> Self {
+impl<W> PrettyWriterGenerator<W> where W: Write {
+    /// Create a new `PrettyWriterGenerator` writing into the stream,
+    /// indenting with the given number of spaces.
+    pub fn new(writer: W, spaces: u16) -> Self {
+        PrettyWriterGenerator::with_config(writer, PrettyConfig::new().spaces(spaces))
+    }
+
+    /// Create a new `PrettyWriterGenerator` writing into the stream, laid
+    /// out as set in the `PrettyConfig`.
+    pub fn with_config(writer: W, config: PrettyConfig) -> Self {
         PrettyWriterGenerator {
-            writer: writer,
+            writer: Column {
+                writer: writer,
+                column: 0,
+            },
             dent: 0,
-            spaces_per_indent: spaces,
+            config: config,
+        }
+    }
+
+    /// Write the trailing new line, if there is to be one, once the whole
+    /// value has been written.
+    pub fn finish(&mut self) -> io::Result<()> {
+        if self.config.trailing_newline {
+            try!(self.writer.write_all(self.config.newline.as_bytes()));
         }
+        Ok(())
+    }
+
+    /// Obtain the underlying stream back.
+    pub fn into_inner(self) -> W {
+        self.writer.writer
     }
 }
 
-impl<'a, W> Generator for PrettyWriterGenerator<'a, W> where W: Write {
-    type T = W;
+impl<W> Generator for PrettyWriterGenerator<W> where W: Write {
+    type T = Column<W>;
 
     #[inline(always)]
-    fn get_writer(&mut self) -> &mut W {
+    fn get_writer(&mut self) -> &mut Column<W> {
         &mut self.writer
     }
 
@@ -338,10 +1019,27 @@ impl<'a, W> Generator for PrettyWriterGenerator<'a, W> where W: Write {
         self.writer.write_all(slice)
     }
 
+    fn write_colon(&mut self) -> io::Result<()> {
+        self.writer.write_all(self.config.colon.as_bytes())
+    }
+
+    fn write_json(&mut self, json: &JsonValue) -> io::Result<()> {
+        if self.config.fits(self.writer.column, json) {
+            return write_inline(self, json);
+        }
+
+        write_value(self, json)
+    }
+
+    #[inline(always)]
+    fn escape(&self) -> EscapeConfig {
+        self.config.escape
+    }
+
     fn new_line(&mut self) -> io::Result<()> {
-        try!(self.write_char(b'\n'));
-        for _ in 0..(self.dent * self.spaces_per_indent) {
-            try!(self.write_char(b' '));
+        try!(self.writer.write_all(self.config.newline.as_bytes()));
+        for _ in 0..self.dent {
+            try!(self.writer.write_all(self.config.indent.as_bytes()));
         }
         Ok(())
     }
@@ -355,6 +1053,207 @@ impl<'a, W> Generator for PrettyWriterGenerator<'a, W> where W: Write {
     }
 }
 
+/// Stream of a `PrettyWriterGenerator`, keeping track of how many bytes
+/// have been written since the last new line, to tell what fits on the
+/// current line.
+pub struct Column<W: Write> {
+    writer: W,
+    column: usize,
+}
+
+impl<W: Write> Write for Column<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = try!(self.writer.write(buf));
+
+        match buf[.. written].iter().rposition(|&ch| ch == b'\n') {
+            Some(newline) => self.column = written - newline - 1,
+            None          => self.column += written,
+        }
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+// Generator of the canonical form of RFC 8785, the JSON Canonicalization
+// Scheme: no whitespace, object keys sorted by their UTF-16 code units, and
+// numbers written the way ECMAScript does. Strings are escaped the same way
+// as by all other generators already, which is what RFC 8785 asks for.
+// Numbers that are not finite have no canonical form, writing them fails
+// with `io::ErrorKind::InvalidData`.
+pub(crate) struct CanonicalGenerator<W: Write> {
+    writer: W,
+}
+
+impl<W> CanonicalGenerator<W> where W: Write {
+    pub fn new(writer: W) -> Self {
+        CanonicalGenerator {
+            writer: writer
+        }
+    }
+}
+
+impl<W> Generator for CanonicalGenerator<W> where W: Write {
+    type T = W;
+
+    #[inline(always)]
+    fn get_writer(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    #[inline(always)]
+    fn write_min(&mut self, _: &[u8], min: u8) -> io::Result<()> {
+        self.writer.write_all(&[min])
+    }
+
+    fn write_number(&mut self, num: &Number) -> io::Result<()> {
+        write_ecmascript(&mut self.writer, to_f64(num))
+    }
+
+    fn write_raw_number(&mut self, raw: &str) -> io::Result<()> {
+        write_ecmascript(&mut self.writer, raw.parse().unwrap_or(f64::NAN))
+    }
+
+    fn write_object(&mut self, object: &Object) -> io::Result<()> {
+        let mut entries: Vec<_> = object.iter().collect();
+
+        entries.sort_by(|a, b| a.0.encode_utf16().cmp(b.0.encode_utf16()));
+
+        try!(self.begin_object());
+
+        for (index, (key, value)) in entries.into_iter().enumerate() {
+            if index != 0 {
+                try!(self.write_char(b','));
+            }
+
+            try!(self.write_entry(key, value));
+        }
+
+        self.end_object()
+    }
+}
+
+// Nearest `f64` to a `Number`. Converting the `Number` into `f64` directly
+// is not always correctly rounded, so anything but small integers goes
+// through the standard library parsing the number in its decimal form.
+fn to_f64(num: &Number) -> f64 {
+    if num.is_nan() {
+        return f64::NAN;
+    }
+
+    let (positive, mantissa, exponent) = num.as_parts();
+
+    if exponent == 0 && mantissa <= 1 << 53 {
+        let float = mantissa as f64;
+
+        return if positive { float } else { -float };
+    }
+
+    let mut decimal = Vec::with_capacity(32);
+
+    unsafe {
+        print_dec::write(&mut decimal, positive, mantissa, exponent).expect("Can't fail");
+    }
+
+    str::from_utf8(&decimal).ok().and_then(|decimal| decimal.parse().ok()).unwrap_or(f64::NAN)
+}
+
+// Write a number the way ECMAScript's `Number.prototype.toString` does,
+// with the shortest digits that read back as the same number, and of those
+// the closest to it. These are found by Grisu3, or when it gives up, taken
+// from the standard library, which then falls back to exact arithmetic.
+fn write_ecmascript<W: Write>(wr: &mut W, float: f64) -> io::Result<()> {
+    if !float.is_finite() {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Number is not finite"));
+    }
+
+    if float == 0.0 {
+        return wr.write_all(b"0");
+    }
+
+    if float < 0.0 {
+        try!(wr.write_all(b"-"));
+    }
+
+    let (mut mantissa, mut exponent) = match grisu3::convert(float.abs()) {
+        Some(digits) => digits,
+        None         => exact_digits(float.abs()),
+    };
+
+    while mantissa % 10 == 0 {
+        mantissa /= 10;
+        exponent += 1;
+    }
+
+    let mut digits = Vec::with_capacity(20);
+
+    unsafe {
+        try!(print_dec::write(&mut digits, true, mantissa, 0));
+    }
+
+    // Same as in the ECMAScript spec, the number is `0.digits` times 10 to
+    // the power of `n`
+    let k = digits.len() as i32;
+    let n = k + exponent as i32;
+
+    if k <= n && n <= 21 {
+        try!(wr.write_all(&digits));
+
+        for _ in k .. n {
+            try!(wr.write_all(b"0"));
+        }
+
+        Ok(())
+    } else if 0 < n && n <= 21 {
+        let (integer, fraction) = digits.split_at(n as usize);
+
+        try!(wr.write_all(integer));
+        try!(wr.write_all(b"."));
+        wr.write_all(fraction)
+    } else if -6 < n && n <= 0 {
+        try!(wr.write_all(b"0."));
+
+        for _ in n .. 0 {
+            try!(wr.write_all(b"0"));
+        }
+
+        wr.write_all(&digits)
+    } else {
+        let (first, rest) = digits.split_at(1);
+
+        try!(wr.write_all(first));
+
+        if !rest.is_empty() {
+            try!(wr.write_all(b"."));
+            try!(wr.write_all(rest));
+        }
+
+        write!(wr, "e{}{}", if n > 0 { "+" } else { "-" }, (n - 1).abs())
+    }
+}
+
+// Shortest digits of a positive number with the decimal exponent, from
+// its `1.2345e-7` form.
+fn exact_digits(float: f64) -> (u64, i16) {
+    let formatted = format!("{:e}", float);
+    let (digits, exponent) = formatted.split_at(formatted.find('e').expect("Formatted with an exponent"));
+    let exponent: i16 = exponent[1 ..].parse().expect("Exponent is a number");
+    let mut mantissa = 0;
+    let mut count = 0;
+
+    for &digit in digits.as_bytes() {
+        if digit != b'.' {
+            mantissa = mantissa * 10 + (digit - b'0') as u64;
+            count += 1;
+        }
+    }
+
+    (mantissa, exponent - count + 1)
+}
+
 // From: https://github.com/dtolnay/fastwrite/blob/master/src/lib.rs#L68
 //
 // LLVM is not able to lower `Vec::extend_from_slice` into a memcpy, so this
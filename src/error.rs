@@ -1,4 +1,5 @@
-use std::{ char, error, fmt };
+use std::{ char, error, fmt, io };
+use parser::Encoding;
 
 /// Error type of this crate.
 ///
@@ -14,7 +15,21 @@ pub enum Error {
     },
     UnexpectedEndOfJson,
     ExceededDepthLimit,
+    ExceededSizeLimit,
+    ExceededStringLengthLimit,
+    ExceededArrayLengthLimit,
+    ExceededObjectLengthLimit,
+    DuplicateKey {
+        key: String,
+        line: usize,
+        column: usize,
+    },
     FailedUtf8Parsing,
+    FailedDecoding(Encoding),
+    FailedReading {
+        kind: io::ErrorKind,
+        message: String,
+    },
     WrongType(String),
 }
 
@@ -35,10 +50,22 @@ impl fmt::Display for Error {
                 ref column,
             } => write!(f, "Unexpected character: {} at ({}:{})", ch, line, column),
 
-            UnexpectedEndOfJson   => write!(f, "Unexpected end of JSON"),
-            ExceededDepthLimit    => write!(f, "Exceeded depth limit"),
-            FailedUtf8Parsing     => write!(f, "Failed to parse UTF-8 bytes"),
-            WrongType(ref s)      => write!(f, "Wrong type, expected: {}", s),
+            UnexpectedEndOfJson       => write!(f, "Unexpected end of JSON"),
+            ExceededDepthLimit        => write!(f, "Exceeded depth limit"),
+            ExceededSizeLimit         => write!(f, "Exceeded size limit"),
+            ExceededStringLengthLimit => write!(f, "Exceeded string length limit"),
+            ExceededArrayLengthLimit  => write!(f, "Exceeded array length limit"),
+            ExceededObjectLengthLimit => write!(f, "Exceeded object length limit"),
+            DuplicateKey {
+                ref key,
+                ref line,
+                ref column,
+            } => write!(f, "Duplicate key: {:?} at ({}:{})", key, line, column),
+
+            FailedUtf8Parsing         => write!(f, "Failed to parse UTF-8 bytes"),
+            FailedDecoding(ref encoding) => write!(f, "Failed to decode the source as {}", encoding),
+            FailedReading { ref message, .. } => write!(f, "Failed to read the source: {}", message),
+            WrongType(ref s)          => write!(f, "Wrong type, expected: {}", s),
         }
     }
 }
@@ -51,8 +78,24 @@ impl error::Error for Error {
             UnexpectedCharacter { .. } => "Unexpected character",
             UnexpectedEndOfJson        => "Unexpected end of JSON",
             ExceededDepthLimit         => "Exceeded depth limit",
+            ExceededSizeLimit          => "Exceeded size limit",
+            ExceededStringLengthLimit  => "Exceeded string length limit",
+            ExceededArrayLengthLimit   => "Exceeded array length limit",
+            ExceededObjectLengthLimit  => "Exceeded object length limit",
+            DuplicateKey { .. }        => "Duplicate key",
             FailedUtf8Parsing          => "Failed to read bytes as UTF-8 from JSON",
+            FailedDecoding(_)          => "Failed to decode the source",
+            FailedReading { .. }       => "Failed to read the source",
             WrongType(_)               => "Wrong type",
         }
     }
 }
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::FailedReading {
+            kind: error.kind(),
+            message: error.to_string(),
+        }
+    }
+}
@@ -0,0 +1,212 @@
+// JSON text sequences
+// ===================
+//
+// RFC 7464, `application/json-seq`. Every record starts with the ASCII record
+// separator (RS, 0x1E) and ends with a line feed. Since a writer may have
+// died halfway through a record, the reader splits the stream on RS alone,
+// so a truncated record only costs us that record, and parsing carries on
+// with the next one.
+
+use std::io::{ self, BufRead, Write };
+use codegen::{ Generator, WriterGenerator };
+use parser::unexpected_at;
+use ndjson::read_until;
+use { JsonValue, ParserOptions, Error, Result };
+
+const RS: u8 = 0x1E;
+
+/// Iterator over values of a JSON text sequence (RFC 7464).
+///
+/// Records that fail to parse are reported as errors, after which the
+/// iterator moves on to the next record. As the RFC requires, top level
+/// numbers, booleans and nulls that aren't followed by whitespace are
+/// considered truncated, and reported as `Error::UnexpectedEndOfJson`.
+/// Empty records are skipped.
+///
+/// Failing to read from the stream is reported once, with
+/// `Error::FailedReading`, after which the iterator ends.
+///
+/// Records are read whole before being parsed, so for untrusted streams
+/// their length should be limited with `ParserOptions::size_limit`.
+/// Records any longer fail with `Error::ExceededSizeLimit`, without more
+/// than the limit being held in memory, and the rest of them is skipped.
+///
+/// ```
+/// use json::{ JsonSeqReader, Error };
+///
+/// let source = b"\x1e{\"a\":1}\n\x1e{\"trunc\x1e42\n\x1e42";
+/// let mut reader = JsonSeqReader::new(&source[..]);
+///
+/// assert_eq!(reader.next().unwrap().unwrap()["a"], 1);
+/// assert!(reader.next().unwrap().is_err());
+/// assert_eq!(reader.next().unwrap().unwrap(), 42);
+/// assert_eq!(reader.next().unwrap(), Err(Error::UnexpectedEndOfJson));
+/// assert!(reader.next().is_none());
+/// ```
+pub struct JsonSeqReader<R: BufRead> {
+    reader: R,
+
+    // Bytes of the current record
+    buffer: Vec<u8>,
+
+    // Whether we are past the first RS
+    started: bool,
+
+    // Set once reading has failed
+    done: bool,
+
+    options: ParserOptions,
+}
+
+impl<R: BufRead> JsonSeqReader<R> {
+    /// Create a new `JsonSeqReader` reading from a buffered stream.
+    pub fn new(reader: R) -> Self {
+        JsonSeqReader::with_options(reader, ParserOptions::new())
+    }
+
+    /// Create a new `JsonSeqReader` with custom `ParserOptions`, applied
+    /// to each record on its own.
+    pub fn with_options(reader: R, options: ParserOptions) -> Self {
+        JsonSeqReader {
+            reader: reader,
+            buffer: Vec::with_capacity(128),
+            started: false,
+            done: false,
+            options: options,
+        }
+    }
+
+    /// Obtain the underlying stream back.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn parse_record(&self, record: &[u8]) -> Result<JsonValue> {
+        let value = try!(self.options.parse_bytes(record));
+
+        let delimited = match value {
+            JsonValue::Number(_)    |
+            JsonValue::RawNumber(_) |
+            JsonValue::Boolean(_)   |
+            JsonValue::Null         => record.last().map_or(false, |&ch| is_whitespace(ch)),
+            _                       => true,
+        };
+
+        if delimited {
+            Ok(value)
+        } else {
+            Err(Error::UnexpectedEndOfJson)
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for JsonSeqReader<R> {
+    type Item = Result<JsonValue>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            self.buffer.clear();
+
+            let read = match read_until(&mut self.reader, RS, &mut self.buffer, self.options.size_limit) {
+                Ok(0)      => return None,
+                Ok(read)   => read,
+                Err(error) => {
+                    self.done = true;
+                    return Some(Err(error.into()));
+                }
+            };
+
+            let started = self.started;
+            self.started = true;
+
+            if read > self.buffer.len() {
+                return Some(Err(Error::ExceededSizeLimit));
+            }
+
+            let record = match self.buffer.last() {
+                Some(&RS) => &self.buffer[.. self.buffer.len() - 1],
+                _         => &self.buffer[..],
+            };
+
+            if record.iter().all(|&ch| is_whitespace(ch)) {
+                continue;
+            }
+
+            // Anything before the first RS is not a part of any record.
+            if !started {
+                let at = record.iter().position(|&ch| !is_whitespace(ch)).unwrap_or(0);
+
+                return Some(Err(unexpected_at(record, at)));
+            }
+
+            return Some(self.parse_record(record));
+        }
+
+        None
+    }
+}
+
+/// Writer of JSON text sequences (RFC 7464), putting each value in its
+/// compact form between the record separator and a line feed.
+///
+/// ```
+/// use json::JsonSeqWriter;
+///
+/// let mut writer = JsonSeqWriter::new(Vec::new());
+///
+/// writer.write(&"foo".into()).unwrap();
+/// writer.write(&42.into()).unwrap();
+///
+/// assert_eq!(writer.into_inner(), b"\x1e\"foo\"\n\x1e42\n");
+/// ```
+pub struct JsonSeqWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> JsonSeqWriter<W> {
+    /// Create a new `JsonSeqWriter` writing into the stream.
+    pub fn new(writer: W) -> Self {
+        JsonSeqWriter {
+            writer: writer,
+        }
+    }
+
+    /// Write a value as a single record.
+    pub fn write(&mut self, value: &JsonValue) -> io::Result<()> {
+        try!(self.writer.write_all(&[RS]));
+
+        {
+            let mut gen = WriterGenerator::new(&mut self.writer);
+            try!(gen.write_json(value));
+        }
+
+        self.writer.write_all(b"\n")
+    }
+
+    /// Write every value of an iterator, each as its own record.
+    pub fn write_all<'a, I>(&mut self, values: I) -> io::Result<()>
+    where I: IntoIterator<Item = &'a JsonValue> {
+        for value in values {
+            try!(self.write(value));
+        }
+
+        Ok(())
+    }
+
+    /// Flush the underlying stream.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Obtain the underlying stream back.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+fn is_whitespace(ch: u8) -> bool {
+    match ch {
+        9 ... 13 | 32 => true,
+        _             => false,
+    }
+}
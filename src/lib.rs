@@ -203,13 +203,19 @@ mod parser;
 mod value;
 mod error;
 mod util;
+mod ndjson;
+mod json_seq;
+mod writer;
 
 pub mod short;
 pub mod object;
 pub mod number;
+pub mod wtf8;
 
 pub use error::Error;
 pub use value::JsonValue;
+pub use codegen::{ Generator, DumpGenerator, PrettyGenerator, WriterGenerator, PrettyWriterGenerator, Column, PrettyConfig, EscapeConfig };
+pub use value::{ BorrowedValue, BorrowedObject, LazyDocument, LazyValue };
 pub use value::JsonValue::Null;
 
 /// Result type used by this crate.
@@ -231,6 +237,18 @@ pub mod iterators {
 
     /// Mutable iterator over key value pairs of `JsonValue::Object`.
     pub type EntriesMut<'a> = super::object::IterMut<'a>;
+
+    /// Iterator over members of `BorrowedValue::Array`.
+    pub type BorrowedMembers<'b, 'a> = ::std::slice::Iter<'b, super::BorrowedValue<'a>>;
+
+    /// Iterator over key value pairs of `BorrowedValue::Object`.
+    pub type BorrowedEntries<'b, 'a> = super::value::BorrowedEntries<'b, 'a>;
+
+    /// Iterator over members of an array `LazyValue`.
+    pub type LazyMembers<'t, 'a> = super::value::LazyMembers<'t, 'a>;
+
+    /// Iterator over key value pairs of an object `LazyValue`.
+    pub type LazyEntries<'t, 'a> = super::value::LazyEntries<'t, 'a>;
 }
 
 #[deprecated(since="0.9.0", note="use `json::Error` instead")]
@@ -239,7 +257,11 @@ pub use Error as JsonError;
 #[deprecated(since="0.9.0", note="use `json::Result` instead")]
 pub use Result as JsonResult;
 
-pub use parser::parse;
+pub use parser::{ parse, parse_bytes, parse_reader, parse_many, parse_with_spans, parse_recovering, parse_borrowed, parse_into, parse_lazy, extract, parse_parallel, parse_ndjson_parallel, ParserOptions, DuplicateKeys, LoneSurrogates, PushParser, EventReader, Event, ParseMany, Spans, Span, Position, Diagnostic, DiagnosticKind, Recycler, Encoding };
+
+pub use ndjson::{ NdjsonReader, NdjsonWriter };
+pub use json_seq::{ JsonSeqReader, JsonSeqWriter };
+pub use writer::JsonWriter;
 
 pub type Array = Vec<JsonValue>;
 
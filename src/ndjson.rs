@@ -0,0 +1,211 @@
+// Newline delimited JSON
+// ======================
+//
+// Also known as JSON Lines. Every line of the stream is a complete JSON
+// value, which makes it easy to append to and to process one record at
+// a time. Values are parsed with the regular parser, and written out with
+// the regular `WriterGenerator`, which never produces a raw newline, so
+// there isn't much to it.
+
+use std::io::{ self, BufRead, Write };
+use codegen::{ Generator, WriterGenerator };
+use { JsonValue, ParserOptions, Error, Result };
+
+/// Iterator over values of a newline delimited JSON stream, yielding
+/// each value along with the number of the line it was on, starting at 1.
+/// Blank lines are skipped.
+///
+/// An invalid line only fails to parse itself, the iterator keeps going
+/// with the next one. Failing to read from the stream is reported once,
+/// with `Error::FailedReading`, after which the iterator ends.
+///
+/// Lines are read whole before being parsed, so for untrusted streams
+/// the length of a line should be limited with `ParserOptions::size_limit`.
+/// Lines any longer fail with `Error::ExceededSizeLimit`, without more
+/// than the limit being held in memory, and the rest of them is skipped.
+///
+/// ```
+/// use json::NdjsonReader;
+///
+/// let source = "{\"id\":1}\n\n{\"id\":\n{\"id\":3}\n";
+/// let mut reader = NdjsonReader::new(source.as_bytes());
+///
+/// let (line, value) = reader.next().unwrap();
+/// assert_eq!((line, value.unwrap()["id"].as_u32()), (1, Some(1)));
+///
+/// let (line, value) = reader.next().unwrap();
+/// assert_eq!(line, 3);
+/// assert!(value.is_err());
+///
+/// let (line, value) = reader.next().unwrap();
+/// assert_eq!((line, value.unwrap()["id"].as_u32()), (4, Some(3)));
+///
+/// assert!(reader.next().is_none());
+/// ```
+pub struct NdjsonReader<R: BufRead> {
+    reader: R,
+
+    // Bytes of the current line
+    buffer: Vec<u8>,
+
+    // Number of the current line
+    line: usize,
+
+    // Set once reading has failed
+    done: bool,
+
+    options: ParserOptions,
+}
+
+impl<R: BufRead> NdjsonReader<R> {
+    /// Create a new `NdjsonReader` reading from a buffered stream.
+    pub fn new(reader: R) -> Self {
+        NdjsonReader::with_options(reader, ParserOptions::new())
+    }
+
+    /// Create a new `NdjsonReader` with custom `ParserOptions`, applied
+    /// to each line on its own.
+    pub fn with_options(reader: R, options: ParserOptions) -> Self {
+        NdjsonReader {
+            reader: reader,
+            buffer: Vec::with_capacity(128),
+            line: 0,
+            done: false,
+            options: options,
+        }
+    }
+
+    /// Obtain the underlying stream back.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: BufRead> Iterator for NdjsonReader<R> {
+    type Item = (usize, Result<JsonValue>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            self.buffer.clear();
+            self.line += 1;
+
+            match read_until(&mut self.reader, b'\n', &mut self.buffer, self.options.size_limit) {
+                Ok(0)     => return None,
+                Ok(read) if read > self.buffer.len() => {
+                    return Some((self.line, Err(Error::ExceededSizeLimit)));
+                },
+                Ok(_)     => {},
+                Err(error) => {
+                    self.done = true;
+                    return Some((self.line, Err(error.into())));
+                }
+            }
+
+            let blank = self.buffer.iter().all(|&ch| match ch {
+                9 ... 13 | 32 => true,
+                _             => false,
+            });
+
+            if !blank {
+                return Some((self.line, self.options.parse_bytes(&self.buffer)));
+            }
+        }
+
+        None
+    }
+}
+
+// Read up to and including the delimiter into the buffer, same as
+// `BufRead::read_until` does, except that no more than `limit` bytes are
+// put into the buffer. Returns the number of bytes read, which is larger
+// than the length of the buffer if the read was cut short.
+pub(crate) fn read_until<R: BufRead>(reader: &mut R, delimiter: u8, buffer: &mut Vec<u8>, limit: usize) -> io::Result<usize> {
+    let mut read = 0;
+
+    loop {
+        let (done, used) = {
+            let available = match reader.fill_buf() {
+                Ok(available) => available,
+                Err(ref error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error),
+            };
+
+            let (done, used) = match available.iter().position(|&ch| ch == delimiter) {
+                Some(end) => (true, end + 1),
+                None      => (available.is_empty(), available.len()),
+            };
+
+            if read == buffer.len() && buffer.len() + used <= limit {
+                buffer.extend_from_slice(&available[.. used]);
+            }
+
+            (done, used)
+        };
+
+        reader.consume(used);
+        read += used;
+
+        if done {
+            return Ok(read);
+        }
+    }
+}
+
+/// Writer of newline delimited JSON, putting each value on its own line
+/// in its compact form.
+///
+/// ```
+/// # #[macro_use] extern crate json;
+/// # fn main() {
+/// use json::NdjsonWriter;
+///
+/// let mut writer = NdjsonWriter::new(Vec::new());
+///
+/// writer.write(&object!{ "id" => 1 }).unwrap();
+/// writer.write(&array!["multi\nline"]).unwrap();
+///
+/// assert_eq!(writer.into_inner(), b"{\"id\":1}\n[\"multi\\nline\"]\n");
+/// # }
+/// ```
+pub struct NdjsonWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> NdjsonWriter<W> {
+    /// Create a new `NdjsonWriter` writing into the stream.
+    pub fn new(writer: W) -> Self {
+        NdjsonWriter {
+            writer: writer,
+        }
+    }
+
+    /// Write a value, followed by a newline.
+    pub fn write(&mut self, value: &JsonValue) -> io::Result<()> {
+        {
+            let mut gen = WriterGenerator::new(&mut self.writer);
+            try!(gen.write_json(value));
+        }
+
+        self.writer.write_all(b"\n")
+    }
+
+    /// Write every value of an iterator, each on its own line.
+    pub fn write_all<'a, I>(&mut self, values: I) -> io::Result<()>
+    where I: IntoIterator<Item = &'a JsonValue> {
+        for value in values {
+            try!(self.write(value));
+        }
+
+        Ok(())
+    }
+
+    /// Flush the underlying stream.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Obtain the underlying stream back.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
@@ -479,6 +479,104 @@ impl ops::Neg for Number {
     }
 }
 
+/// Number kept in its original lexical form, see
+/// `ParserOptions::lossless_numbers`, along with the `Number` it stands
+/// for. The text is always a valid JSON number, so that it can be written
+/// out as it is, and raw numbers compare equal to other numbers of the
+/// same value, whichever way they were written.
+///
+/// `RawNumber` owns its text, while the one of a `BorrowedValue` is a
+/// `RawNumber<&str>` borrowing it from the source.
+///
+/// ```
+/// # use json::number::RawNumber;
+/// let raw = RawNumber::new("1.50").unwrap();
+///
+/// assert_eq!(raw.as_str(), "1.50");
+/// assert_eq!(raw.as_number(), 1.5);
+/// assert_eq!(raw, RawNumber::new("15e-1").unwrap());
+///
+/// assert!(RawNumber::new("abc").is_none());
+/// assert!(RawNumber::new(" 1").is_none());
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct RawNumber<S = String> {
+    raw: S,
+    number: Number,
+}
+
+impl<S: AsRef<str>> RawNumber<S> {
+    /// Create a `RawNumber` from its text, provided it is a valid JSON
+    /// number without any whitespace around it.
+    pub fn new(raw: S) -> Option<Self> {
+        let number = {
+            let text = raw.as_ref();
+
+            if text.trim().len() != text.len() {
+                return None;
+            }
+
+            match ::parse(text) {
+                Ok(::JsonValue::Number(number)) => number,
+                _                               => return None,
+            }
+        };
+
+        Some(RawNumber {
+            raw: raw,
+            number: number,
+        })
+    }
+
+    // The parser has already checked the text, and knows the number.
+    #[inline]
+    pub(crate) fn from_parts(raw: S, number: Number) -> Self {
+        RawNumber {
+            raw: raw,
+            number: number,
+        }
+    }
+
+    /// Obtain the original lexical form of the number.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        self.raw.as_ref()
+    }
+
+    /// Obtain the number, with the same loss of precision as regular
+    /// parsing would cause.
+    #[inline]
+    pub fn as_number(&self) -> Number {
+        self.number
+    }
+
+    /// Obtain the text of the number back.
+    #[inline]
+    pub fn into_inner(self) -> S {
+        self.raw
+    }
+}
+
+impl<S: AsRef<str>, T: AsRef<str>> PartialEq<RawNumber<T>> for RawNumber<S> {
+    #[inline]
+    fn eq(&self, other: &RawNumber<T>) -> bool {
+        self.number == other.number
+    }
+}
+
+impl<S: AsRef<str>> PartialEq<Number> for RawNumber<S> {
+    #[inline]
+    fn eq(&self, other: &Number) -> bool {
+        self.number == *other
+    }
+}
+
+impl<S: AsRef<str>> fmt::Display for RawNumber<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.raw.as_ref())
+    }
+}
+
 // Commented out for now - not doing math ops for 0.10.0
 // -----------------------------------------------------
 //
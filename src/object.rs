@@ -298,13 +298,20 @@ impl Object {
     /// `Object` will handle the heap allocation of the key if needed for
     /// better performance.
     pub fn insert(&mut self, key: &str, value: JsonValue) {
+        self.insert_index(key, value);
+    }
+
+    // Same as `insert`, returning the index of the node holding the value,
+    // so the parser can put the value in place once it's been read.
+    #[inline(always)]
+    pub(crate) fn insert_index(&mut self, key: &str, value: JsonValue) -> usize {
         let key = key.as_bytes();
         let hash = hash_key(key);
 
         if self.store.len() == 0 {
             self.store.push(Node::new(value, hash, key.len()));
             self.store[0].key.attach(key);
-            return;
+            return 0;
         }
 
         let mut node = unsafe { &mut *self.node_at_index_mut(0) };
@@ -313,27 +320,62 @@ impl Object {
         loop {
             if hash == node.key.hash && key == node.key.as_bytes() {
                 node.value = value;
-                return;
+
+                // `parent` is the index of `node` until we descend further
+                return parent;
             } else if hash < node.key.hash {
                 if node.left != 0 {
                     parent = node.left;
                     node = unsafe { &mut *self.node_at_index_mut(node.left) };
                     continue;
                 }
-                self.store[parent].left = self.add_node(key, value, hash);
-                return;
+                let index = self.add_node(key, value, hash);
+                self.store[parent].left = index;
+                return index;
             } else {
                 if node.right != 0 {
                     parent = node.right;
                     node = unsafe { &mut *self.node_at_index_mut(node.right) };
                     continue;
                 }
-                self.store[parent].right = self.add_node(key, value, hash);
-                return;
+                let index = self.add_node(key, value, hash);
+                self.store[parent].right = index;
+                return index;
+            }
+        }
+    }
+
+    // Index of the node holding the value for `key`, if there is one.
+    pub(crate) fn index_of(&self, key: &str) -> Option<usize> {
+        if self.store.len() == 0 {
+            return None;
+        }
+
+        let key = key.as_bytes();
+        let hash = hash_key(key);
+
+        let mut index = 0;
+
+        loop {
+            let node = unsafe { self.store.get_unchecked(index) };
+
+            if hash == node.key.hash && key == node.key.as_bytes() {
+                return Some(index);
+            }
+
+            index = if hash < node.key.hash { node.left } else { node.right };
+
+            if index == 0 {
+                return None;
             }
         }
     }
 
+    #[inline]
+    pub(crate) fn value_at_mut(&mut self, index: usize) -> &mut JsonValue {
+        &mut self.store[index].value
+    }
+
     #[inline]
     pub fn override_last(&mut self, value: JsonValue) {
         if let Some(node) = self.store.last_mut() {
@@ -0,0 +1,122 @@
+// Borrowed values
+// ===============
+//
+// Building a `BorrowedValue` instead of a `JsonValue` with the very same
+// `build_value`. The only real difference is in strings, which are sliced
+// out of the source whenever they have no escapes in them, so they can
+// keep the lifetime of the source.
+
+use std::borrow::Cow;
+use number::{ Number, RawNumber };
+use value::{ BorrowedValue, BorrowedObject };
+use super::{ Node, Entries, Builder, Text };
+
+// Builder of borrowed values, there is nothing to hold on to.
+pub(super) struct Borrowing;
+
+impl<'a> Builder<'a> for Borrowing {
+    type Value = BorrowedValue<'a>;
+
+    #[inline]
+    fn string<'b>(&mut self, text: Text<'a, 'b>) -> BorrowedValue<'a> {
+        BorrowedValue::String(cow(text))
+    }
+
+    #[inline]
+    fn raw_number(&mut self, raw: &'a str, num: Number) -> BorrowedValue<'a> {
+        BorrowedValue::RawNumber(RawNumber::from_parts(raw, num))
+    }
+
+    #[inline]
+    fn array(&mut self) -> Vec<BorrowedValue<'a>> {
+        Vec::with_capacity(2)
+    }
+
+    #[inline]
+    fn object(&mut self) -> BorrowedObject<'a> {
+        BorrowedObject::with_capacity(3)
+    }
+}
+
+impl<'a> Node<'a> for BorrowedValue<'a> {
+    type Object = BorrowedObject<'a>;
+
+    #[inline]
+    fn null() -> Self {
+        BorrowedValue::Null
+    }
+
+    #[inline]
+    fn boolean(value: bool) -> Self {
+        BorrowedValue::Boolean(value)
+    }
+
+    #[inline]
+    fn number(num: Number) -> Self {
+        BorrowedValue::Number(num)
+    }
+
+    #[inline]
+    fn array(array: Vec<Self>) -> Self {
+        BorrowedValue::Array(array)
+    }
+
+    #[inline]
+    fn object(object: BorrowedObject<'a>) -> Self {
+        BorrowedValue::Object(object)
+    }
+
+    #[inline]
+    fn as_array_mut(&mut self) -> Option<&mut Vec<Self>> {
+        match *self {
+            BorrowedValue::Array(ref mut array) => Some(array),
+            _                                   => None,
+        }
+    }
+}
+
+impl<'a> Entries<'a> for BorrowedObject<'a> {
+    type Value = BorrowedValue<'a>;
+
+    #[inline]
+    fn new() -> Self {
+        BorrowedObject::new()
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        BorrowedObject::len(self)
+    }
+
+    #[inline]
+    fn index_of(&self, key: &str) -> Option<usize> {
+        BorrowedObject::index_of(self, key)
+    }
+
+    #[inline]
+    fn value_at_mut(&mut self, index: usize) -> &mut BorrowedValue<'a> {
+        BorrowedObject::value_at_mut(self, index)
+    }
+
+    #[inline]
+    fn insert_null<'b>(&mut self, key: Text<'a, 'b>) -> usize {
+        match BorrowedObject::index_of(self, key.as_str()) {
+            Some(index) => {
+                *BorrowedObject::value_at_mut(self, index) = BorrowedValue::Null;
+                index
+            },
+            None => self.push(cow(key), BorrowedValue::Null),
+        }
+    }
+}
+
+// Strings with escapes have to be copied out of the buffer. Lone surrogates
+// can't be borrowed as they are, they stay replaced by U+FFFD.
+#[inline]
+fn cow<'a, 'b>(text: Text<'a, 'b>) -> Cow<'a, str> {
+    match text {
+        Text::Borrowed(text)  => Cow::Borrowed(text),
+        Text::Transient(text) => Cow::Owned(text.into()),
+        Text::Wtf8(text, _)   => Cow::Owned(text.into()),
+    }
+}
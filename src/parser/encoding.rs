@@ -0,0 +1,233 @@
+// Encodings
+// =========
+//
+// JSON is meant to be UTF-8, yet plenty of tools on Windows write it out
+// in UTF-16, with a byte order mark in front. RFC 4627 tells how to detect
+// the encoding of a JSON text without a byte order mark: the first two
+// characters are always ASCII, so the pattern of zero bytes among the first
+// four gives it away. Anything other than UTF-8 is transcoded into UTF-8
+// before it reaches the parser.
+
+use std::borrow::Cow;
+use std::{ char, cmp, fmt };
+use { Error, Result };
+
+/// Encoding of a JSON source, as detected when the
+/// `ParserOptions::detect_encoding` option is on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Utf32Le,
+    Utf32Be,
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Encoding::Utf8    => "UTF-8",
+            Encoding::Utf16Le => "UTF-16LE",
+            Encoding::Utf16Be => "UTF-16BE",
+            Encoding::Utf32Le => "UTF-32LE",
+            Encoding::Utf32Be => "UTF-32BE",
+        })
+    }
+}
+
+// Detect the encoding from up to the first four bytes of the source, along
+// with the length of the byte order mark, if there is one.
+fn detect(bytes: &[u8]) -> (Encoding, usize) {
+    // Longer marks first, the one of UTF-32LE starts with that of UTF-16LE
+    let marks: [(&[u8], Encoding); 5] = [
+        (b"\xEF\xBB\xBF", Encoding::Utf8),
+        (b"\x00\x00\xFE\xFF", Encoding::Utf32Be),
+        (b"\xFF\xFE\x00\x00", Encoding::Utf32Le),
+        (b"\xFE\xFF", Encoding::Utf16Be),
+        (b"\xFF\xFE", Encoding::Utf16Le),
+    ];
+
+    for &(mark, encoding) in marks.iter() {
+        if bytes.starts_with(mark) {
+            return (encoding, mark.len());
+        }
+    }
+
+    let zero = |index: usize| bytes.get(index) == Some(&0);
+
+    let encoding = if bytes.len() >= 4 && zero(0) && zero(1) && zero(2) {
+        Encoding::Utf32Be
+    } else if zero(1) && zero(2) && zero(3) {
+        Encoding::Utf32Le
+    } else if bytes.len() >= 2 && zero(0) {
+        Encoding::Utf16Be
+    } else if zero(1) {
+        Encoding::Utf16Le
+    } else {
+        Encoding::Utf8
+    };
+
+    (encoding, 0)
+}
+
+// Transcode a whole source into UTF-8, borrowing it if it already is.
+pub fn transcode(bytes: &[u8]) -> Result<Cow<[u8]>> {
+    let (encoding, bom) = detect(&bytes[.. cmp::min(bytes.len(), 4)]);
+
+    if encoding == Encoding::Utf8 {
+        return Ok(Cow::Borrowed(&bytes[bom ..]));
+    }
+
+    let mut transcoder = Transcoder::new();
+    let mut decoded = Vec::with_capacity(bytes.len());
+
+    try!(transcoder.feed(bytes, &mut decoded));
+    try!(transcoder.finish(&mut decoded));
+
+    Ok(Cow::Owned(decoded))
+}
+
+// Transcoder for sources that come in chunks, see `PushParser`.
+pub struct Transcoder {
+    // Not known until the first four bytes are in
+    encoding: Option<Encoding>,
+
+    // Bytes that can't be decoded just yet, the start of the source while
+    // the encoding is being detected, or a partial code unit
+    pending: Vec<u8>,
+
+    // High surrogate waiting for its pair, UTF-16 only
+    high: Option<u32>,
+}
+
+impl Transcoder {
+    pub fn new() -> Self {
+        Transcoder {
+            encoding: None,
+            pending: Vec::with_capacity(4),
+            high: None,
+        }
+    }
+
+    pub fn feed(&mut self, mut chunk: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        if self.encoding.is_none() {
+            let take = cmp::min(4 - self.pending.len(), chunk.len());
+
+            self.pending.extend_from_slice(&chunk[.. take]);
+            chunk = &chunk[take ..];
+
+            if self.pending.len() < 4 {
+                return Ok(());
+            }
+
+            try!(self.start(out));
+        }
+
+        self.decode(chunk, out)
+    }
+
+    pub fn finish(&mut self, out: &mut Vec<u8>) -> Result<()> {
+        if self.encoding.is_none() {
+            try!(self.start(out));
+        }
+
+        if !self.pending.is_empty() || self.high.is_some() {
+            return Err(Error::FailedDecoding(self.encoding.unwrap_or(Encoding::Utf8)));
+        }
+
+        Ok(())
+    }
+
+    fn start(&mut self, out: &mut Vec<u8>) -> Result<()> {
+        let (encoding, bom) = detect(&self.pending);
+        let head: Vec<u8> = self.pending.drain(..).skip(bom).collect();
+
+        self.encoding = Some(encoding);
+
+        self.decode(&head, out)
+    }
+
+    fn decode(&mut self, mut chunk: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        let encoding = match self.encoding {
+            Some(Encoding::Utf8) | None => {
+                out.extend_from_slice(chunk);
+                return Ok(());
+            },
+            Some(encoding) => encoding,
+        };
+
+        let size = size_of(encoding);
+
+        // Complete the code unit split between chunks first
+        if !self.pending.is_empty() {
+            let take = cmp::min(size - self.pending.len(), chunk.len());
+
+            self.pending.extend_from_slice(&chunk[.. take]);
+            chunk = &chunk[take ..];
+
+            if self.pending.len() < size {
+                return Ok(());
+            }
+
+            let unit = unit(encoding, &self.pending);
+
+            self.pending.clear();
+
+            try!(self.push(encoding, unit, out));
+        }
+
+        let whole = chunk.len() - chunk.len() % size;
+
+        for bytes in chunk[.. whole].chunks(size) {
+            try!(self.push(encoding, unit(encoding, bytes), out));
+        }
+
+        self.pending.extend_from_slice(&chunk[whole ..]);
+
+        Ok(())
+    }
+
+    fn push(&mut self, encoding: Encoding, unit: u32, out: &mut Vec<u8>) -> Result<()> {
+        let codepoint = match (self.high.take(), unit) {
+            (Some(high), 0xDC00 ... 0xDFFF) if size_of(encoding) == 2 => {
+                ((high - 0xD800) << 10 | unit - 0xDC00) + 0x10000
+            },
+            (Some(_), _) => return Err(Error::FailedDecoding(encoding)),
+            (None, 0xD800 ... 0xDBFF) if size_of(encoding) == 2 => {
+                self.high = Some(unit);
+                return Ok(());
+            },
+            (None, unit) => unit,
+        };
+
+        match char::from_u32(codepoint) {
+            Some(ch) => {
+                let mut buf = [0; 4];
+
+                out.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+
+                Ok(())
+            },
+            None => Err(Error::FailedDecoding(encoding)),
+        }
+    }
+}
+
+fn size_of(encoding: Encoding) -> usize {
+    match encoding {
+        Encoding::Utf8                        => 1,
+        Encoding::Utf16Le | Encoding::Utf16Be => 2,
+        Encoding::Utf32Le | Encoding::Utf32Be => 4,
+    }
+}
+
+// Read a single code unit.
+fn unit(encoding: Encoding, bytes: &[u8]) -> u32 {
+    match encoding {
+        Encoding::Utf16Le => (bytes[0] as u32) | (bytes[1] as u32) << 8,
+        Encoding::Utf16Be => (bytes[0] as u32) << 8 | (bytes[1] as u32),
+        Encoding::Utf32Le => (bytes[0] as u32) | (bytes[1] as u32) << 8 | (bytes[2] as u32) << 16 | (bytes[3] as u32) << 24,
+        Encoding::Utf32Be => (bytes[0] as u32) << 24 | (bytes[1] as u32) << 16 | (bytes[2] as u32) << 8 | (bytes[3] as u32),
+        Encoding::Utf8    => bytes[0] as u32,
+    }
+}
@@ -0,0 +1,284 @@
+// Extraction
+// ==========
+//
+// Pull a handful of values out of a document, addressed by JSON Pointers,
+// without building the rest of it. Only the arrays and objects leading to
+// one of the pointers are walked through, the values at the pointers are
+// parsed as usual, and everything else is stepped over by counting
+// brackets and skipping strings, never allocating or decoding anything.
+
+use std::{ slice, str };
+use { JsonValue, Error, Result };
+use super::{ Parser, ParserOptions };
+
+// An array or object on the way to some of the pointers: the pointers that
+// go through it, and the index of the current member, arrays only.
+struct Frame {
+    live: Vec<usize>,
+    object: bool,
+    index: usize,
+}
+
+// Split a pointer into its reference tokens, unescaping `~1` and `~0` as
+// RFC 6901 says. Pointers that are not valid give `None`.
+fn split_pointer(pointer: &str) -> Option<Vec<String>> {
+    if pointer.is_empty() {
+        return Some(Vec::new());
+    }
+
+    if !pointer.starts_with('/') {
+        return None;
+    }
+
+    pointer[1 ..].split('/').map(|token| {
+        if !token.contains('~') {
+            return Some(token.into());
+        }
+
+        let mut unescaped = String::with_capacity(token.len());
+        let mut chars = token.chars();
+
+        while let Some(ch) = chars.next() {
+            match ch {
+                '~' => match chars.next() {
+                    Some('0') => unescaped.push('~'),
+                    Some('1') => unescaped.push('/'),
+                    _         => return None,
+                },
+                ch  => unescaped.push(ch),
+            }
+        }
+
+        Some(unescaped)
+    }).collect()
+}
+
+// Array index a reference token stands for, if any. Leading zeros are not
+// allowed, and `-`, standing for the member past the end, is never there.
+fn array_index(token: &str) -> Option<usize> {
+    let bytes = token.as_bytes();
+
+    if bytes.is_empty() || (bytes.len() > 1 && bytes[0] == b'0') {
+        return None;
+    }
+
+    if !bytes.iter().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+
+    token.parse().ok()
+}
+
+// Follow the rest of a pointer inside a value that has been parsed.
+fn lookup<'a>(mut value: &'a JsonValue, path: &[String]) -> Option<&'a JsonValue> {
+    for token in path {
+        value = match *value {
+            JsonValue::Object(ref object) => match object.get(token) {
+                Some(value) => value,
+                None        => return None,
+            },
+            JsonValue::Array(ref array) => match array_index(token).and_then(|index| array.get(index)) {
+                Some(value) => value,
+                None        => return None,
+            },
+            _ => return None,
+        };
+    }
+
+    Some(value)
+}
+
+impl<'a> Parser<'a> {
+    // Step over a string, the opening quote has been consumed already.
+    pub(super) fn skip_string(&mut self) -> Result<()> {
+        loop {
+            self.index += ::util::scan::string_run(&self.source[self.index ..]);
+
+            match expect_byte!(self) {
+                b'"'  => return Ok(()),
+                b'\\' => { expect_byte!(self); },
+                _     => return self.unexpected_character(),
+            }
+        }
+    }
+
+    // Step over a value, the first byte of which has been consumed already.
+    // Arrays and objects are only checked for balanced brackets, and
+    // numbers and literals for being made of the bytes they can be made of.
+    pub(super) fn skip_value(&mut self, ch: u8) -> Result<()> {
+        match ch {
+            b'"' => self.skip_string(),
+            b'[' | b'{' => {
+                let mut depth = 1;
+
+                loop {
+                    match expect_byte!(self) {
+                        b'"'        => try!(self.skip_string()),
+                        b'[' | b'{' => depth += 1,
+                        b']' | b'}' => {
+                            depth -= 1;
+
+                            if depth == 0 {
+                                return Ok(());
+                            }
+                        },
+                        _ => {},
+                    }
+                }
+            },
+            b'-' | b'0' ... b'9' | b't' | b'f' | b'n' => {
+                while !self.is_eof() {
+                    match self.read_byte() {
+                        b'a' ... b'z' | b'0' ... b'9' | b'-' | b'+' | b'.' | b'E' => self.bump(),
+                        _ => break,
+                    }
+                }
+
+                Ok(())
+            },
+            _ => self.unexpected_character(),
+        }
+    }
+}
+
+pub(super) fn extract(source: &str, pointers: &[&str], options: ParserOptions) -> Result<Vec<Option<JsonValue>>> {
+    let paths: Vec<_> = pointers.iter().map(|pointer| split_pointer(pointer)).collect();
+    let mut found = vec![None; pointers.len()];
+
+    let mut parser = Parser::new(source).with_options(options);
+    let mut stack: Vec<Frame> = Vec::new();
+
+    // Pointers going through the value about to be read
+    let mut live: Vec<usize> = (0 .. paths.len()).filter(|&index| paths[index].is_some()).collect();
+
+    if parser.length > options.size_limit {
+        return Err(Error::ExceededSizeLimit);
+    }
+
+    let mut ch = expect_byte_ignore_whitespace!(parser);
+
+    'value: loop {
+        let depth = stack.len();
+
+        // Should a key appear more than once, whatever was found under the
+        // previous value with that key is gone, same as with `json::parse`.
+        for &index in &live {
+            found[index] = None;
+        }
+
+        if live.iter().any(|&index| paths[index].as_ref().map_or(false, |path| path.len() == depth)) {
+            // Let the regular parser read the value from the start
+            parser.index -= 1;
+
+            let value = try!(parser.parse_value());
+
+            for &index in &live {
+                if let Some(ref path) = paths[index] {
+                    found[index] = lookup(&value, &path[depth ..]).cloned();
+                }
+            }
+        } else if !live.is_empty() && (ch == b'[' || ch == b'{') {
+            let object = ch == b'{';
+
+            ch = expect_byte_ignore_whitespace!(parser);
+
+            if ch != if object { b'}' } else { b']' } {
+                if depth >= parser.options.depth_limit {
+                    return Err(Error::ExceededDepthLimit);
+                }
+
+                stack.push(Frame {
+                    live: live,
+                    object: object,
+                    index: 0,
+                });
+
+                live = Vec::new();
+
+                if object {
+                    if ch != b'"' {
+                        return parser.unexpected_character();
+                    }
+
+                    let key = expect_string!(parser);
+
+                    select(&paths, &stack[depth].live, depth, |token| token == key, &mut live);
+
+                    expect!(parser, b':');
+                    ch = expect_byte_ignore_whitespace!(parser);
+                } else {
+                    select(&paths, &stack[depth].live, depth, |token| array_index(token) == Some(0), &mut live);
+                }
+
+                continue 'value;
+            }
+        } else {
+            try!(parser.skip_value(ch));
+        }
+
+        // A value has been read, close all the arrays and objects that end
+        // right after it.
+        loop {
+            let object = match stack.last() {
+                None        => {
+                    expect_eof!(parser);
+
+                    return Ok(found);
+                },
+                Some(frame) => frame.object,
+            };
+
+            ch = expect_byte_ignore_whitespace!(parser);
+
+            match ch {
+                b',' => {
+                    let depth = stack.len() - 1;
+                    let frame = &mut stack[depth];
+
+                    live.clear();
+                    ch = expect_byte_ignore_whitespace!(parser);
+
+                    if object {
+                        if ch != b'"' {
+                            return parser.unexpected_character();
+                        }
+
+                        let key = expect_string!(parser);
+
+                        select(&paths, &frame.live, depth, |token| token == key, &mut live);
+
+                        expect!(parser, b':');
+                        ch = expect_byte_ignore_whitespace!(parser);
+                    } else {
+                        frame.index += 1;
+
+                        let index = frame.index;
+
+                        select(&paths, &frame.live, depth, |token| array_index(token) == Some(index), &mut live);
+                    }
+
+                    continue 'value;
+                },
+                b']' if !object => {},
+                b'}' if object  => {},
+                _ => return parser.unexpected_character(),
+            }
+
+            stack.pop();
+        }
+    }
+}
+
+// Pick the pointers going through the member at `depth`, out of those going
+// through its array or object.
+fn select<F>(paths: &[Option<Vec<String>>], parent: &[usize], depth: usize, matches: F, live: &mut Vec<usize>)
+    where F: Fn(&str) -> bool
+{
+    for &index in parent {
+        if let Some(ref path) = paths[index] {
+            if path.len() > depth && matches(&path[depth]) {
+                live.push(index);
+            }
+        }
+    }
+}
@@ -0,0 +1,228 @@
+// Lazy documents
+// ==============
+//
+// Check that the source is valid JSON without building any values, only
+// noting down where each value starts and ends on a tape, see `LazyValue`.
+// Strings are checked with the same macro the regular parser uses, which
+// only copies them into the reusable buffer if they have escapes, while
+// numbers are merely checked to be well formed.
+
+use std::{ slice, str, u32 };
+use value::{ LazyDocument, Token };
+use { Error, Result };
+use super::{ Parser, ParserOptions };
+
+// An array or object being read: position of its token on the tape, and
+// how many members, or keys, it has so far.
+struct Frame {
+    token: usize,
+    count: usize,
+    object: bool,
+}
+
+impl<'a> Parser<'a> {
+    fn push_token(&self, tape: &mut Vec<Token>, start: usize) {
+        tape.push(Token {
+            start: start as u32,
+            len: (self.index - start) as u32,
+            extent: 1,
+        });
+    }
+
+    // Read the key at the current position, along with the colon after it.
+    fn read_lazy_key(&mut self, tape: &mut Vec<Token>, ch: u8) -> Result<()> {
+        if ch != b'"' {
+            return self.unexpected_character();
+        }
+
+        let start = self.index - 1;
+
+        expect_string!(self);
+
+        self.push_token(tape, start);
+
+        expect!(self, b':');
+
+        Ok(())
+    }
+
+    // Step over a number, making sure it is well formed. The first byte
+    // has been consumed already.
+    fn skip_number(&mut self, mut ch: u8) -> Result<()> {
+        if ch == b'-' {
+            ch = expect_byte!(self);
+        }
+
+        match ch {
+            b'0'          => {},
+            b'1' ... b'9' => self.skip_digits(),
+            _             => return self.unexpected_character(),
+        }
+
+        if !self.is_eof() && self.read_byte() == b'.' {
+            self.bump();
+
+            match expect_byte!(self) {
+                b'0' ... b'9' => self.skip_digits(),
+                _             => return self.unexpected_character(),
+            }
+        }
+
+        if !self.is_eof() && (self.read_byte() == b'e' || self.read_byte() == b'E') {
+            self.bump();
+
+            let mut ch = expect_byte!(self);
+
+            if ch == b'+' || ch == b'-' {
+                ch = expect_byte!(self);
+            }
+
+            match ch {
+                b'0' ... b'9' => self.skip_digits(),
+                _             => return self.unexpected_character(),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn skip_digits(&mut self) {
+        while !self.is_eof() {
+            match self.read_byte() {
+                b'0' ... b'9' => self.bump(),
+                _             => break,
+            }
+        }
+    }
+
+    // Record the tape of a single value. Of relaxed syntax only comments
+    // are supported here, being skipped along with whitespace.
+    fn parse_tape(&mut self, tape: &mut Vec<Token>) -> Result<()> {
+        if self.length > self.options.size_limit || self.length > u32::MAX as usize {
+            return Err(Error::ExceededSizeLimit);
+        }
+
+        let mut stack: Vec<Frame> = Vec::with_capacity(3);
+        let mut ch = expect_byte_ignore_whitespace!(self);
+
+        'parsing: loop {
+            let start = self.index - 1;
+
+            match ch {
+                b'[' | b'{' => {
+                    let object = ch == b'{';
+
+                    self.push_token(tape, start);
+
+                    ch = expect_byte_ignore_whitespace!(self);
+
+                    if ch != if object { b'}' } else { b']' } {
+                        if stack.len() >= self.options.depth_limit {
+                            return Err(Error::ExceededDepthLimit);
+                        }
+
+                        if object {
+                            if self.options.object_length_limit == 0 {
+                                return Err(Error::ExceededObjectLengthLimit);
+                            }
+
+                            try!(self.read_lazy_key(tape, ch));
+
+                            ch = expect_byte_ignore_whitespace!(self);
+                        } else if self.options.array_length_limit == 0 {
+                            return Err(Error::ExceededArrayLengthLimit);
+                        }
+
+                        stack.push(Frame {
+                            token: tape.len() - 1 - object as usize,
+                            count: 1,
+                            object: object,
+                        });
+
+                        continue 'parsing;
+                    }
+
+                    let token = tape.len() - 1;
+                    tape[token].len = (self.index - start) as u32;
+                },
+                b'"' => {
+                    expect_string!(self);
+                    self.push_token(tape, start);
+                },
+                b'-' | b'0' ... b'9' => {
+                    try!(self.skip_number(ch));
+                    self.push_token(tape, start);
+                },
+                b't' => {
+                    expect_sequence!(self, b'r', b'u', b'e');
+                    self.push_token(tape, start);
+                },
+                b'f' => {
+                    expect_sequence!(self, b'a', b'l', b's', b'e');
+                    self.push_token(tape, start);
+                },
+                b'n' => {
+                    expect_sequence!(self, b'u', b'l', b'l');
+                    self.push_token(tape, start);
+                },
+                _ => return self.unexpected_character(),
+            }
+
+            // A value has been read, close all the arrays and objects that
+            // end right after it.
+            loop {
+                let (object, count) = match stack.last() {
+                    None        => return Ok(()),
+                    Some(frame) => (frame.object, frame.count),
+                };
+
+                ch = expect_byte_ignore_whitespace!(self);
+
+                match ch {
+                    b',' => {
+                        ch = expect_byte_ignore_whitespace!(self);
+
+                        if object {
+                            if count >= self.options.object_length_limit {
+                                return Err(Error::ExceededObjectLengthLimit);
+                            }
+
+                            try!(self.read_lazy_key(tape, ch));
+
+                            ch = expect_byte_ignore_whitespace!(self);
+                        } else if count >= self.options.array_length_limit {
+                            return Err(Error::ExceededArrayLengthLimit);
+                        }
+
+                        if let Some(frame) = stack.last_mut() {
+                            frame.count += 1;
+                        }
+
+                        continue 'parsing;
+                    },
+                    b']' if !object => {},
+                    b'}' if object  => {},
+                    _ => return self.unexpected_character(),
+                }
+
+                let frame = stack.pop().expect("Containers are closed once");
+                let extent = tape.len() - frame.token;
+                let token = &mut tape[frame.token];
+
+                token.len = (self.index - token.start as usize) as u32;
+                token.extent = extent as u32;
+            }
+        }
+    }
+}
+
+pub fn parse_lazy(source: &str, options: ParserOptions) -> Result<LazyDocument> {
+    let mut parser = Parser::new(source).with_options(options);
+    let mut tape = Vec::new();
+
+    try!(parser.parse_tape(&mut tape));
+
+    expect_eof!(parser);
+
+    Ok(LazyDocument::new(source, options, tape))
+}
@@ -0,0 +1,77 @@
+// Multiple documents
+// ==================
+//
+// Some feeds just concatenate JSON values, with or without whitespace in
+// between. The regular parser insists on the end of the source after the
+// root value, so here we parse values one by one until we run out of
+// source, starting each one where the previous has ended.
+
+use { JsonValue, Result };
+use super::Parser;
+
+/// Iterator over JSON values following one another in a single source,
+/// created with `json::parse_many`. Yields each value along with the byte
+/// offset right past its end.
+///
+/// Values don't need to be separated by anything, unless they would run
+/// into each other, such as two numbers. Iteration stops at the first
+/// error.
+///
+/// ```
+/// let source = r#"{"a":1}{"b":2} [3]
+/// "four""#;
+///
+/// let values: Vec<_> = json::parse_many(source).map(Result::unwrap).collect();
+///
+/// assert_eq!(values.len(), 4);
+/// assert_eq!(values[1].0["b"], 2);
+/// assert_eq!(values[1].1, 14);
+/// assert_eq!(&source[values[3].1 - 6 .. values[3].1], "\"four\"");
+/// ```
+pub struct ParseMany<'a> {
+    parser: Parser<'a>,
+    done: bool,
+}
+
+impl<'a> ParseMany<'a> {
+    pub(super) fn new(parser: Parser<'a>) -> Self {
+        ParseMany {
+            parser: parser,
+            done: false,
+        }
+    }
+
+    fn parse_next(&mut self) -> Result<Option<(JsonValue, usize)>> {
+        try!(self.parser.skip_whitespace());
+
+        if self.parser.is_eof() {
+            return Ok(None);
+        }
+
+        let value = try!(self.parser.parse_value());
+
+        Ok(Some((value, self.parser.index)))
+    }
+}
+
+impl<'a> Iterator for ParseMany<'a> {
+    type Item = Result<(JsonValue, usize)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.parse_next() {
+            Ok(Some(next)) => Some(Ok(next)),
+            Ok(None)       => {
+                self.done = true;
+                None
+            },
+            Err(error)     => {
+                self.done = true;
+                Some(Err(error))
+            },
+        }
+    }
+}
@@ -0,0 +1,1823 @@
+// HERE BE DRAGONS!
+// ================
+//
+// Making a fast parser is hard. This is a _not so naive_ implementation of
+// recursive descent that does almost nothing. _There is no backtracking_, the
+// whole parsing is 100% predictive, even though it's not BNF, and will have
+// linear performance based on the length of the source!
+//
+// There is a lot of macros here! Like, woah! This is mostly due to the fact
+// that Rust isn't very cool about optimizing inlined functions that return
+// a `Result` type. Since different functions will have different `Result`
+// signatures, the `try!` macro will always have to repackage our results.
+// With macros those issues don't exist, the macro will return an unpackaged
+// result - whatever it is - and if we ever stumble upon the error, we can
+// return an `Err` without worrying about the exact signature of `Result`.
+//
+// This makes for some ugly code, but it is faster. Hopefully in the future
+// with MIR support the compiler will get smarter about this.
+
+use std::{ str, slice, char, cmp, mem, i16 };
+use std::io::{ self, Read };
+use object::Object;
+use number::{ Number, RawNumber, NAN };
+use util::scan;
+use wtf8::Wtf8String;
+use value::{ BorrowedValue, LazyDocument };
+use { JsonValue, Error, Result };
+
+mod push;
+mod options;
+mod spans;
+mod many;
+mod recover;
+mod recycler;
+mod encoding;
+
+pub use self::push::PushParser;
+pub use self::options::{ ParserOptions, DuplicateKeys, LoneSurrogates };
+pub use self::spans::{ Spans, Span, Position };
+pub use self::many::ParseMany;
+pub use self::recover::{ Diagnostic, DiagnosticKind };
+pub use self::recycler::Recycler;
+pub use self::encoding::Encoding;
+
+// This is not actual max precision, but a threshold at which number parsing
+// kicks into checked math.
+const MAX_PRECISION: u64 = 576460752303423500;
+
+
+// How many nested Objects/Arrays are allowed to be parsed by default
+const DEPTH_LIMIT: usize = 512;
+
+// How many threads parallel parsing uses by default
+const THREADS: usize = 4;
+
+
+// The `Parser` struct keeps track of indexing over our buffer. All niceness
+// has been abandoned in favor of raw pointer magic. Does that make you feel
+// dirty? _Good._
+struct Parser<'a> {
+    // Helper buffer for parsing strings that can't be just memcopied from
+    // the original source (escaped characters)
+    buffer: Vec<u8>,
+
+    // Offsets in the buffer above of lone surrogates kept in WTF-8, see
+    // `LoneSurrogates::Preserve`, and a copy of the buffer with them in
+    // place, once they are replaced in the buffer itself
+    surrogates: Vec<usize>,
+    wtf8: Vec<u8>,
+
+    // Bytes to parse
+    source: &'a [u8],
+
+    // Byte pointer to the slice above
+    byte_ptr: *const u8,
+
+    // Current index
+    index: usize,
+
+    // Length of the source
+    length: usize,
+
+    // Whether strings have to be checked for valid UTF-8, which is only
+    // the case if the source didn't come from a `&str`
+    validate_utf8: bool,
+
+    // Limits and such
+    options: ParserOptions,
+
+    // Keys with values collected into arrays, see `insert_key`
+    collected: Vec<(usize, usize)>,
+}
+
+
+// Read a byte from the source.
+// Will return an error if there are no more bytes.
+macro_rules! expect_byte {
+    ($parser:ident) => ({
+        if $parser.is_eof() {
+            return Err(Error::UnexpectedEndOfJson);
+        }
+
+        let ch = $parser.read_byte();
+        $parser.bump();
+        ch
+    })
+}
+
+
+// Expect a sequence of specific bytes in specific order, error otherwise.
+// This is useful for reading the 3 JSON identifiers:
+//
+// - "t" has to be followed by "rue"
+// - "f" has to be followed by "alse"
+// - "n" has to be followed by "ull"
+//
+// Anything else is an error.
+macro_rules! expect_sequence {
+    ($parser:ident, $( $ch:pat ),*) => {
+        $(
+            match expect_byte!($parser) {
+                $ch => {},
+                _   => return $parser.unexpected_character(),
+            }
+        )*
+    }
+}
+
+
+// A drop in macro for when we expect to read a byte, but we don't care
+// about any whitespace characters that might occur before it. In relaxed
+// mode comments are treated as whitespace as well.
+macro_rules! expect_byte_ignore_whitespace {
+    ($parser:ident) => ({
+        let mut ch = expect_byte!($parser);
+
+        // Don't go straight for the loop, assume we are in the clear first.
+        match ch {
+            // whitespace
+            9 ... 13 | 32 | b'/' => {
+                loop {
+                    match ch {
+                        9 ... 13 | 32 => {
+                            $parser.index += ::util::scan::whitespace_run(&$parser.source[$parser.index ..]);
+                        },
+                        b'/' if $parser.options.relaxed => try!($parser.skip_comment()),
+                        _ => break
+                    }
+                    ch = expect_byte!($parser);
+                }
+            },
+            _ => {}
+        }
+
+        ch
+    })
+}
+
+// Expect to find EOF or just whitespaces leading to EOF after a JSON value
+macro_rules! expect_eof {
+    ($parser:ident) => ({
+        while !$parser.is_eof() {
+            match $parser.read_byte() {
+                9 ... 13 | 32 => $parser.bump(),
+                b'/' if $parser.options.relaxed => {
+                    $parser.bump();
+                    try!($parser.skip_comment());
+                },
+                _             => {
+                    $parser.bump();
+                    return $parser.unexpected_character();
+                }
+            }
+        }
+    })
+}
+
+// Expect a particular byte to be next. Also available with a variant
+// creates a `match` expression just to ease some pain.
+macro_rules! expect {
+    ($parser:ident, $byte:expr) => ({
+        let ch = expect_byte_ignore_whitespace!($parser);
+
+        if ch != $byte {
+            return $parser.unexpected_character()
+        }
+    });
+
+    {$parser:ident $(, $byte:pat => $then:expr )*} => ({
+        let ch = expect_byte_ignore_whitespace!($parser);
+
+        match ch {
+            $(
+                $byte => $then,
+            )*
+            _ => return $parser.unexpected_character()
+        }
+
+    })
+}
+
+
+// Look up table that marks which characters are allowed in their raw
+// form in a string.
+const QU: bool = false;  // double quote       0x22
+const BS: bool = false;  // backslash          0x5C
+const CT: bool = false;  // control character  0x00 ... 0x1F
+const __: bool = true;
+
+static ALLOWED: [bool; 256] = [
+// 0   1   2   3   4   5   6   7   8   9   A   B   C   D   E   F
+  CT, CT, CT, CT, CT, CT, CT, CT, CT, CT, CT, CT, CT, CT, CT, CT, // 0
+  CT, CT, CT, CT, CT, CT, CT, CT, CT, CT, CT, CT, CT, CT, CT, CT, // 1
+  __, __, QU, __, __, __, __, __, __, __, __, __, __, __, __, __, // 2
+  __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, // 3
+  __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, // 4
+  __, __, __, __, __, __, __, __, __, __, __, __, BS, __, __, __, // 5
+  __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, // 6
+  __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, // 7
+  __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, // 8
+  __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, // 9
+  __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, // A
+  __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, // B
+  __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, // C
+  __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, // D
+  __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, // E
+  __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, // F
+];
+
+
+// Expect a string. This is called after encountering, and consuming, a
+// double quote character. This macro has a happy path variant where it
+// does almost nothing as long as all characters are allowed (as described
+// in the look up table above), skipping over them in bulk. If it encounters
+// a closing quote without any escapes, it will use a slice straight from
+// the source, avoiding unnecessary buffering.
+macro_rules! expect_string {
+    ($parser:ident) => ({
+        let result: &str;
+        let start = $parser.index;
+
+        loop {
+            $parser.index += ::util::scan::string_run(&$parser.source[$parser.index ..]);
+
+            let ch = expect_byte!($parser);
+            if ch == b'"' {
+                unsafe {
+                    let ptr = $parser.byte_ptr.offset(start as isize);
+                    let len = $parser.index - 1 - start;
+                    let bytes = slice::from_raw_parts(ptr, len);
+
+                    if $parser.validate_utf8 && str::from_utf8(bytes).is_err() {
+                        return Err(Error::FailedUtf8Parsing);
+                    }
+
+                    result = str::from_utf8_unchecked(bytes);
+                }
+                break;
+            }
+            if ch == b'\\' {
+                result = try!($parser.read_complex_string(start));
+                break;
+            }
+
+            return $parser.unexpected_character();
+        }
+
+        if result.len() > $parser.options.string_length_limit {
+            return Err(Error::ExceededStringLengthLimit);
+        }
+
+        result
+    })
+}
+
+
+// Expect an object key, `ch` being the first byte of it. Keys have to be
+// strings, unless we are in relaxed mode.
+macro_rules! expect_key {
+    ($parser:ident, $ch:ident) => ({
+        if $ch == b'"' {
+            expect_string!($parser)
+        } else {
+            try!($parser.read_relaxed_key($ch))
+        }
+    })
+}
+
+
+// Expect a number. Of some kind.
+macro_rules! expect_number {
+    ($parser:ident, $first:ident) => ({
+        let mut num = ($first - b'0') as u64;
+
+        let result: Number;
+
+        // Cap on how many iterations we do while reading to u64
+        // in order to avoid an overflow.
+        loop {
+            if num >= MAX_PRECISION {
+                result = try!($parser.read_big_number(num));
+                break;
+            }
+
+            if $parser.is_eof() {
+                result = num.into();
+                break;
+            }
+
+            let ch = $parser.read_byte();
+
+            match ch {
+                b'0' ... b'9' => {
+                    $parser.bump();
+                    num = num * 10 + (ch - b'0') as u64;
+                },
+                _             => {
+                    let mut e = 0;
+                    result = allow_number_extensions!($parser, num, e, ch);
+                    break;
+                }
+            }
+        }
+
+        result
+    })
+}
+
+
+// Invoked after parsing an integer, this will account for fractions and/or
+// `e` notation.
+macro_rules! allow_number_extensions {
+    ($parser:ident, $num:ident, $e:ident, $ch:ident) => ({
+        match $ch {
+            b'.'        => {
+                $parser.bump();
+                expect_fraction!($parser, $num, $e)
+            },
+            b'e' | b'E' => {
+                $parser.bump();
+                try!($parser.expect_exponent($num, $e))
+            },
+            _  => $num.into()
+        }
+    });
+
+    // Alternative variant that defaults everything to 0. This is actually
+    // quite handy as the only number that can begin with zero, has to have
+    // a zero mantissa. Leading zeroes are illegal in JSON!
+    ($parser:ident) => ({
+        if $parser.is_eof() {
+            0.into()
+        } else {
+            let mut num = 0;
+            let mut e = 0;
+            let ch = $parser.read_byte();
+            allow_number_extensions!($parser, num, e, ch)
+        }
+    })
+}
+
+
+// If a dot `b"."` byte has been read, start reading the decimal fraction
+// of the number.
+macro_rules! expect_fraction {
+    ($parser:ident, $num:ident, $e:ident) => ({
+        let result: Number;
+
+        // Relaxed mode allows for a trailing decimal point, so the fraction
+        // can be empty.
+        if !$parser.options.relaxed {
+            let ch = expect_byte!($parser);
+
+            match ch {
+                b'0' ... b'9' => {
+                    if $num < MAX_PRECISION {
+                        $num = $num * 10 + (ch - b'0') as u64;
+                        $e -= 1;
+                    } else {
+                        match $num.checked_mul(10).and_then(|num| {
+                            num.checked_add((ch - b'0') as u64)
+                        }) {
+                            Some(result) => {
+                                $num = result;
+                                $e -= 1;
+                            },
+                            None => {}
+                        }
+                    }
+                },
+                _ => return $parser.unexpected_character()
+            }
+        }
+
+        loop {
+            if $parser.is_eof() {
+                result = unsafe { Number::from_parts_unchecked(true, $num, $e) };
+                break;
+            }
+            let ch = $parser.read_byte();
+
+            match ch {
+                b'0' ... b'9' => {
+                    $parser.bump();
+                    if $num < MAX_PRECISION {
+                        $num = $num * 10 + (ch - b'0') as u64;
+                        $e -= 1;
+                    } else {
+                        match $num.checked_mul(10).and_then(|num| {
+                            num.checked_add((ch - b'0') as u64)
+                        }) {
+                            Some(result) => {
+                                $num = result;
+                                $e -= 1;
+                            },
+                            None => {}
+                        }
+                    }
+                },
+                b'e' | b'E' => {
+                    $parser.bump();
+                    result = try!($parser.expect_exponent($num, $e));
+                    break;
+                }
+                _ => {
+                    result = unsafe { Number::from_parts_unchecked(true, $num, $e) };
+                    break;
+                }
+            }
+        }
+
+        result
+    })
+}
+
+// The event reader relies on the macros above, so it has to be declared
+// after them.
+mod reader;
+
+pub use self::reader::{ EventReader, Event };
+
+// So does the parser of borrowed values.
+mod borrowed;
+
+// And the one of lazy documents.
+mod lazy;
+
+// And extraction by JSON Pointer.
+mod extract;
+
+// And parallel parsing.
+mod parallel;
+
+impl<'a> Parser<'a> {
+    pub fn new(source: &'a str) -> Self {
+        let mut parser = Parser::from_bytes(source.as_bytes());
+        parser.validate_utf8 = false;
+        parser
+    }
+
+    // Parser for bytes that haven't been checked to be valid UTF-8. Only
+    // the contents of strings are checked, as any other byte outside of
+    // the ASCII range is an error anyway.
+    pub fn from_bytes(source: &'a [u8]) -> Self {
+        Parser::with_buffer(source, Vec::with_capacity(30))
+    }
+
+    // Same as `from_bytes`, with the buffer for strings with escapes taken
+    // over from another parser, so that it doesn't have to be allocated
+    // anew.
+    fn with_buffer(source: &'a [u8], buffer: Vec<u8>) -> Self {
+        Parser {
+            buffer: buffer,
+            surrogates: Vec::new(),
+            wtf8: Vec::new(),
+            source: source,
+            byte_ptr: source.as_ptr(),
+            index: 0,
+            length: source.len(),
+            validate_utf8: true,
+            options: ParserOptions::new(),
+            collected: Vec::new(),
+        }
+    }
+
+    pub fn with_options(mut self, options: ParserOptions) -> Self {
+        self.options = options;
+
+        if options.detect_encoding && self.index == 0 && self.source.starts_with(b"\xEF\xBB\xBF") {
+            self.index = 3;
+        }
+
+        self
+    }
+
+    // Check if we are at the end of the source.
+    #[inline(always)]
+    fn is_eof(&mut self) -> bool {
+        self.index == self.length
+    }
+
+    // Read a byte from the source. Note that this does not increment
+    // the index. In few cases (all of them related to number parsing)
+    // we want to peek at the byte before doing anything. This will,
+    // very very rarely, lead to a situation where the same byte is read
+    // twice, but since this operation is using a raw pointer, the cost
+    // is virtually irrelevant.
+    #[inline(always)]
+    fn read_byte(&mut self) -> u8 {
+        debug_assert!(self.index < self.length, "Reading out of bounds");
+
+        unsafe { *self.byte_ptr.offset(self.index as isize) }
+    }
+
+    // Manually increment the index. Calling `read_byte` and then `bump`
+    // is equivalent to consuming a byte on an iterator.
+    #[inline(always)]
+    fn bump(&mut self) {
+        self.index = self.index.wrapping_add(1);
+    }
+
+    // So we got an unexpected character, now what? Well, figure out where
+    // it is, and throw an error!
+    fn unexpected_character<T: Sized>(&mut self) -> Result<T> {
+        Err(unexpected_at(self.source, self.index - 1))
+    }
+
+    fn duplicate_key<T: Sized>(&self, key: &str, at: usize) -> Result<T> {
+        let (line, column) = self.position(at);
+
+        Err(Error::DuplicateKey {
+            key: key.into(),
+            line: line,
+            column: column,
+        })
+    }
+
+    // Line and column of the byte at `at`, both starting at 1.
+    fn position(&self, at: usize) -> (usize, usize) {
+        position(self.source, at)
+    }
+
+    // Boring
+    fn read_hexdec_digit(&mut self) -> Result<u32> {
+        let ch = expect_byte!(self);
+        Ok(match ch {
+            b'0' ... b'9' => (ch - b'0'),
+            b'a' ... b'f' => (ch + 10 - b'a'),
+            b'A' ... b'F' => (ch + 10 - b'A'),
+            _             => return self.unexpected_character(),
+        } as u32)
+    }
+
+    // Boring
+    fn read_hexdec_codepoint(&mut self) -> Result<u32> {
+        Ok(
+            try!(self.read_hexdec_digit()) << 12 |
+            try!(self.read_hexdec_digit()) << 8  |
+            try!(self.read_hexdec_digit()) << 4  |
+            try!(self.read_hexdec_digit())
+        )
+    }
+
+    // Oh look, some action. This method reads an escaped unicode
+    // sequence such as `\uDEAD` from the string. Except `DEAD` is
+    // not a valid codepoint, so it also needs to handle errors...
+    fn read_codepoint(&mut self) -> Result<()> {
+        let mut codepoint = try!(self.read_hexdec_codepoint());
+
+        match codepoint {
+            0x0000 ... 0xD7FF => {},
+            0xD800 ... 0xDBFF if self.options.lone_surrogates == LoneSurrogates::Error => {
+                codepoint -= 0xD800;
+                codepoint <<= 10;
+
+                expect_sequence!(self, b'\\', b'u');
+
+                let lower = try!(self.read_hexdec_codepoint());
+
+                if let 0xDC00 ... 0xDFFF = lower {
+                    codepoint = (codepoint | lower - 0xDC00) + 0x010000;
+                } else {
+                    return Err(Error::FailedUtf8Parsing)
+                }
+            },
+            0xD800 ... 0xDBFF => {
+                let at = self.index;
+
+                // Only take the next escape if it completes the pair,
+                // otherwise leave it to be read on its own.
+                if self.source[at ..].starts_with(b"\\u") {
+                    self.index += 2;
+
+                    let lower = try!(self.read_hexdec_codepoint());
+
+                    if let 0xDC00 ... 0xDFFF = lower {
+                        codepoint = ((codepoint - 0xD800) << 10 | lower - 0xDC00) + 0x010000;
+                    } else {
+                        self.index = at;
+                        codepoint = try!(self.lone_surrogate(codepoint));
+                    }
+                } else {
+                    codepoint = try!(self.lone_surrogate(codepoint));
+                }
+            },
+            0xDC00 ... 0xDFFF => codepoint = try!(self.lone_surrogate(codepoint)),
+            0xE000 ... 0xFFFF => {},
+            _ => return Err(Error::FailedUtf8Parsing)
+        }
+
+        // Only a preserved lone surrogate can still be one at this point
+        if let 0xD800 ... 0xDFFF = codepoint {
+            self.surrogates.push(self.buffer.len());
+        }
+
+        match codepoint {
+            0x0000 ... 0x007F => self.buffer.push(codepoint as u8),
+            0x0080 ... 0x07FF => self.buffer.extend_from_slice(&[
+                (((codepoint >> 6) as u8) & 0x1F) | 0xC0,
+                ((codepoint        as u8) & 0x3F) | 0x80
+            ]),
+            0x0800 ... 0xFFFF => self.buffer.extend_from_slice(&[
+                (((codepoint >> 12) as u8) & 0x0F) | 0xE0,
+                (((codepoint >> 6)  as u8) & 0x3F) | 0x80,
+                ((codepoint         as u8) & 0x3F) | 0x80
+            ]),
+            0x10000 ... 0x10FFFF => self.buffer.extend_from_slice(&[
+                (((codepoint >> 18) as u8) & 0x07) | 0xF0,
+                (((codepoint >> 12) as u8) & 0x3F) | 0x80,
+                (((codepoint >> 6)  as u8) & 0x3F) | 0x80,
+                ((codepoint         as u8) & 0x3F) | 0x80
+            ]),
+            _ => return Err(Error::FailedUtf8Parsing)
+        }
+
+        Ok(())
+    }
+
+    // Code point to use in place of a surrogate that is not part of a pair,
+    // see `LoneSurrogates`.
+    fn lone_surrogate(&self, codepoint: u32) -> Result<u32> {
+        match self.options.lone_surrogates {
+            LoneSurrogates::Error    => Err(Error::FailedUtf8Parsing),
+            LoneSurrogates::Replace  => Ok(0xFFFD),
+            LoneSurrogates::Preserve => Ok(codepoint),
+        }
+    }
+
+    // What's so complex about strings you may ask? Not that much really.
+    // This method is called if the `expect_string!` macro encounters an
+    // escape. The added complexity is that it will have to use an internal
+    // buffer to read all the escaped characters into, before finally
+    // producing a usable slice. What it means it that parsing "foo\bar"
+    // is whole lot slower than parsing "foobar", as the former suffers from
+    // having to be read from source to a buffer and then from a buffer to
+    // our target string. Nothing to be done about this, really.
+    fn read_complex_string<'b>(&mut self, start: usize) -> Result<&'b str> {
+        try!(self.read_escaped_string(start));
+
+        self.buffered_str()
+    }
+
+    // Read a string with escapes into the buffer, see `read_complex_string`.
+    fn read_escaped_string(&mut self, start: usize) -> Result<()> {
+        self.buffer.clear();
+        self.surrogates.clear();
+        let mut ch = b'\\';
+
+        // TODO: Use fastwrite here as well
+        self.buffer.extend_from_slice(&self.source[start .. self.index - 1]);
+
+        loop {
+            if ALLOWED[ch as usize] {
+                let run = scan::string_run(&self.source[self.index ..]);
+
+                self.buffer.push(ch);
+                self.buffer.extend_from_slice(&self.source[self.index .. self.index + run]);
+                self.index += run;
+
+                ch = expect_byte!(self);
+                continue;
+            }
+            match ch {
+                b'"'  => break,
+                b'\\' => try!(self.read_escape()),
+                _     => return self.unexpected_character()
+            }
+            ch = expect_byte!(self);
+        }
+
+        Ok(())
+    }
+
+    // Read an escape sequence into the buffer, after the backslash.
+    #[inline]
+    fn read_escape(&mut self) -> Result<()> {
+        let escaped = expect_byte!(self);
+        let escaped = match escaped {
+            b'u'  => return self.read_codepoint(),
+            b'"'  |
+            b'\\' |
+            b'/'  => escaped,
+            b'\'' if self.options.relaxed => escaped,
+            b'b'  => 0x8,
+            b'f'  => 0xC,
+            b't'  => b'\t',
+            b'r'  => b'\r',
+            b'n'  => b'\n',
+            _     => return self.unexpected_character()
+        };
+        self.buffer.push(escaped);
+
+        Ok(())
+    }
+
+    // Produce a string slice out of the buffer, once a string has been
+    // read into it.
+    fn buffered_str<'b>(&mut self) -> Result<&'b str> {
+        // Lone surrogates can't be in a `str`, U+FFFD has the same length
+        for &at in &self.surrogates {
+            self.buffer[at .. at + 3].copy_from_slice("\u{FFFD}".as_bytes());
+        }
+
+        self.surrogates.clear();
+
+        if self.validate_utf8 && str::from_utf8(&self.buffer).is_err() {
+            return Err(Error::FailedUtf8Parsing);
+        }
+
+        // Since the original source is already valid UTF-8, and `\`
+        // cannot occur in front of a codepoint > 127, this is safe.
+        Ok(unsafe {
+            str::from_utf8_unchecked(
+                // Because the buffer is stored on the parser, returning it
+                // as a slice here freaks out the borrow checker. The compiler
+                // can't know that the buffer isn't used till the result
+                // of this function is long used and irrelevant. To avoid
+                // issues here, we construct a new slice from raw parts, which
+                // then has lifetime bound to the outer function scope instead
+                // of the parser itself.
+                slice::from_raw_parts(self.buffer.as_ptr(), self.buffer.len())
+            )
+        })
+    }
+
+    // Same as `buffered_str`, except lone surrogates are also kept as
+    // WTF-8 if there are any.
+    fn buffered_text<'b>(&mut self) -> Result<Text<'a, 'b>> {
+        if self.surrogates.is_empty() {
+            return Ok(Text::Transient(try!(self.buffered_str())));
+        }
+
+        self.wtf8.clear();
+        self.wtf8.extend_from_slice(&self.buffer);
+
+        let text = try!(self.buffered_str());
+
+        // Same as in `buffered_str`
+        Ok(Text::Wtf8(text, unsafe {
+            slice::from_raw_parts(self.wtf8.as_ptr(), self.wtf8.len())
+        }))
+    }
+
+    // Single quoted strings are only allowed in relaxed mode. They are rare
+    // enough to not bother with a happy path, everything goes through the
+    // buffer.
+    fn read_single_quoted_string<'b>(&mut self) -> Result<&'b str> {
+        try!(self.read_single_quoted());
+
+        self.buffered_str()
+    }
+
+    // Read a single quoted string into the buffer.
+    fn read_single_quoted(&mut self) -> Result<()> {
+        self.buffer.clear();
+        self.surrogates.clear();
+
+        loop {
+            let ch = expect_byte!(self);
+
+            match ch {
+                b'\''        => break,
+                b'\\'        => try!(self.read_escape()),
+                0 ... 0x1F   => return self.unexpected_character(),
+                _            => self.buffer.push(ch),
+            }
+        }
+
+        if self.buffer.len() > self.options.string_length_limit {
+            return Err(Error::ExceededStringLengthLimit);
+        }
+
+        Ok(())
+    }
+
+    // In relaxed mode object keys can also be single quoted strings, or
+    // unquoted identifiers. Only ASCII identifiers are supported.
+    fn read_relaxed_key<'b>(&mut self, ch: u8) -> Result<&'b str> {
+        if !self.options.relaxed {
+            return self.unexpected_character();
+        }
+
+        match ch {
+            b'\'' => self.read_single_quoted_string(),
+            b'a' ... b'z' | b'A' ... b'Z' | b'_' | b'$' => {
+                let start = self.index - 1;
+
+                while !self.is_eof() {
+                    match self.read_byte() {
+                        b'a' ... b'z' | b'A' ... b'Z' | b'0' ... b'9' | b'_' | b'$' => self.bump(),
+                        _ => break
+                    }
+                }
+
+                if self.index - start > self.options.string_length_limit {
+                    return Err(Error::ExceededStringLengthLimit);
+                }
+
+                // Identifiers are ASCII only, so this is safe.
+                Ok(unsafe {
+                    str::from_utf8_unchecked(slice::from_raw_parts(
+                        self.byte_ptr.offset(start as isize),
+                        self.index - start
+                    ))
+                })
+            },
+            _ => self.unexpected_character()
+        }
+    }
+
+    // Skip any whitespace, and comments in relaxed mode, without expecting
+    // anything to follow.
+    fn skip_whitespace(&mut self) -> Result<()> {
+        while !self.is_eof() {
+            match self.read_byte() {
+                9 ... 13 | 32 => self.index += scan::whitespace_run(&self.source[self.index ..]),
+                b'/' if self.options.relaxed => {
+                    self.bump();
+                    try!(self.skip_comment());
+                },
+                _ => break
+            }
+        }
+
+        Ok(())
+    }
+
+    // Skip a comment in relaxed mode, after the opening `/` has been read.
+    fn skip_comment(&mut self) -> Result<()> {
+        match expect_byte!(self) {
+            b'/' => {
+                while !self.is_eof() {
+                    let ch = self.read_byte();
+                    self.bump();
+
+                    if ch == b'\n' {
+                        break;
+                    }
+                }
+            },
+            b'*' => {
+                let mut star = false;
+
+                loop {
+                    let ch = expect_byte!(self);
+
+                    if star && ch == b'/' {
+                        break;
+                    }
+
+                    star = ch == b'*';
+                }
+            },
+            _ => return self.unexpected_character()
+        }
+
+        Ok(())
+    }
+
+    // Numbers in relaxed mode can also be hexadecimal, start or end with
+    // a decimal point, or be `Infinity` or `NaN`. The sign, if any, has
+    // already been read.
+    fn read_relaxed_number(&mut self, ch: u8) -> Result<Number> {
+        Ok(match ch {
+            b'0' => {
+                if !self.is_eof() && (self.read_byte() == b'x' || self.read_byte() == b'X') {
+                    self.bump();
+                    try!(self.read_hexadecimal())
+                } else {
+                    allow_number_extensions!(self)
+                }
+            },
+            b'1' ... b'9' => expect_number!(self, ch),
+            b'.' => {
+                // There has to be at least one digit after a leading point
+                match expect_byte!(self) {
+                    b'0' ... b'9' => self.index -= 1,
+                    _             => return self.unexpected_character()
+                }
+
+                let mut num = 0u64;
+                let mut e = 0i16;
+
+                expect_fraction!(self, num, e)
+            },
+            b'I' => {
+                expect_sequence!(self, b'n', b'f', b'i', b'n', b'i', b't', b'y');
+
+                // `Number` has no infinity, but an exponent this large
+                // converts to an infinite `f64`.
+                unsafe { Number::from_parts_unchecked(true, 1, i16::MAX) }
+            },
+            b'N' => {
+                expect_sequence!(self, b'a', b'N');
+                NAN
+            },
+            _ => return self.unexpected_character()
+        })
+    }
+
+    // Read digits of a hexadecimal number, after the `0x` prefix.
+    fn read_hexadecimal(&mut self) -> Result<Number> {
+        let mut num = try!(self.read_hexdec_digit()) as u64;
+
+        // Once the number doesn't fit `u64`, precision is lost anyway.
+        let mut float: Option<f64> = None;
+
+        while !self.is_eof() {
+            let digit = match self.read_byte() {
+                ch @ b'0' ... b'9' => ch - b'0',
+                ch @ b'a' ... b'f' => ch + 10 - b'a',
+                ch @ b'A' ... b'F' => ch + 10 - b'A',
+                _                  => break
+            } as u64;
+
+            self.bump();
+
+            float = match (float, num.checked_mul(16)) {
+                (None, Some(shifted)) => {
+                    num = shifted | digit;
+                    None
+                },
+                (None, None)          => Some(num as f64 * 16.0 + digit as f64),
+                (Some(float), _)      => Some(float * 16.0 + digit as f64),
+            };
+        }
+
+        Ok(match float {
+            Some(float) => float.into(),
+            None        => num.into(),
+        })
+    }
+
+    // Big numbers! If the `expect_number!` reaches a point where the decimal
+    // mantissa could have overflown the size of u64, it will switch to this
+    // control path instead. This method will pick up where the macro started,
+    // but instead of continuing to read into the mantissa, it will increment
+    // the exponent. Note that no digits are actually read here, as we already
+    // exceeded the precision range of f64 anyway.
+    fn read_big_number(&mut self, mut num: u64) -> Result<Number> {
+        let mut e = 0i16;
+        loop {
+            if self.is_eof() {
+                return Ok(unsafe { Number::from_parts_unchecked(true, num, e) });
+            }
+            let ch = self.read_byte();
+            match ch {
+                b'0' ... b'9' => {
+                    self.bump();
+                    match num.checked_mul(10).and_then(|num| {
+                        num.checked_add((ch - b'0') as u64)
+                    }) {
+                        Some(result) => num = result,
+                        None         => e += 1 ,
+                    }
+                },
+                b'.' => {
+                    self.bump();
+                    return Ok(expect_fraction!(self, num, e));
+                },
+                b'e' | b'E' => {
+                    self.bump();
+                    return self.expect_exponent(num, e);
+                }
+                _  => break
+            }
+        }
+
+        Ok(unsafe { Number::from_parts_unchecked(true, num, e) })
+    }
+
+    // Called in the rare case that a number with `e` notation has been
+    // encountered. This is pretty straight forward, I guess.
+    fn expect_exponent(&mut self, num: u64, big_e: i16) -> Result<Number> {
+        let mut ch = expect_byte!(self);
+        let sign = match ch {
+            b'-' => {
+                ch = expect_byte!(self);
+                -1
+            },
+            b'+' => {
+                ch = expect_byte!(self);
+                1
+            },
+            _    => 1
+        };
+
+        let mut e = match ch {
+            b'0' ... b'9' => (ch - b'0') as i16,
+            _ => return self.unexpected_character(),
+        };
+
+        loop {
+            if self.is_eof() {
+                break;
+            }
+            let ch = self.read_byte();
+            match ch {
+                b'0' ... b'9' => {
+                    self.bump();
+                    e = e.saturating_mul(10).saturating_add((ch - b'0') as i16);
+                },
+                _  => break
+            }
+        }
+
+        Ok(unsafe { Number::from_parts_unchecked(true, num, (big_e.saturating_add(e * sign))) })
+    }
+
+    // Same as `expect_string!`, except the result tells apart strings that
+    // are slices of the source, and so can be borrowed from it, from the
+    // ones that had escapes, which are slices of the buffer.
+    #[inline(always)]
+    fn read_text<'b>(&mut self) -> Result<Text<'a, 'b>> {
+        let source = self.source;
+        let start = self.index;
+
+        let result = loop {
+            self.index += scan::string_run(&source[self.index ..]);
+
+            match expect_byte!(self) {
+                b'"' => {
+                    let bytes = &source[start .. self.index - 1];
+
+                    if self.validate_utf8 && str::from_utf8(bytes).is_err() {
+                        return Err(Error::FailedUtf8Parsing);
+                    }
+
+                    break Text::Borrowed(unsafe { str::from_utf8_unchecked(bytes) });
+                },
+                b'\\' => {
+                    try!(self.read_escaped_string(start));
+                    break try!(self.buffered_text());
+                },
+                _     => return self.unexpected_character(),
+            }
+        };
+
+        if result.as_str().len() > self.options.string_length_limit {
+            return Err(Error::ExceededStringLengthLimit);
+        }
+
+        Ok(result)
+    }
+
+    // Same as `expect_key!`, see `read_text`.
+    #[inline(always)]
+    fn read_key<'b>(&mut self, ch: u8) -> Result<Text<'a, 'b>> {
+        if ch == b'"' {
+            self.read_text()
+        } else {
+            Ok(Text::Transient(try!(self.read_relaxed_key(ch))))
+        }
+    }
+
+    // Wrap up a number that started at `start`, keeping its lexical form
+    // if we are asked to.
+    #[inline(always)]
+    fn number_value<B: Builder<'a>>(&mut self, builder: &mut B, start: usize, num: Number) -> B::Value {
+        if self.options.lossless_numbers {
+            let source = self.source;
+            let raw = &source[start .. self.index];
+
+            // Relaxed numbers can't be written out as they are
+            if !self.options.relaxed || is_json_number(raw) {
+                // Numbers are ASCII only, so this is safe.
+                return builder.raw_number(unsafe { str::from_utf8_unchecked(raw) }, num);
+            }
+        }
+
+        Node::number(num)
+    }
+
+    // Parse away!
+    fn parse(&mut self) -> Result<JsonValue> {
+        self.parse_with(&mut Allocating)
+    }
+
+    // Parse the whole source into whatever kind of value the `builder`
+    // builds.
+    fn parse_with<B: Builder<'a>>(&mut self, builder: &mut B) -> Result<B::Value> {
+        let value = try!(self.build_value(builder));
+
+        expect_eof!(self);
+
+        Ok(value)
+    }
+
+    // Parse a single `JsonValue`, leaving whatever comes after it in the
+    // source alone.
+    #[inline(always)]
+    fn parse_value(&mut self) -> Result<JsonValue> {
+        self.build_value(&mut Allocating)
+    }
+
+    // Parse a single value, of whatever kind the `builder` builds.
+    #[inline(always)]
+    fn build_value<B: Builder<'a>>(&mut self, builder: &mut B) -> Result<B::Value> {
+        if self.length > self.options.size_limit {
+            return Err(Error::ExceededSizeLimit);
+        }
+
+        let mut stack = Vec::with_capacity(3);
+        let mut ch = expect_byte_ignore_whitespace!(self);
+
+        'parsing: loop {
+            // Position of `ch`, where a number would start
+            let start = self.index - 1;
+
+            let mut value: B::Value = match ch {
+                b'[' => {
+                    ch = expect_byte_ignore_whitespace!(self);
+
+                    if ch != b']' {
+                        if stack.len() >= self.options.depth_limit {
+                            return Err(Error::ExceededDepthLimit);
+                        }
+
+                        stack.push(StackBlock::Array(builder.array()));
+                        continue 'parsing;
+                    }
+
+                    Node::array(Vec::new())
+                },
+                b'{' => {
+                    ch = expect_byte_ignore_whitespace!(self);
+
+                    if ch != b'}' {
+                        if stack.len() >= self.options.depth_limit {
+                            return Err(Error::ExceededDepthLimit);
+                        }
+
+                        let mut object = builder.object();
+
+                        let key = try!(self.read_key(ch));
+
+                        if self.options.object_length_limit == 0 {
+                            return Err(Error::ExceededObjectLengthLimit);
+                        }
+
+                        let slot = Slot::Node(object.insert_null(key));
+                        expect!(self, b':');
+
+                        stack.push(StackBlock::Object(object, slot));
+
+                        ch = expect_byte_ignore_whitespace!(self);
+
+                        continue 'parsing;
+                    }
+
+                    Node::object(Entries::new())
+                },
+                b'"' => {
+                    let text = try!(self.read_text());
+                    builder.string(text)
+                },
+                b'0' if self.options.relaxed => {
+                    let num = try!(self.read_relaxed_number(ch));
+                    self.number_value(builder, start, num)
+                },
+                b'0' => {
+                    let num = allow_number_extensions!(self);
+                    self.number_value(builder, start, num)
+                },
+                b'1' ... b'9' => {
+                    let num = expect_number!(self, ch);
+                    self.number_value(builder, start, num)
+                },
+                b'-' => {
+                    let ch = expect_byte!(self);
+                    let num = - match ch {
+                        _ if self.options.relaxed => try!(self.read_relaxed_number(ch)),
+                        b'0' => allow_number_extensions!(self),
+                        b'1' ... b'9' => expect_number!(self, ch),
+                        _    => return self.unexpected_character()
+                    };
+                    self.number_value(builder, start, num)
+                },
+                b'+' if self.options.relaxed => {
+                    let ch = expect_byte!(self);
+                    Node::number(try!(self.read_relaxed_number(ch)))
+                },
+                b'.' | b'I' | b'N' if self.options.relaxed => {
+                    Node::number(try!(self.read_relaxed_number(ch)))
+                },
+                b'\'' if self.options.relaxed => {
+                    try!(self.read_single_quoted());
+                    builder.string(try!(self.buffered_text()))
+                },
+                b't' => {
+                    expect_sequence!(self, b'r', b'u', b'e');
+                    Node::boolean(true)
+                },
+                b'f' => {
+                    expect_sequence!(self, b'a', b'l', b's', b'e');
+                    Node::boolean(false)
+                },
+                b'n' => {
+                    expect_sequence!(self, b'u', b'l', b'l');
+                    Node::null()
+                },
+                _    => return self.unexpected_character()
+            };
+
+            'popping: loop {
+                match stack.pop() {
+                    None => return Ok(value),
+
+                    Some(StackBlock::Array(mut array)) => {
+                        if array.len() >= self.options.array_length_limit {
+                            return Err(Error::ExceededArrayLengthLimit);
+                        }
+
+                        array.push(value);
+
+                        ch = expect_byte_ignore_whitespace!(self);
+
+                        match ch {
+                            b',' => {
+                                ch = expect_byte_ignore_whitespace!(self);
+
+                                // Trailing comma in relaxed mode
+                                if ch == b']' && self.options.relaxed {
+                                    value = Node::array(array);
+                                    continue 'popping;
+                                }
+
+                                stack.push(StackBlock::Array(array));
+
+                                continue 'parsing;
+                            },
+                            b']' => {
+                                value = Node::array(array);
+                                continue 'popping;
+                            },
+                            _ => return self.unexpected_character()
+                        }
+                    },
+
+                    Some(StackBlock::Object(mut object, slot)) => {
+                        put_value(&mut object, slot, value);
+
+                        ch = expect_byte_ignore_whitespace!(self);
+
+                        match ch {
+                            b',' => {
+                                ch = expect_byte_ignore_whitespace!(self);
+
+                                // Trailing comma in relaxed mode
+                                if ch == b'}' && self.options.relaxed {
+                                    forget_collected(&mut self.collected, stack.len());
+                                    value = Node::object(object);
+                                    continue 'popping;
+                                }
+
+                                if object.len() >= self.options.object_length_limit {
+                                    return Err(Error::ExceededObjectLengthLimit);
+                                }
+
+                                let at = self.index - 1;
+                                let key = try!(self.read_key(ch));
+                                let slot = match insert_key(&self.options, &mut self.collected, &mut object, key, stack.len()) {
+                                    Some(slot) => slot,
+                                    None       => return self.duplicate_key(key.as_str(), at),
+                                };
+                                expect!(self, b':');
+
+                                stack.push(StackBlock::Object(object, slot));
+
+                                ch = expect_byte_ignore_whitespace!(self);
+
+                                continue 'parsing;
+                            },
+                            b'}' => {
+                                forget_collected(&mut self.collected, stack.len());
+                                value = Node::object(object);
+
+                                continue 'popping;
+                            },
+                            _ => return self.unexpected_character()
+                        }
+                    },
+                }
+            }
+        }
+    }
+}
+
+enum StackBlock<V = JsonValue, O = Object> {
+    Array(Vec<V>),
+    Object(O, Slot),
+}
+
+// A string read from the source.
+#[derive(Clone, Copy)]
+enum Text<'a, 'b> {
+    // Slice of the source, without escapes
+    Borrowed(&'a str),
+
+    // Slice of the buffer, or some other short lived string, that has to
+    // be copied to be kept
+    Transient(&'b str),
+
+    // String with lone surrogates, see `LoneSurrogates::Preserve`, with
+    // each replaced by U+FFFD, along with its WTF-8 bytes. Both are short
+    // lived, same as above.
+    Wtf8(&'b str, &'b [u8]),
+}
+
+impl<'a, 'b> Text<'a, 'b> {
+    fn as_str(&self) -> &str {
+        match *self {
+            Text::Borrowed(text)  => text,
+            Text::Transient(text) => text,
+            Text::Wtf8(text, _)   => text,
+        }
+    }
+}
+
+// Values built by `build_value`, that is `JsonValue` and `BorrowedValue`.
+trait Node<'a>: Sized {
+    type Object: Entries<'a, Value = Self>;
+
+    fn null() -> Self;
+    fn boolean(value: bool) -> Self;
+    fn number(num: Number) -> Self;
+    fn array(array: Vec<Self>) -> Self;
+    fn object(object: Self::Object) -> Self;
+    fn as_array_mut(&mut self) -> Option<&mut Vec<Self>>;
+}
+
+// Objects of the values above.
+trait Entries<'a>: Sized {
+    type Value: Node<'a, Object = Self>;
+
+    fn new() -> Self;
+    fn len(&self) -> usize;
+    fn index_of(&self, key: &str) -> Option<usize>;
+    fn value_at_mut(&mut self, index: usize) -> &mut Self::Value;
+
+    // Add a key with a `null` value, or if the key is already there, set
+    // its value to `null`. Returns the index of the key either way.
+    fn insert_null<'b>(&mut self, key: Text<'a, 'b>) -> usize;
+}
+
+// Where `build_value` gets its strings, numbers and empty containers from.
+trait Builder<'a> {
+    type Value: Node<'a>;
+
+    fn string<'b>(&mut self, text: Text<'a, 'b>) -> Self::Value;
+    fn raw_number(&mut self, raw: &'a str, num: Number) -> Self::Value;
+    fn array(&mut self) -> Vec<Self::Value>;
+    fn object(&mut self) -> <Self::Value as Node<'a>>::Object;
+}
+
+// Builder of `JsonValue`s allocating everything anew, see `Recycler` for
+// one that doesn't.
+struct Allocating;
+
+impl<'a> Builder<'a> for Allocating {
+    type Value = JsonValue;
+
+    #[inline]
+    fn string<'b>(&mut self, text: Text<'a, 'b>) -> JsonValue {
+        match text {
+            Text::Wtf8(_, bytes) => JsonValue::Wtf8(Wtf8String::from_bytes(bytes.into())),
+            _                    => text.as_str().into(),
+        }
+    }
+
+    #[inline]
+    fn raw_number(&mut self, raw: &'a str, num: Number) -> JsonValue {
+        JsonValue::RawNumber(RawNumber::from_parts(raw.into(), num))
+    }
+
+    #[inline]
+    fn array(&mut self) -> Vec<JsonValue> {
+        Vec::with_capacity(2)
+    }
+
+    #[inline]
+    fn object(&mut self) -> Object {
+        Object::with_capacity(3)
+    }
+}
+
+impl<'a> Node<'a> for JsonValue {
+    type Object = Object;
+
+    #[inline]
+    fn null() -> Self {
+        JsonValue::Null
+    }
+
+    #[inline]
+    fn boolean(value: bool) -> Self {
+        JsonValue::Boolean(value)
+    }
+
+    #[inline]
+    fn number(num: Number) -> Self {
+        JsonValue::Number(num)
+    }
+
+    #[inline]
+    fn array(array: Vec<Self>) -> Self {
+        JsonValue::Array(array)
+    }
+
+    #[inline]
+    fn object(object: Object) -> Self {
+        JsonValue::Object(object)
+    }
+
+    #[inline]
+    fn as_array_mut(&mut self) -> Option<&mut Vec<Self>> {
+        match *self {
+            JsonValue::Array(ref mut array) => Some(array),
+            _                               => None,
+        }
+    }
+}
+
+impl<'a> Entries<'a> for Object {
+    type Value = JsonValue;
+
+    #[inline]
+    fn new() -> Self {
+        Object::new()
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        Object::len(self)
+    }
+
+    #[inline]
+    fn index_of(&self, key: &str) -> Option<usize> {
+        Object::index_of(self, key)
+    }
+
+    #[inline]
+    fn value_at_mut(&mut self, index: usize) -> &mut JsonValue {
+        Object::value_at_mut(self, index)
+    }
+
+    #[inline]
+    fn insert_null<'b>(&mut self, key: Text<'a, 'b>) -> usize {
+        self.insert_index(key.as_str(), JsonValue::Null)
+    }
+}
+
+// Where the value of the last key read goes in the object.
+#[derive(Clone, Copy)]
+enum Slot {
+    // Node at the index holds the value
+    Node(usize),
+
+    // Node at the index holds an array of values of a repeated key
+    Collect(usize),
+
+    // Value of a repeated key that isn't kept
+    Discard,
+}
+
+// Insert a key into an object following the duplicate key policy, and
+// figure out where its value goes. Returns `None` if the key is repeated
+// and that's an error.
+//
+// Keys that had their values collected into an array are kept track of in
+// `collected`, along with the `depth` of the object, so that the array is
+// only created once.
+fn insert_key<'a, 'b, O: Entries<'a>>(
+    options: &ParserOptions,
+    collected: &mut Vec<(usize, usize)>,
+    object: &mut O,
+    key: Text<'a, 'b>,
+    depth: usize
+) -> Option<Slot> {
+    if options.duplicate_keys == DuplicateKeys::LastWins {
+        return Some(Slot::Node(object.insert_null(key)));
+    }
+
+    let index = match object.index_of(key.as_str()) {
+        Some(index) => index,
+        None        => return Some(Slot::Node(object.insert_null(key))),
+    };
+
+    match options.duplicate_keys {
+        DuplicateKeys::FirstWins => Some(Slot::Discard),
+        DuplicateKeys::Collect   => {
+            if !collected.contains(&(depth, index)) {
+                let value = object.value_at_mut(index);
+                let first = mem::replace(value, Node::null());
+                *value = Node::array(vec![first]);
+                collected.push((depth, index));
+            }
+            Some(Slot::Collect(index))
+        },
+        _ => None,
+    }
+}
+
+fn put_value<'a, O: Entries<'a>>(object: &mut O, slot: Slot, value: O::Value) {
+    match slot {
+        Slot::Node(index)    => *object.value_at_mut(index) = value,
+        Slot::Collect(index) => {
+            if let Some(array) = object.value_at_mut(index).as_array_mut() {
+                array.push(value);
+            }
+        },
+        Slot::Discard        => {},
+    }
+}
+
+// The object at `depth` is being closed, forget about its collected keys.
+#[inline]
+fn forget_collected(collected: &mut Vec<(usize, usize)>, depth: usize) {
+    while collected.last().map_or(false, |&(at, _)| at == depth) {
+        collected.pop();
+    }
+}
+
+// Line and column, both starting at 1, of the byte at `at`.
+fn position(source: &[u8], at: usize) -> (usize, usize) {
+    let before = String::from_utf8_lossy(&source[..at]);
+
+    let (lineno, col) = before.lines()
+                              .enumerate()
+                              .last()
+                              .unwrap_or((0, ""));
+
+    let colno = col.chars().count();
+
+    (lineno + 1, colno + 1)
+}
+
+// Error for the character starting at `at`, wherever it wasn't expected.
+pub(crate) fn unexpected_at(source: &[u8], at: usize) -> Error {
+    let (line, column) = position(source, at);
+
+    Error::UnexpectedCharacter {
+        ch: char_at(source, at),
+        line: line,
+        column: column,
+    }
+}
+
+// Decode the character at `index` for the purpose of reporting errors,
+// falling back to the replacement character on invalid UTF-8.
+fn char_at(source: &[u8], index: usize) -> char {
+    let bytes = &source[index .. cmp::min(index + 4, source.len())];
+
+    let valid = match str::from_utf8(bytes) {
+        Ok(valid)  => valid,
+        Err(error) => unsafe { str::from_utf8_unchecked(&bytes[.. error.valid_up_to()]) },
+    };
+
+    valid.chars().next().unwrap_or(char::REPLACEMENT_CHARACTER)
+}
+
+// Check that a number already accepted in relaxed mode is also valid JSON,
+// that is no leading `+` or `.`, no trailing `.`, no hexadecimals, and no
+// `Infinity` or `NaN`.
+fn is_json_number(raw: &[u8]) -> bool {
+    let digits = match raw.first() {
+        Some(&b'-') => &raw[1 ..],
+        _           => raw,
+    };
+
+    match digits.first() {
+        Some(&b'0' ... b'9') => {},
+        _                    => return false,
+    }
+
+    let mut prev = 0;
+
+    for &ch in digits {
+        match ch {
+            b'0' ... b'9' | b'+' | b'-' => {},
+            b'.' | b'e' | b'E' if prev != b'.' => {},
+            _ => return false,
+        }
+        prev = ch;
+    }
+
+    prev != b'.'
+}
+
+// All that hard work, and in the end it's just a single function in the API.
+#[inline]
+pub fn parse(source: &str) -> Result<JsonValue> {
+    Parser::new(source).parse()
+}
+
+/// Parse JSON from bytes. Unlike converting the bytes to a `&str` first,
+/// this only checks that the contents of strings are valid UTF-8, as
+/// they are being parsed, saving an additional pass over the source.
+///
+/// ```
+/// let data = json::parse_bytes(b"[\"foo\", 42]").unwrap();
+///
+/// assert_eq!(data[0], "foo");
+/// assert_eq!(data[1], 42);
+///
+/// assert_eq!(json::parse_bytes(b"[\"\xff\"]"), Err(json::Error::FailedUtf8Parsing));
+/// ```
+#[inline]
+pub fn parse_bytes(source: &[u8]) -> Result<JsonValue> {
+    Parser::from_bytes(source).parse()
+}
+
+/// Parse JSON from an implementor of `std::io::Read`, such as a file or
+/// a socket. The source is read in chunks and fed to a `PushParser`, so
+/// it never has to be held in memory in its entirety.
+///
+/// ```
+/// let source: &[u8] = br#"{"foo":"bar"}"#;
+/// let data = json::parse_reader(source).unwrap();
+///
+/// assert_eq!(data["foo"], "bar");
+/// ```
+pub fn parse_reader<R: Read>(reader: R) -> Result<JsonValue> {
+    read_into(PushParser::new(), reader)
+}
+
+/// Parse a source made of any number of JSON values one after another,
+/// such as `{"a":1}{"b":2}` or values separated by whitespace. See
+/// `ParseMany` for details.
+pub fn parse_many(source: &str) -> ParseMany {
+    ParseMany::new(Parser::new(source))
+}
+
+/// Parse JSON, along with a side table of `Spans`, telling where in the
+/// source each value and object key is. This is slower than `json::parse`,
+/// so only use it when you need to point at things in the source, such
+/// as when reporting invalid configuration.
+pub fn parse_with_spans(source: &str) -> Result<(JsonValue, Spans)> {
+    spans::parse_with_spans(source, ParserOptions::new())
+}
+
+/// Parse JSON into a `BorrowedValue`, which borrows strings without escape
+/// sequences, object keys and raw numbers from the source, instead of
+/// allocating them. Useful for read only access to parsed data, see
+/// `BorrowedValue`.
+///
+/// ```
+/// let source = r#"{"user":{"id":7,"roles":["admin"]}}"#;
+/// let data = json::parse_borrowed(source).unwrap();
+///
+/// assert_eq!(data["user"]["id"], 7);
+/// assert_eq!(data["user"]["roles"][0].as_str(), Some("admin"));
+/// assert_eq!(data.to_json(), json::parse(source).unwrap());
+/// ```
+pub fn parse_borrowed(source: &str) -> Result<BorrowedValue> {
+    parse_borrowed_with(source, ParserOptions::new())
+}
+
+/// Parse JSON into an existing value, replacing it. Strings, arrays and
+/// objects of the old value are reused for the new one where possible,
+/// which saves on allocations when the same `JsonValue` is parsed into
+/// over and over. On error the `target` is left as `Null`. To also reuse
+/// allocations across different values, see `Recycler`.
+///
+/// ```
+/// let mut data = json::JsonValue::new_object();
+///
+/// json::parse_into(r#"{"items":[1,2,3]}"#, &mut data).unwrap();
+/// assert_eq!(data["items"].len(), 3);
+///
+/// json::parse_into(r#"{"items":[4,5]}"#, &mut data).unwrap();
+/// assert_eq!(data["items"].len(), 2);
+/// ```
+pub fn parse_into(source: &str, target: &mut JsonValue) -> Result<()> {
+    Recycler::new().parse_into(source, target)
+}
+
+/// Check that the source is valid JSON, without decoding it just yet. Only
+/// the values that are accessed through the returned `LazyDocument` are
+/// decoded, when they are accessed, see `LazyValue`.
+///
+/// ```
+/// let data = json::parse_lazy(r#"{"a":[1,2,3],"b":"skipped"}"#).unwrap();
+///
+/// assert_eq!(data["a"][2], 3);
+/// assert!(json::parse_lazy("[1,2,").is_err());
+/// ```
+pub fn parse_lazy(source: &str) -> Result<LazyDocument> {
+    lazy::parse_lazy(source, ParserOptions::new())
+}
+
+/// Parse only the values at the given JSON Pointers, such as `"/user/id"`,
+/// stepping over everything else in the source. Returns the values in the
+/// order of the pointers, with `None` for pointers that lead nowhere, or
+/// are not valid pointers. The empty pointer, `""`, is the whole document.
+///
+/// Much less work is done than with `json::parse` followed by picking the
+/// values out: apart from the arrays and objects on the way to the values,
+/// the source is only checked for balanced brackets and closed strings.
+///
+/// ```
+/// let source = r#"{"user":{"id":7,"name":"Ann"},"items":[{"price":9.5},{"price":3}]}"#;
+/// let found = json::extract(source, &["/user/id", "/items/1/price", "/items/2"]).unwrap();
+///
+/// assert_eq!(found, vec![Some(7.into()), Some(3.into()), None]);
+/// ```
+pub fn extract(source: &str, pointers: &[&str]) -> Result<Vec<Option<JsonValue>>> {
+    extract::extract(source, pointers, ParserOptions::new())
+}
+
+/// Parse JSON on many threads, for sources with a large array at the top
+/// level, such as dumps of a database table. The members of the array are
+/// found with a quick pass over the source, which only counts brackets and
+/// skips strings, then split into chunks that are parsed at the same time.
+/// Anything else, and small sources, are parsed the same as `json::parse`
+/// would.
+///
+/// Should the source not be valid JSON, the very same error is reported as
+/// by `json::parse`.
+///
+/// ```
+/// let source = format!("[{}1]", "{\"id\":1,\"tags\":[\"a\",\"b\"]},".repeat(20000));
+/// let data = json::parse_parallel(&source).unwrap();
+///
+/// assert_eq!(data.len(), 20001);
+/// assert_eq!(data[19999]["tags"][1], "b");
+/// assert_eq!(data, json::parse(&source).unwrap());
+/// ```
+pub fn parse_parallel(source: &str) -> Result<JsonValue> {
+    parallel::parse_parallel(source, ParserOptions::new())
+}
+
+/// Parse newline delimited JSON on many threads, into an array of the
+/// values on each line, in order. Blank lines are skipped. Unlike with
+/// `NdjsonReader`, an invalid line fails the whole source, with its
+/// position counted from the start of the source.
+///
+/// ```
+/// let data = json::parse_ndjson_parallel("{\"id\":1}\n\n{\"id\":2}\n").unwrap();
+///
+/// assert_eq!(data.len(), 2);
+/// assert_eq!(data[1]["id"], 2);
+/// assert!(json::parse_ndjson_parallel("1\n[2,\n").is_err());
+/// ```
+pub fn parse_ndjson_parallel(source: &str) -> Result<JsonValue> {
+    parallel::parse_ndjson_parallel(source, ParserOptions::new())
+}
+
+fn parse_borrowed_with(source: &str, options: ParserOptions) -> Result<BorrowedValue> {
+    Parser::new(source).with_options(options).parse_with(&mut borrowed::Borrowing)
+}
+
+/// Parse JSON without ever giving up, for tools such as editors that want
+/// to report every problem in a document at once. Returns a best-effort
+/// value along with a `Diagnostic` for each problem found, in the order
+/// they were found. For valid JSON there are no diagnostics, and the value
+/// is the same as returned by `json::parse`.
+///
+/// Stray characters are skipped, missing commas, colons and closing
+/// brackets are assumed, unclosed strings end with the line, and values
+/// that can't be read are replaced with `null`.
+///
+/// ```
+/// use json::DiagnosticKind;
+///
+/// let source = "{\n  \"name\": \"config\n  \"port\": 80 \"debug\": tru\n}";
+/// let (data, diagnostics) = json::parse_recovering(source);
+///
+/// assert_eq!(data["name"], "config");
+/// assert_eq!(data["port"], 80);
+/// assert!(data["debug"].is_null());
+///
+/// let kinds: Vec<_> = diagnostics.iter().map(|diagnostic| diagnostic.kind.clone()).collect();
+///
+/// assert_eq!(kinds, vec![
+///     DiagnosticKind::UnclosedString,
+///     DiagnosticKind::MissingComma,
+///     DiagnosticKind::MissingComma,
+///     DiagnosticKind::InvalidLiteral,
+/// ]);
+/// assert_eq!(diagnostics[0].to_string(), "Unclosed string at (2:11)");
+/// ```
+pub fn parse_recovering(source: &str) -> (JsonValue, Vec<Diagnostic>) {
+    recover::parse_recovering(source, ParserOptions::new())
+}
+
+// Feed everything from the `reader` into the `parser`.
+fn read_into<R: Read>(mut parser: PushParser, mut reader: R) -> Result<JsonValue> {
+    let mut chunk = [0; 8192];
+
+    loop {
+        let read = match reader.read(&mut chunk) {
+            Ok(0)     => break,
+            Ok(read)  => read,
+            Err(ref error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error.into()),
+        };
+
+        try!(parser.feed(&chunk[.. read]));
+    }
+
+    parser.finish()
+}
@@ -0,0 +1,352 @@
+use std::usize;
+use std::io::Read;
+use value::{ BorrowedValue, LazyDocument };
+use { JsonValue, Result };
+use super::{ Parser, PushParser, Spans, ParseMany, Diagnostic, Recycler, DEPTH_LIMIT, THREADS };
+use super::encoding;
+
+/// What to do when an object being parsed has the same key more than once.
+///
+/// ```
+/// # #[macro_use] extern crate json;
+/// # fn main() {
+/// use json::{ ParserOptions, DuplicateKeys };
+///
+/// let source = r#"{"a":1,"a":2}"#;
+/// let options = ParserOptions::new();
+///
+/// assert_eq!(options.parse(source).unwrap()["a"], 2);
+/// assert_eq!(options.duplicate_keys(DuplicateKeys::FirstWins).parse(source).unwrap()["a"], 1);
+/// assert_eq!(options.duplicate_keys(DuplicateKeys::Collect).parse(source).unwrap()["a"], array![1, 2]);
+/// assert!(options.duplicate_keys(DuplicateKeys::Error).parse(source).is_err());
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateKeys {
+    /// Keep the value of the last occurrence. This is the default.
+    LastWins,
+
+    /// Keep the value of the first occurrence, ignoring the rest.
+    FirstWins,
+
+    /// Fail with `Error::DuplicateKey`, pointing at the repeated key.
+    Error,
+
+    /// Put values of all occurrences into an array, in order. Keys that
+    /// are not repeated keep their value as is.
+    Collect,
+}
+
+/// What to do with `\u` escapes of UTF-16 surrogates that are not part of
+/// a pair, such as `"\uD83D"` on its own. These can't be decoded into a
+/// valid `str`, yet they are common in data coming from JavaScript or
+/// Windows, where strings are sequences of UTF-16 code units.
+///
+/// ```
+/// use json::{ ParserOptions, LoneSurrogates };
+///
+/// let source = r#"["\uD83D\uDE00", "\uD83D!"]"#;
+/// let options = ParserOptions::new();
+///
+/// assert!(options.parse(source).is_err());
+///
+/// let data = options.lone_surrogates(LoneSurrogates::Replace).parse(source).unwrap();
+///
+/// assert_eq!(data[0], "😀");
+/// assert_eq!(data[1], "\u{FFFD}!");
+///
+/// let data = options.lone_surrogates(LoneSurrogates::Preserve).parse(source).unwrap();
+///
+/// assert_eq!(data[0], "😀");
+/// assert!(data[1].as_str().is_none());
+/// assert_eq!(data.dump(), r#"["😀","\ud83d!"]"#);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoneSurrogates {
+    /// Fail with `Error::FailedUtf8Parsing`. This is the default.
+    Error,
+
+    /// Replace each lone surrogate with U+FFFD, the replacement character.
+    Replace,
+
+    /// Keep each lone surrogate. Strings with any are parsed into
+    /// `JsonValue::Wtf8`, which is written out with the lone surrogates
+    /// escaped again, so the data makes it through a round trip unchanged.
+    /// Object keys, as well as strings of borrowed values and events of
+    /// the `EventReader`, have to be valid `str`s, so there lone surrogates
+    /// are replaced as with `Replace`.
+    Preserve,
+}
+
+/// Options for parsing JSON. Sensible defaults are provided through
+/// `ParserOptions::new()`, which are also used by `json::parse`. Each
+/// option can then be changed with a chainable setter.
+///
+/// The limits are useful when parsing untrusted input, as they keep the
+/// amount of memory used by a malicious source bounded. Each violated
+/// limit is reported with its own `Error` variant.
+///
+/// ```
+/// use json::{ ParserOptions, Error };
+///
+/// let options = ParserOptions::new()
+///     .depth_limit(2)
+///     .array_length_limit(3);
+///
+/// assert!(options.parse("[[1, 2, 3]]").is_ok());
+/// assert_eq!(options.parse("[[[1]]]").unwrap_err(), Error::ExceededDepthLimit);
+/// assert_eq!(options.parse("[1, 2, 3, 4]").unwrap_err(), Error::ExceededArrayLengthLimit);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParserOptions {
+    pub(super) depth_limit: usize,
+    pub(crate) size_limit: usize,
+    pub(super) string_length_limit: usize,
+    pub(super) array_length_limit: usize,
+    pub(super) object_length_limit: usize,
+    pub(super) relaxed: bool,
+    pub(super) lossless_numbers: bool,
+    pub(super) duplicate_keys: DuplicateKeys,
+    pub(super) lone_surrogates: LoneSurrogates,
+    pub(super) detect_encoding: bool,
+    pub(super) threads: usize,
+}
+
+impl ParserOptions {
+    /// Create options with default values. The only limit set by default
+    /// is a depth limit of 512 nested arrays and objects.
+    pub fn new() -> Self {
+        ParserOptions {
+            depth_limit: DEPTH_LIMIT,
+            size_limit: usize::MAX,
+            string_length_limit: usize::MAX,
+            array_length_limit: usize::MAX,
+            object_length_limit: usize::MAX,
+            relaxed: false,
+            lossless_numbers: false,
+            duplicate_keys: DuplicateKeys::LastWins,
+            lone_surrogates: LoneSurrogates::Error,
+            detect_encoding: false,
+            threads: THREADS,
+        }
+    }
+
+    /// How many nested arrays and objects are allowed. Defaults to `512`.
+    pub fn depth_limit(mut self, limit: usize) -> Self {
+        self.depth_limit = limit;
+        self
+    }
+
+    /// Maximum size of the source in bytes.
+    pub fn size_limit(mut self, limit: usize) -> Self {
+        self.size_limit = limit;
+        self
+    }
+
+    /// Maximum length in bytes of any string, including object keys,
+    /// after escape sequences have been decoded.
+    pub fn string_length_limit(mut self, limit: usize) -> Self {
+        self.string_length_limit = limit;
+        self
+    }
+
+    /// Maximum number of members of any array.
+    pub fn array_length_limit(mut self, limit: usize) -> Self {
+        self.array_length_limit = limit;
+        self
+    }
+
+    /// Maximum number of keys of any object.
+    pub fn object_length_limit(mut self, limit: usize) -> Self {
+        self.object_length_limit = limit;
+        self
+    }
+
+    /// Accept JSON5 style input: `//` and `/* */` comments, trailing
+    /// commas, single quoted strings, unquoted identifier keys, hexadecimal
+    /// numbers, a leading `+`, leading or trailing decimal points, as well
+    /// as `Infinity` and `NaN`. Defaults to `false`.
+    ///
+    /// `Number` has no representation of infinity, so `Infinity` is parsed
+    /// as a number with a huge exponent, converting to an infinite `f64`.
+    ///
+    /// ```
+    /// use json::ParserOptions;
+    ///
+    /// let data = ParserOptions::new().relaxed(true).parse("
+    ///     // a comment
+    ///     {
+    ///         unquoted: 'single',
+    ///         hex: 0xFF,
+    ///         list: [+1, .5, 2.,],
+    ///     }
+    /// ").unwrap();
+    ///
+    /// assert_eq!(data["unquoted"], "single");
+    /// assert_eq!(data["hex"], 255);
+    /// assert_eq!(data["list"][1], 0.5);
+    /// ```
+    pub fn relaxed(mut self, relaxed: bool) -> Self {
+        self.relaxed = relaxed;
+        self
+    }
+
+    /// Keep numbers in their original lexical form, as
+    /// `JsonValue::RawNumber`, instead of converting them to `Number`.
+    /// Numbers with more digits than fit into `Number` then survive being
+    /// parsed and written out again without a single digit changed.
+    /// Defaults to `false`.
+    ///
+    /// Numbers using syntax only allowed in relaxed mode, such as `0xFF`
+    /// or `.5`, are not valid JSON, and are converted to `Number` as usual.
+    /// The `EventReader` is not affected by this option.
+    ///
+    /// ```
+    /// use json::ParserOptions;
+    ///
+    /// let source = r#"{"id":98765432109876543210,"price":10.10}"#;
+    /// let data = ParserOptions::new().lossless_numbers(true).parse(source).unwrap();
+    ///
+    /// assert_eq!(data.dump(), source);
+    /// ```
+    pub fn lossless_numbers(mut self, lossless: bool) -> Self {
+        self.lossless_numbers = lossless;
+        self
+    }
+
+    /// What to do with repeated keys in objects. Defaults to
+    /// `DuplicateKeys::LastWins`. The `EventReader` is not affected by
+    /// this option, it reports every key as it is.
+    pub fn duplicate_keys(mut self, policy: DuplicateKeys) -> Self {
+        self.duplicate_keys = policy;
+        self
+    }
+
+    /// What to do with escapes of lone UTF-16 surrogates in strings.
+    /// Defaults to `LoneSurrogates::Error`.
+    pub fn lone_surrogates(mut self, policy: LoneSurrogates) -> Self {
+        self.lone_surrogates = policy;
+        self
+    }
+
+    /// Skip a UTF-8 byte order mark at the start of the source, and have
+    /// bytes, given to `parse_bytes`, `parse_reader` or a `PushParser`,
+    /// in UTF-16 or UTF-32 transcoded into UTF-8 before they are parsed.
+    /// The encoding is detected from the byte order mark, or without one,
+    /// from the zero bytes among the first four, as RFC 4627 describes.
+    /// Defaults to `false`.
+    ///
+    /// ```
+    /// # #[macro_use] extern crate json;
+    /// # fn main() {
+    /// use json::ParserOptions;
+    ///
+    /// let source = b"\xFF\xFE[\x001\x00,\x00\"\x00\xE9\x00\"\x00]\x00";
+    /// let data = ParserOptions::new().detect_encoding(true).parse_bytes(source).unwrap();
+    ///
+    /// assert_eq!(data, array![1, "é"]);
+    /// # }
+    /// ```
+    pub fn detect_encoding(mut self, detect: bool) -> Self {
+        self.detect_encoding = detect;
+        self
+    }
+
+    /// How many threads `parse_parallel` and `parse_ndjson_parallel` may
+    /// use. Defaults to `4`.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Parse a `&str` with these options, see `json::parse`.
+    pub fn parse(&self, source: &str) -> Result<JsonValue> {
+        Parser::new(source).with_options(*self).parse()
+    }
+
+    /// Parse a `&str` into a `BorrowedValue` with these options, see
+    /// `json::parse_borrowed`.
+    pub fn parse_borrowed<'a>(&self, source: &'a str) -> Result<BorrowedValue<'a>> {
+        super::parse_borrowed_with(source, *self)
+    }
+
+    /// Parse a `&str` into an existing value with these options, see
+    /// `json::parse_into`.
+    pub fn parse_into(&self, source: &str, target: &mut JsonValue) -> Result<()> {
+        Recycler::with_options(*self).parse_into(source, target)
+    }
+
+    /// Parse a `&str` into a `LazyDocument` with these options, see
+    /// `json::parse_lazy`. The limits apply, and comments are allowed in
+    /// relaxed mode, but otherwise the source has to be strict JSON.
+    /// Duplicate keys are looked up the same way as with the default
+    /// `DuplicateKeys::LastWins`. Lone surrogates, if allowed, are decoded
+    /// by `LazyValue::as_str` as with `LoneSurrogates::Replace`, while
+    /// `LazyValue::to_json` follows these options.
+    pub fn parse_lazy<'a>(&self, source: &'a str) -> Result<LazyDocument<'a>> {
+        super::lazy::parse_lazy(source, *self)
+    }
+
+    /// Extract values by JSON Pointer with these options, see
+    /// `json::extract`. The source always has to be strict JSON, and the
+    /// limits don't apply to values that are skipped over.
+    pub fn extract(&self, source: &str, pointers: &[&str]) -> Result<Vec<Option<JsonValue>>> {
+        super::extract::extract(source, pointers, *self)
+    }
+
+    /// Parse a `&str` on many threads with these options, see
+    /// `json::parse_parallel`.
+    pub fn parse_parallel(&self, source: &str) -> Result<JsonValue> {
+        super::parallel::parse_parallel(source, *self)
+    }
+
+    /// Parse newline delimited JSON on many threads with these options, see
+    /// `json::parse_ndjson_parallel`. The size limit applies to the source
+    /// as a whole, all other options to each line on its own.
+    pub fn parse_ndjson_parallel(&self, source: &str) -> Result<JsonValue> {
+        super::parallel::parse_ndjson_parallel(source, *self)
+    }
+
+    /// Parse bytes with these options, see `json::parse_bytes`.
+    pub fn parse_bytes(&self, source: &[u8]) -> Result<JsonValue> {
+        if self.detect_encoding {
+            let source = try!(encoding::transcode(source));
+
+            return Parser::from_bytes(&source).with_options(*self).parse();
+        }
+
+        Parser::from_bytes(source).with_options(*self).parse()
+    }
+
+    /// Parse from a reader with these options, see `json::parse_reader`.
+    pub fn parse_reader<R: Read>(&self, reader: R) -> Result<JsonValue> {
+        super::read_into(PushParser::with_options(*self), reader)
+    }
+
+    /// Parse a source made of many JSON values with these options, see
+    /// `json::parse_many`.
+    pub fn parse_many<'a>(&self, source: &'a str) -> ParseMany<'a> {
+        ParseMany::new(Parser::new(source).with_options(*self))
+    }
+
+    /// Parse a `&str` with these options, along with a side table of
+    /// `Spans`, see `json::parse_with_spans`.
+    pub fn parse_with_spans(&self, source: &str) -> Result<(JsonValue, Spans)> {
+        super::spans::parse_with_spans(source, *self)
+    }
+
+    /// Parse a `&str` with these options, reporting all problems found
+    /// instead of failing, see `json::parse_recovering`. Only the depth
+    /// limit, which can't be raised past the default of 512 here,
+    /// `duplicate_keys` and `lossless_numbers` apply. Relaxed syntax is
+    /// reported like any other problem.
+    pub fn parse_recovering(&self, source: &str) -> (JsonValue, Vec<Diagnostic>) {
+        super::recover::parse_recovering(source, *self)
+    }
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        ParserOptions::new()
+    }
+}
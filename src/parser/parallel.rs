@@ -0,0 +1,229 @@
+// Parallel parsing
+// ================
+//
+// Large arrays and newline delimited JSON are split into chunks of about
+// the same size, each parsed on a thread of its own. Finding where members
+// of an array end still takes a pass over the source, but stepping over
+// values by counting brackets and skipping strings, same as `extract` does,
+// is a lot faster than building them. Lines are even easier to find, since
+// a valid JSON value never has a raw newline in it.
+
+use std::{ cmp, mem, panic, thread, usize };
+use std::sync::Arc;
+use { JsonValue, Error, Result };
+use super::{ Parser, ParserOptions };
+
+// Sources with less than this many bytes for each thread are not worth
+// spawning threads for.
+const MIN_CHUNK: usize = 64 * 1024;
+
+// How many threads to split a source of the given length between.
+fn threads(options: &ParserOptions, length: usize) -> usize {
+    cmp::max(cmp::min(options.threads, length / MIN_CHUNK), 1)
+}
+
+// Parse each chunk, a range of the source, on a thread of its own, and put
+// all the values together in order. Should more than one chunk fail, the
+// error of the first one is returned.
+fn parse_chunks<F>(source: &str, chunks: &[(usize, usize)], parse: F) -> Result<JsonValue>
+    where F: Fn(&str, usize, usize) -> Result<Vec<JsonValue>> + Send + Sync + 'static
+{
+    if chunks.len() == 1 {
+        return parse(source, chunks[0].0, chunks[0].1).map(JsonValue::Array);
+    }
+
+    // Threads can only borrow what lives forever, yet each and every one
+    // of them is joined before anything else is done, panics included, so
+    // the source outlives them all.
+    let shared: &'static str = unsafe { mem::transmute(source) };
+    let parse = Arc::new(parse);
+
+    let handles: Vec<_> = chunks.iter().map(|&(start, end)| {
+        let parse = parse.clone();
+
+        thread::Builder::new().spawn(move || parse(shared, start, end))
+    }).collect();
+
+    let results: Vec<_> = handles.into_iter().map(|handle| {
+        handle.map(|handle| handle.join())
+    }).collect();
+
+    let mut values = Vec::new();
+
+    for (result, &(start, end)) in results.into_iter().zip(chunks) {
+        let result = match result {
+            Ok(Ok(result))   => result,
+            Ok(Err(payload)) => panic::resume_unwind(payload),
+
+            // No thread could be spawned for the chunk
+            Err(_)           => parse(source, start, end),
+        };
+
+        values.extend(try!(result));
+    }
+
+    Ok(JsonValue::Array(values))
+}
+
+impl<'a> Parser<'a> {
+    // Split the members of the top level array into chunks of at least
+    // `target` bytes, each ending right before a comma or the closing
+    // bracket, and return the number of members. Only brackets and strings
+    // are checked, the members are parsed later.
+    fn split_array(&mut self, target: usize, chunks: &mut Vec<(usize, usize)>) -> Result<usize> {
+        if expect_byte_ignore_whitespace!(self) != b'[' {
+            return self.unexpected_character();
+        }
+
+        let mut start = self.index;
+        let mut count = 0;
+        let mut ch = expect_byte_ignore_whitespace!(self);
+
+        if ch == b']' {
+            return self.unexpected_character();
+        }
+
+        loop {
+            try!(self.skip_value(ch));
+
+            count += 1;
+
+            match expect_byte_ignore_whitespace!(self) {
+                b',' => {
+                    if self.index - start > target {
+                        chunks.push((start, self.index - 1));
+                        start = self.index;
+                    }
+
+                    ch = expect_byte_ignore_whitespace!(self);
+                },
+                b']' => {
+                    chunks.push((start, self.index - 1));
+
+                    expect_eof!(self);
+
+                    return Ok(count);
+                },
+                _ => return self.unexpected_character(),
+            }
+        }
+    }
+
+    // Parse comma separated members up to the end of the source.
+    fn parse_members(&mut self) -> Result<Vec<JsonValue>> {
+        let mut members = Vec::new();
+
+        'members: loop {
+            members.push(try!(self.parse_value()));
+
+            while !self.is_eof() {
+                let ch = self.read_byte();
+
+                self.bump();
+
+                match ch {
+                    9 ... 13 | 32 => {},
+                    b','          => continue 'members,
+                    _             => return self.unexpected_character(),
+                }
+            }
+
+            return Ok(members);
+        }
+    }
+}
+
+pub(super) fn parse_parallel(source: &str, options: ParserOptions) -> Result<JsonValue> {
+    let threads = threads(&options, source.len());
+
+    if threads > 1 && !options.relaxed && options.depth_limit > 0 && source.len() <= options.size_limit {
+        let mut parser = Parser::new(source).with_options(options);
+        let mut chunks = Vec::with_capacity(threads + 1);
+
+        match parser.split_array(source.len() / threads, &mut chunks) {
+            Ok(count) if count <= options.array_length_limit => {
+                // Members are one level deep into the array
+                let options = options.depth_limit(options.depth_limit - 1).size_limit(usize::MAX);
+
+                // Errors are found at the same position as by the regular
+                // parser, which is counted from the start of the source
+                return parse_chunks(source, &chunks, move |source: &str, start, end| {
+                    let mut parser = Parser::new(&source[.. end]).with_options(options);
+
+                    parser.index = start;
+                    parser.parse_members()
+                });
+            },
+            _ => {},
+        }
+    }
+
+    // Anything that isn't a large array, and any errors in the structure of
+    // one, are left to the regular parser, so that the same error is
+    // reported as by `json::parse`.
+    Parser::new(source).with_options(options).parse()
+}
+
+pub(super) fn parse_ndjson_parallel(source: &str, options: ParserOptions) -> Result<JsonValue> {
+    if source.len() > options.size_limit {
+        return Err(Error::ExceededSizeLimit);
+    }
+
+    let bytes = source.as_bytes();
+    let target = cmp::max(bytes.len() / threads(&options, bytes.len()), 1);
+    let mut chunks = Vec::new();
+
+    // Past the byte order mark, if it is to be skipped
+    let mut start = Parser::new(source).with_options(options).index;
+
+    // Split at the first newline past the target size of each chunk
+    while start < bytes.len() {
+        let end = cmp::min(start + target, bytes.len());
+        let end = match bytes[end ..].iter().position(|&ch| ch == b'\n') {
+            Some(newline) => end + newline + 1,
+            None          => bytes.len(),
+        };
+
+        chunks.push((start, end));
+        start = end;
+    }
+
+    if chunks.is_empty() {
+        return Ok(JsonValue::Array(Vec::new()));
+    }
+
+    let options = options.size_limit(usize::MAX);
+
+    parse_chunks(source, &chunks, move |source: &str, start, end| {
+        let bytes = source.as_bytes();
+        let mut parser = Parser::new(source).with_options(options);
+        let mut values = Vec::new();
+        let mut line = start;
+
+        while line < end {
+            let next = match bytes[line .. end].iter().position(|&ch| ch == b'\n') {
+                Some(newline) => line + newline + 1,
+                None          => end,
+            };
+
+            let blank = bytes[line .. next].iter().all(|&ch| match ch {
+                9 ... 13 | 32 => true,
+                _             => false,
+            });
+
+            if !blank {
+                // Parse the line alone, yet with positions of errors
+                // counted from the start of the source.
+                parser.source = &bytes[.. next];
+                parser.length = next;
+                parser.index = line;
+
+                values.push(try!(parser.parse()));
+            }
+
+            line = next;
+        }
+
+        Ok(values)
+    })
+}
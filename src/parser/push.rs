@@ -0,0 +1,639 @@
+// Push parsing
+// ============
+//
+// The regular `Parser` wants the entire source up front, which is great for
+// speed, but not so great if the source is trickling in from a socket. The
+// `PushParser` here is a small state machine that can be fed chunks of bytes
+// as they arrive. It keeps the same explicit `StackBlock` stack the regular
+// parser uses, except the stack lives on the struct so it survives between
+// calls to `feed`.
+//
+// Structural bytes (brackets, colons, commas) and whitespace are handled
+// directly. Scalar tokens (strings, numbers) are collected into a small
+// buffer until they are complete, and then handed over to the regular
+// `Parser`, so we don't duplicate the escaping or number parsing logic.
+
+use std::{ str, mem };
+use object::Object;
+use { JsonValue, Error, Result };
+use super::{ Parser, ParserOptions, char_at, StackBlock, Slot };
+use super::encoding::Transcoder;
+use super::{ Text, insert_key, put_value, forget_collected };
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum State {
+    // Expecting any value.
+    Value,
+
+    // Just read `[`, expecting a value or `]`.
+    ArrayStart,
+
+    // Just read `{`, expecting a key or `}`.
+    ObjectStart,
+
+    // Just read `,` inside an object, expecting a key.
+    Key,
+
+    // Just read a key, expecting `:`.
+    Colon,
+
+    // Just read a value inside of a container, expecting `,` or a
+    // closing bracket.
+    Separator,
+
+    // The root value is complete, only whitespace is allowed from here on.
+    Done,
+
+    // Inside of a string token. `key` marks strings that are object keys,
+    // `quote` is the byte that closes the string, and `escape` marks that
+    // the last byte read was a backslash.
+    String { key: bool, quote: u8, escape: bool },
+
+    // Inside of an unquoted object key, relaxed mode only.
+    Identifier,
+
+    // Inside of a number token.
+    Number,
+
+    // Inside of `true`, `false` or `null`, having matched `matched` bytes.
+    Literal { word: &'static [u8], matched: usize },
+
+    // Comments in relaxed mode. Once the comment is over, the parser
+    // resumes from the state it was in before it.
+    CommentStart,
+    LineComment,
+    BlockComment { star: bool },
+}
+
+/// Incremental parser that can be fed the source in chunks of bytes, as
+/// they arrive. This is useful for overlapping parsing with I/O, without
+/// having to buffer the entire source first.
+///
+/// Chunks can be split at any byte, including the middle of a string or
+/// a multi-byte UTF-8 sequence.
+///
+/// ```
+/// let mut parser = json::PushParser::new();
+///
+/// parser.feed(br#"{"foo":[1,2"#).unwrap();
+/// parser.feed(br#",3],"bar":"ba"#).unwrap();
+/// parser.feed(br#"z"}"#).unwrap();
+///
+/// let data = parser.finish().unwrap();
+///
+/// assert_eq!(data["foo"][2], 3);
+/// assert_eq!(data["bar"], "baz");
+/// ```
+pub struct PushParser {
+    // Open containers, same as in the regular parser
+    stack: Vec<StackBlock>,
+
+    // What we are expecting to read next
+    state: State,
+
+    // Bytes of the scalar token currently being read
+    token: Vec<u8>,
+
+    // Buffer of the parser tokens are parsed with, kept for the next one
+    buffer: Vec<u8>,
+
+    // Completed root value
+    root: Option<JsonValue>,
+
+    // State to return to after a comment
+    resume: State,
+
+    // Keys with values collected into arrays, same as in the regular parser
+    collected: Vec<(usize, usize)>,
+
+    // Position of the next byte to be read, used for errors
+    line: usize,
+    column: usize,
+
+    // Position of the first byte of the current token
+    token_line: usize,
+    token_column: usize,
+
+    // Total number of bytes fed so far
+    size: usize,
+
+    // Limits and such
+    options: ParserOptions,
+
+    // Source transcoded into UTF-8, see `ParserOptions::detect_encoding`
+    transcoder: Option<Transcoder>,
+    decoded: Vec<u8>,
+}
+
+impl PushParser {
+    /// Create a new `PushParser`, ready to be fed.
+    pub fn new() -> Self {
+        PushParser::with_options(ParserOptions::new())
+    }
+
+    /// Create a new `PushParser` with custom `ParserOptions`.
+    pub fn with_options(options: ParserOptions) -> Self {
+        PushParser {
+            stack: Vec::with_capacity(3),
+            state: State::Value,
+            token: Vec::with_capacity(30),
+            buffer: Vec::with_capacity(30),
+            root: None,
+            resume: State::Value,
+            collected: Vec::new(),
+            line: 1,
+            column: 1,
+            token_line: 1,
+            token_column: 1,
+            size: 0,
+            options: options,
+            transcoder: if options.detect_encoding { Some(Transcoder::new()) } else { None },
+            decoded: Vec::new(),
+        }
+    }
+
+    /// Feed the next chunk of the source to the parser. Will return an
+    /// error as soon as the source so far is known to be invalid.
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<()> {
+        let mut decoded = match self.transcoder {
+            Some(ref mut transcoder) => {
+                let mut decoded = mem::replace(&mut self.decoded, Vec::new());
+
+                decoded.clear();
+                try!(transcoder.feed(chunk, &mut decoded));
+                decoded
+            },
+            None => return self.feed_decoded(chunk),
+        };
+
+        let result = self.feed_decoded(&decoded);
+
+        mem::swap(&mut self.decoded, &mut decoded);
+
+        result
+    }
+
+    fn feed_decoded(&mut self, chunk: &[u8]) -> Result<()> {
+        self.size = self.size.saturating_add(chunk.len());
+
+        if self.size > self.options.size_limit {
+            return Err(Error::ExceededSizeLimit);
+        }
+
+        let mut index = 0;
+
+        // Index up to which `line` and `column` are up to date
+        let mut synced = 0;
+
+        while index < chunk.len() {
+            match self.state {
+                State::String { key, quote, mut escape } => {
+                    let start = index;
+                    let mut closed = false;
+
+                    while index < chunk.len() {
+                        let ch = chunk[index];
+                        index += 1;
+
+                        if escape {
+                            escape = false;
+                        } else if ch == b'\\' {
+                            escape = true;
+                        } else if ch == quote {
+                            closed = true;
+                            break;
+                        }
+                    }
+
+                    self.token.extend_from_slice(&chunk[start .. index]);
+
+                    // The limit applies to decoded strings, but we don't want
+                    // to buffer forever before we can check that. An escape
+                    // sequence is at most 6 bytes long, so if the token is
+                    // longer than this, the decoded string can't fit either.
+                    let raw_limit = self.options.string_length_limit.saturating_mul(6).saturating_add(2);
+
+                    if self.token.len() > raw_limit {
+                        return Err(Error::ExceededStringLengthLimit);
+                    }
+
+                    if closed {
+                        try!(self.end_string(key));
+                    } else {
+                        self.state = State::String { key: key, quote: quote, escape: escape };
+                    }
+                    continue;
+                },
+                State::Identifier => {
+                    let start = index;
+
+                    while index < chunk.len() {
+                        match chunk[index] {
+                            b'a' ... b'z' | b'A' ... b'Z' | b'0' ... b'9' | b'_' | b'$' => index += 1,
+                            _ => break
+                        }
+                    }
+
+                    self.token.extend_from_slice(&chunk[start .. index]);
+
+                    if self.token.len() > self.options.string_length_limit {
+                        return Err(Error::ExceededStringLengthLimit);
+                    }
+
+                    if index < chunk.len() {
+                        try!(self.end_identifier());
+                    }
+                    continue;
+                },
+                State::Number => {
+                    let start = index;
+                    let relaxed = self.options.relaxed;
+
+                    // Relaxed numbers can be hexadecimal, `Infinity` or `NaN`,
+                    // so any letters are collected and left for the `Parser`
+                    // to sort out.
+                    while index < chunk.len() {
+                        match chunk[index] {
+                            b'0' ... b'9' | b'.' | b'e' | b'E' | b'+' | b'-' => index += 1,
+                            b'a' ... b'z' | b'A' ... b'Z' if relaxed => index += 1,
+                            _ => break
+                        }
+                    }
+
+                    self.token.extend_from_slice(&chunk[start .. index]);
+
+                    if index < chunk.len() {
+                        self.advance(&chunk[synced .. index]);
+                        synced = index;
+                        try!(self.end_number(Some((chunk, index))));
+                    }
+                    continue;
+                },
+                State::Literal { word, matched } => {
+                    if chunk[index] != word[matched] {
+                        self.advance(&chunk[synced .. index]);
+                        return self.unexpected_character(chunk, index);
+                    }
+
+                    index += 1;
+
+                    if matched + 1 == word.len() {
+                        self.push_value(match word[0] {
+                            b't' => JsonValue::Boolean(true),
+                            b'f' => JsonValue::Boolean(false),
+                            _    => JsonValue::Null,
+                        });
+                    } else {
+                        self.state = State::Literal { word: word, matched: matched + 1 };
+                    }
+                    continue;
+                },
+                State::CommentStart => {
+                    self.state = match chunk[index] {
+                        b'/' => State::LineComment,
+                        b'*' => State::BlockComment { star: false },
+                        _    => {
+                            self.advance(&chunk[synced .. index]);
+                            return self.unexpected_character(chunk, index);
+                        }
+                    };
+                    index += 1;
+                    continue;
+                },
+                State::LineComment => {
+                    if chunk[index] == b'\n' {
+                        self.state = self.resume;
+                    }
+                    index += 1;
+                    continue;
+                },
+                State::BlockComment { star } => {
+                    let ch = chunk[index];
+
+                    self.state = if star && ch == b'/' {
+                        self.resume
+                    } else {
+                        State::BlockComment { star: ch == b'*' }
+                    };
+                    index += 1;
+                    continue;
+                },
+                _ => {}
+            }
+
+            let ch = chunk[index];
+
+            if let 9 ... 13 | 32 = ch {
+                index += 1;
+                continue;
+            }
+
+            if ch == b'/' && self.options.relaxed {
+                self.resume = self.state;
+                self.state = State::CommentStart;
+                index += 1;
+                continue;
+            }
+
+            self.advance(&chunk[synced .. index]);
+            synced = index;
+
+            match (self.state, ch) {
+                (State::ArrayStart, b']')  => try!(self.close(chunk, index)),
+                (State::ArrayStart, _)     => {
+                    if self.stack.len() > self.options.depth_limit {
+                        return Err(Error::ExceededDepthLimit);
+                    }
+                    if self.options.array_length_limit == 0 {
+                        return Err(Error::ExceededArrayLengthLimit);
+                    }
+                    try!(self.begin_value(chunk, index));
+                },
+                // Trailing commas in relaxed mode. `close` makes sure the
+                // bracket matches the container.
+                (State::Value, b']')       |
+                (State::Key, b'}') if self.options.relaxed => try!(self.close(chunk, index)),
+                (State::Value, _)          => try!(self.begin_value(chunk, index)),
+                (State::ObjectStart, b'}') => try!(self.close(chunk, index)),
+                (State::ObjectStart, _)    => {
+                    if self.stack.len() > self.options.depth_limit {
+                        return Err(Error::ExceededDepthLimit);
+                    }
+                    if self.options.object_length_limit == 0 {
+                        return Err(Error::ExceededObjectLengthLimit);
+                    }
+                    try!(self.begin_key(chunk, index));
+                },
+                (State::Key, _)            => try!(self.begin_key(chunk, index)),
+                (State::Colon, b':')       => self.state = State::Value,
+                (State::Separator, b',')   => {
+                    self.state = match self.stack.last() {
+                        Some(&StackBlock::Object(ref object, _)) => {
+                            if object.len() >= self.options.object_length_limit {
+                                return Err(Error::ExceededObjectLengthLimit);
+                            }
+                            State::Key
+                        },
+                        Some(&StackBlock::Array(ref array)) => {
+                            if array.len() >= self.options.array_length_limit {
+                                return Err(Error::ExceededArrayLengthLimit);
+                            }
+                            State::Value
+                        },
+                        None => State::Value,
+                    };
+                },
+                (State::Separator, b']')   |
+                (State::Separator, b'}')   => try!(self.close(chunk, index)),
+                _                          => return self.unexpected_character(chunk, index),
+            }
+
+            index += 1;
+        }
+
+        self.advance(&chunk[synced ..]);
+
+        Ok(())
+    }
+
+    /// Signal that there are no more chunks to be fed, and obtain the
+    /// parsed value. Will return an error if the source was incomplete.
+    pub fn finish(mut self) -> Result<JsonValue> {
+        if let Some(mut transcoder) = self.transcoder.take() {
+            let mut decoded = Vec::new();
+
+            try!(transcoder.finish(&mut decoded));
+            try!(self.feed_decoded(&decoded));
+        }
+
+        if self.state == State::LineComment {
+            self.state = self.resume;
+        }
+
+        if self.state == State::Number {
+            try!(self.end_number(None));
+        }
+
+        match self.state {
+            State::Done => Ok(self.root.take().expect("Must have a root value")),
+            _           => Err(Error::UnexpectedEndOfJson),
+        }
+    }
+
+    // Keep track of lines and columns of bytes that have been read.
+    fn advance(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            if byte == b'\n' {
+                self.line += 1;
+                self.column = 1;
+            } else if byte & 0xC0 != 0x80 {
+                // Only count first bytes of UTF-8 sequences
+                self.column += 1;
+            }
+        }
+    }
+
+    // Start reading a new scalar token at the current position.
+    fn begin_token(&mut self, ch: u8) {
+        self.token.clear();
+        self.token.push(ch);
+        self.token_line = self.line;
+        self.token_column = self.column;
+    }
+
+    // Start reading an object key, which has to be a string, unless we
+    // are in relaxed mode.
+    fn begin_key(&mut self, chunk: &[u8], index: usize) -> Result<()> {
+        let ch = chunk[index];
+
+        self.state = match ch {
+            b'"' => State::String { key: true, quote: ch, escape: false },
+            b'\'' if self.options.relaxed => {
+                State::String { key: true, quote: ch, escape: false }
+            },
+            b'a' ... b'z' | b'A' ... b'Z' | b'_' | b'$' if self.options.relaxed => {
+                State::Identifier
+            },
+            _ => return self.unexpected_character(chunk, index),
+        };
+
+        self.begin_token(ch);
+
+        Ok(())
+    }
+
+    fn begin_value(&mut self, chunk: &[u8], index: usize) -> Result<()> {
+        let ch = chunk[index];
+        let relaxed = self.options.relaxed;
+
+        self.state = match ch {
+            b'[' => {
+                self.stack.push(StackBlock::Array(Vec::with_capacity(2)));
+                State::ArrayStart
+            },
+            b'{' => {
+                // The slot is set once the first key is read
+                self.stack.push(StackBlock::Object(Object::with_capacity(3), Slot::Discard));
+                State::ObjectStart
+            },
+            b'"' => {
+                self.begin_token(ch);
+                State::String { key: false, quote: ch, escape: false }
+            },
+            b'\'' if relaxed => {
+                self.begin_token(ch);
+                State::String { key: false, quote: ch, escape: false }
+            },
+            b'-' | b'0' ... b'9' => {
+                self.begin_token(ch);
+                State::Number
+            },
+            b'+' | b'.' | b'I' | b'N' if relaxed => {
+                self.begin_token(ch);
+                State::Number
+            },
+            b't' => State::Literal { word: b"true", matched: 1 },
+            b'f' => State::Literal { word: b"false", matched: 1 },
+            b'n' => State::Literal { word: b"null", matched: 1 },
+            _    => return self.unexpected_character(chunk, index),
+        };
+
+        Ok(())
+    }
+
+    // Put a complete value into the container on top of the stack, or
+    // make it the root value if the stack is empty.
+    fn push_value(&mut self, value: JsonValue) {
+        self.state = State::Separator;
+
+        match self.stack.last_mut() {
+            Some(&mut StackBlock::Array(ref mut array))   => array.push(value),
+            Some(&mut StackBlock::Object(ref mut object, slot)) => put_value(object, slot, value),
+            None => {
+                self.root = Some(value);
+                self.state = State::Done;
+            }
+        }
+    }
+
+    // Close the container on top of the stack, making sure the closing
+    // bracket at `index` matches it.
+    fn close(&mut self, chunk: &[u8], index: usize) -> Result<()> {
+        let value = match (self.stack.pop(), chunk[index]) {
+            (Some(StackBlock::Array(array)), b']')   => JsonValue::Array(array),
+            (Some(StackBlock::Object(object, _)), b'}') => {
+                forget_collected(&mut self.collected, self.stack.len());
+                JsonValue::Object(object)
+            },
+            (block, _) => {
+                if let Some(block) = block {
+                    self.stack.push(block);
+                }
+                return self.unexpected_character(chunk, index);
+            }
+        };
+
+        self.push_value(value);
+
+        Ok(())
+    }
+
+    fn end_string(&mut self, key: bool) -> Result<()> {
+        let value = try!(self.parse_token().map_err(|err| self.token_error(err)));
+
+        if key {
+            // Keys have lone surrogates replaced, see `LoneSurrogates::Preserve`
+            match value {
+                JsonValue::Wtf8(ref key) => try!(self.insert_key(&key.to_string_lossy())),
+                ref value                => try!(self.insert_key(value.as_str().expect("Must be a string"))),
+            }
+        } else {
+            self.push_value(value);
+        }
+
+        Ok(())
+    }
+
+    // Identifiers end on the first byte that can't be a part of them, which
+    // is left to be read as usual.
+    fn end_identifier(&mut self) -> Result<()> {
+        let token = mem::replace(&mut self.token, Vec::new());
+
+        // Identifiers are ASCII only, so this is safe.
+        let result = self.insert_key(unsafe { str::from_utf8_unchecked(&token) });
+
+        self.token = token;
+
+        result
+    }
+
+    // Insert a key into the object on top of the stack, following the
+    // duplicate key policy.
+    fn insert_key(&mut self, key: &str) -> Result<()> {
+        let depth = self.stack.len() - 1;
+
+        if let Some(&mut StackBlock::Object(ref mut object, ref mut slot)) = self.stack.last_mut() {
+            *slot = match insert_key(&self.options, &mut self.collected, object, Text::Transient(key), depth) {
+                Some(slot) => slot,
+                None       => return Err(Error::DuplicateKey {
+                    key: key.into(),
+                    line: self.token_line,
+                    column: self.token_column,
+                }),
+            };
+        }
+
+        self.state = State::Colon;
+
+        Ok(())
+    }
+
+    // Numbers don't have a closing character, so they end either on the
+    // first byte that can't be a part of a number (`terminator`), or when
+    // the source is finished.
+    fn end_number(&mut self, terminator: Option<(&[u8], usize)>) -> Result<()> {
+        match self.parse_token() {
+            Ok(value) => {
+                self.push_value(value);
+                Ok(())
+            },
+            Err(Error::UnexpectedEndOfJson) => match terminator {
+                Some((chunk, index)) => self.unexpected_character(chunk, index),
+                None                 => Err(Error::UnexpectedEndOfJson),
+            },
+            Err(err) => Err(self.token_error(err)),
+        }
+    }
+
+    fn parse_token(&mut self) -> Result<JsonValue> {
+        let buffer = mem::replace(&mut self.buffer, Vec::new());
+        let mut parser = Parser::with_buffer(&self.token, buffer).with_options(self.options);
+        let result = parser.parse();
+
+        self.buffer = parser.buffer;
+
+        result
+    }
+
+    // Errors from parsing a token have positions relative to the token,
+    // translate them to positions in the entire source.
+    fn token_error(&self, err: Error) -> Error {
+        match err {
+            Error::UnexpectedCharacter { ch, line, column } => Error::UnexpectedCharacter {
+                ch: ch,
+                line: self.token_line + line - 1,
+                column: if line == 1 { self.token_column + column - 1 } else { column },
+            },
+            err => err
+        }
+    }
+
+    // The `line` and `column` have to be up to date with `index` here.
+    fn unexpected_character<T: Sized>(&self, chunk: &[u8], index: usize) -> Result<T> {
+        Err(Error::UnexpectedCharacter {
+            ch: char_at(chunk, index),
+            line: self.line,
+            column: self.column,
+        })
+    }
+}
@@ -0,0 +1,332 @@
+// Pull parsing
+// ============
+//
+// Sometimes you don't want the whole tree, just a few values out of a huge
+// document. The `EventReader` walks the source with the very same macros as
+// the regular parser, but instead of building `JsonValue`s it hands out
+// events one at a time. Strings without escapes are sliced straight from
+// the source, and the only allocation on the happy path is the stack of
+// open containers.
+
+use std::{ str, slice };
+use number::Number;
+use { Error, Result };
+use super::{ Parser, ParserOptions, MAX_PRECISION };
+
+/// A single event produced by the `EventReader`.
+///
+/// String slices in `Key` and `String` events are only valid until the
+/// next event is read.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Event<'a> {
+    StartObject,
+    EndObject,
+    StartArray,
+    EndArray,
+    Key(&'a str),
+    String(&'a str),
+    Number(Number),
+    Boolean(bool),
+    Null,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Container {
+    Array,
+    Object,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    // Expecting any value.
+    Value,
+
+    // Just read `[`, expecting a value or `]`.
+    ArrayStart,
+
+    // Just read `{`, expecting a key or `}`.
+    ObjectStart,
+
+    // Just read a value inside a container, expecting `,` or a
+    // closing bracket.
+    Separator,
+
+    // The root value is complete.
+    Done,
+}
+
+/// Streaming reader that produces `Event`s from the source without
+/// ever building a `JsonValue` tree.
+///
+/// ```
+/// use json::{ EventReader, Event };
+///
+/// let mut reader = EventReader::new(r#"{"id":42,"tags":["a","b"]}"#);
+///
+/// assert_eq!(reader.next_event().unwrap(), Some(Event::StartObject));
+/// assert_eq!(reader.next_event().unwrap(), Some(Event::Key("id")));
+/// assert_eq!(reader.next_event().unwrap(), Some(Event::Number(42.into())));
+/// assert_eq!(reader.next_event().unwrap(), Some(Event::Key("tags")));
+/// assert_eq!(reader.next_event().unwrap(), Some(Event::StartArray));
+/// assert_eq!(reader.next_event().unwrap(), Some(Event::String("a")));
+/// assert_eq!(reader.next_event().unwrap(), Some(Event::String("b")));
+/// assert_eq!(reader.next_event().unwrap(), Some(Event::EndArray));
+/// assert_eq!(reader.next_event().unwrap(), Some(Event::EndObject));
+/// assert_eq!(reader.next_event().unwrap(), None);
+/// ```
+pub struct EventReader<'a> {
+    parser: Parser<'a>,
+
+    // Open containers, along with the number of members read so far
+    stack: Vec<(Container, usize)>,
+
+    state: State,
+
+    // Byte offsets of the last event read
+    start: usize,
+    end: usize,
+}
+
+impl<'a> EventReader<'a> {
+    /// Create a new `EventReader` for the source.
+    pub fn new(source: &'a str) -> Self {
+        EventReader::with_options(source, ParserOptions::new())
+    }
+
+    /// Create a new `EventReader` for the source with custom `ParserOptions`.
+    pub fn with_options(source: &'a str, options: ParserOptions) -> Self {
+        EventReader {
+            parser: Parser::new(source).with_options(options),
+            stack: Vec::with_capacity(3),
+            state: State::Value,
+            start: 0,
+            end: 0,
+        }
+    }
+
+    /// Byte offsets of the start and the end (exclusive) of the last event
+    /// read. Keys span the quoted string only, without the colon.
+    ///
+    /// ```
+    /// use json::{ EventReader, Event };
+    ///
+    /// let mut reader = EventReader::new(r#"[1, "two"]"#);
+    ///
+    /// reader.next_event().unwrap();
+    /// reader.next_event().unwrap();
+    ///
+    /// assert_eq!(reader.next_event().unwrap(), Some(Event::String("two")));
+    /// assert_eq!(reader.offsets(), (4, 9));
+    /// ```
+    pub fn offsets(&self) -> (usize, usize) {
+        (self.start, self.end)
+    }
+
+    /// Read the next event. Will return `None` once the root value has
+    /// been read entirely, and only whitespace remains in the source.
+    pub fn next_event(&mut self) -> Result<Option<Event>> {
+        let event = {
+            let parser = &mut self.parser;
+
+            match self.state {
+                State::Done => return Ok(None),
+
+                State::Value => {
+                    if self.stack.is_empty() && parser.length > parser.options.size_limit {
+                        return Err(Error::ExceededSizeLimit);
+                    }
+
+                    let ch = expect_byte_ignore_whitespace!(parser);
+                    self.start = parser.index - 1;
+                    try!(read_value(parser, &mut self.stack, &mut self.state, ch))
+                },
+
+                State::ArrayStart => {
+                    let ch = expect_byte_ignore_whitespace!(parser);
+                    self.start = parser.index - 1;
+
+                    if ch == b']' {
+                        self.stack.pop();
+                        Event::EndArray
+                    } else {
+                        if self.stack.len() > parser.options.depth_limit {
+                            return Err(Error::ExceededDepthLimit);
+                        }
+                        try!(count_member(parser, &mut self.stack));
+                        try!(read_value(parser, &mut self.stack, &mut self.state, ch))
+                    }
+                },
+
+                State::ObjectStart => {
+                    let ch = expect_byte_ignore_whitespace!(parser);
+                    self.start = parser.index - 1;
+
+                    match ch {
+                        b'}' => {
+                            self.stack.pop();
+                            Event::EndObject
+                        },
+                        _ => {
+                            if self.stack.len() > parser.options.depth_limit {
+                                return Err(Error::ExceededDepthLimit);
+                            }
+                            try!(count_member(parser, &mut self.stack));
+                            let key = expect_key!(parser, ch);
+                            self.end = parser.index;
+                            expect!(parser, b':');
+                            self.state = State::Value;
+                            return Ok(Some(Event::Key(key)));
+                        }
+                    }
+                },
+
+                State::Separator => {
+                    let ch = expect_byte_ignore_whitespace!(parser);
+                    self.start = parser.index - 1;
+
+                    let container = self.stack.last().map(|&(container, _)| container);
+
+                    match (container, ch) {
+                        (Some(Container::Array), b',') => {
+                            let ch = expect_byte_ignore_whitespace!(parser);
+                            self.start = parser.index - 1;
+
+                            // Trailing comma in relaxed mode
+                            if ch == b']' && parser.options.relaxed {
+                                self.stack.pop();
+                                Event::EndArray
+                            } else {
+                                try!(count_member(parser, &mut self.stack));
+                                try!(read_value(parser, &mut self.stack, &mut self.state, ch))
+                            }
+                        },
+                        (Some(Container::Array), b']') => {
+                            self.stack.pop();
+                            Event::EndArray
+                        },
+                        (Some(Container::Object), b',') => {
+                            let ch = expect_byte_ignore_whitespace!(parser);
+                            self.start = parser.index - 1;
+
+                            // Trailing comma in relaxed mode
+                            if ch == b'}' && parser.options.relaxed {
+                                self.stack.pop();
+                                Event::EndObject
+                            } else {
+                                try!(count_member(parser, &mut self.stack));
+                                let key = expect_key!(parser, ch);
+                                self.end = parser.index;
+                                expect!(parser, b':');
+                                self.state = State::Value;
+                                return Ok(Some(Event::Key(key)));
+                            }
+                        },
+                        (Some(Container::Object), b'}') => {
+                            self.stack.pop();
+                            Event::EndObject
+                        },
+                        _ => return parser.unexpected_character()
+                    }
+                },
+            }
+        };
+
+        self.end = self.parser.index;
+
+        // Opening a container sets the state on its own, everything else
+        // is a complete value.
+        match event {
+            Event::StartArray | Event::StartObject => {},
+            _ => {
+                if self.stack.is_empty() {
+                    let parser = &mut self.parser;
+                    expect_eof!(parser);
+                    self.state = State::Done;
+                } else {
+                    self.state = State::Separator;
+                }
+            }
+        }
+
+        Ok(Some(event))
+    }
+}
+
+// Count another member of the container on top of the stack, making sure
+// it doesn't exceed the length limits.
+fn count_member(parser: &Parser, stack: &mut Vec<(Container, usize)>) -> Result<()> {
+    if let Some(&mut (container, ref mut count)) = stack.last_mut() {
+        let (limit, error) = match container {
+            Container::Array  => (parser.options.array_length_limit, Error::ExceededArrayLengthLimit),
+            Container::Object => (parser.options.object_length_limit, Error::ExceededObjectLengthLimit),
+        };
+
+        if *count >= limit {
+            return Err(error);
+        }
+
+        *count += 1;
+    }
+
+    Ok(())
+}
+
+// Read a value starting with `ch`. Containers are only opened here, closing
+// them is up to the caller.
+fn read_value<'b>(
+    parser: &mut Parser,
+    stack: &mut Vec<(Container, usize)>,
+    state: &mut State,
+    ch: u8
+) -> Result<Event<'b>> {
+    Ok(match ch {
+        b'[' => {
+            stack.push((Container::Array, 0));
+            *state = State::ArrayStart;
+            Event::StartArray
+        },
+        b'{' => {
+            stack.push((Container::Object, 0));
+            *state = State::ObjectStart;
+            Event::StartObject
+        },
+        b'"' => Event::String(expect_string!(parser)),
+        b'0' if parser.options.relaxed => {
+            Event::Number(try!(parser.read_relaxed_number(ch)))
+        },
+        b'0' => Event::Number(allow_number_extensions!(parser)),
+        b'1' ... b'9' => Event::Number(expect_number!(parser, ch)),
+        b'-' => {
+            let ch = expect_byte!(parser);
+            Event::Number(- match ch {
+                _ if parser.options.relaxed => try!(parser.read_relaxed_number(ch)),
+                b'0' => allow_number_extensions!(parser),
+                b'1' ... b'9' => expect_number!(parser, ch),
+                _    => return parser.unexpected_character()
+            })
+        },
+        b'+' if parser.options.relaxed => {
+            let ch = expect_byte!(parser);
+            Event::Number(try!(parser.read_relaxed_number(ch)))
+        },
+        b'.' | b'I' | b'N' if parser.options.relaxed => {
+            Event::Number(try!(parser.read_relaxed_number(ch)))
+        },
+        b'\'' if parser.options.relaxed => {
+            Event::String(try!(parser.read_single_quoted_string()))
+        },
+        b't' => {
+            expect_sequence!(parser, b'r', b'u', b'e');
+            Event::Boolean(true)
+        },
+        b'f' => {
+            expect_sequence!(parser, b'a', b'l', b's', b'e');
+            Event::Boolean(false)
+        },
+        b'n' => {
+            expect_sequence!(parser, b'u', b'l', b'l');
+            Event::Null
+        },
+        _    => return parser.unexpected_character()
+    })
+}
@@ -0,0 +1,661 @@
+// Recovering parser
+// =================
+//
+// Editors want to show every problem in a document at once, and still
+// make some sense of the rest of it. This is a separate, forgiving pass
+// that never fails: whatever it can't make sense of is reported as a
+// `Diagnostic` and skipped, and missing pieces are assumed to be there.
+//
+// Containers are parsed by recursive descent, which is bounded by the
+// depth limit. Strings and numbers are handed over to the regular parser,
+// so anything valid comes out exactly as `json::parse` would have it.
+
+use std::{ cmp, fmt };
+use object::Object;
+use JsonValue;
+use super::{ Parser, ParserOptions, Span, Position, Slot, char_at, DEPTH_LIMIT };
+use super::{ Text, insert_key, put_value, forget_collected };
+
+/// Kind of a problem found by `json::parse_recovering`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    /// Character that doesn't belong where it is. It is skipped, along
+    /// with any other such characters right after it.
+    UnexpectedCharacter(char),
+
+    /// The source has ended before a value has started.
+    UnexpectedEndOfJson,
+
+    /// Word that is neither `true`, `false` nor `null`, read as `null`.
+    InvalidLiteral,
+
+    /// Malformed number. Whatever leading part of it is a number is kept,
+    /// otherwise it's read as `null`.
+    InvalidNumber,
+
+    /// String with an invalid escape or a control character in it, read
+    /// as it is written in the source.
+    InvalidString,
+
+    /// String that isn't closed before the end of the line.
+    UnclosedString,
+
+    /// Array that isn't closed, pointing at its opening bracket.
+    UnclosedArray,
+
+    /// Object that isn't closed, pointing at its opening brace.
+    UnclosedObject,
+
+    /// Object key that isn't in quotes, used as it is.
+    UnquotedKey,
+
+    /// Repeated object key, with `DuplicateKeys::Error` only. The value
+    /// of the first occurrence is kept.
+    DuplicateKey(String),
+
+    /// Two values, or object members, with no comma in between.
+    MissingComma,
+
+    /// Object key with no colon after it.
+    MissingColon,
+
+    /// Comma or colon with no value after it.
+    MissingValue,
+
+    /// Comma right before the end of an array or an object.
+    TrailingComma,
+
+    /// Array or object nested deeper than the depth limit, read as `null`.
+    ExceededDepthLimit,
+}
+
+impl fmt::Display for DiagnosticKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::DiagnosticKind::*;
+
+        match *self {
+            UnexpectedCharacter(ch) => write!(f, "Unexpected character: {}", ch),
+            UnexpectedEndOfJson     => write!(f, "Unexpected end of JSON"),
+            InvalidLiteral          => write!(f, "Invalid literal"),
+            InvalidNumber           => write!(f, "Invalid number"),
+            InvalidString           => write!(f, "Invalid string"),
+            UnclosedString          => write!(f, "Unclosed string"),
+            UnclosedArray           => write!(f, "Unclosed array"),
+            UnclosedObject          => write!(f, "Unclosed object"),
+            UnquotedKey             => write!(f, "Unquoted key"),
+            DuplicateKey(ref key)   => write!(f, "Duplicate key: {:?}", key),
+            MissingComma            => write!(f, "Missing comma"),
+            MissingColon            => write!(f, "Missing colon"),
+            MissingValue            => write!(f, "Missing value"),
+            TrailingComma           => write!(f, "Trailing comma"),
+            ExceededDepthLimit      => write!(f, "Exceeded depth limit"),
+        }
+    }
+}
+
+/// A problem found by `json::parse_recovering`, along with the part of the
+/// source it is about. Things that are missing have an empty span, right
+/// where they were expected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    pub span: Span,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at ({}:{})", self.kind, self.span.start.line, self.span.start.column)
+    }
+}
+
+struct Recovering<'a> {
+    source: &'a str,
+    index: usize,
+
+    // Strings and numbers are read by this one
+    parser: Parser<'a>,
+
+    options: ParserOptions,
+    collected: Vec<(usize, usize)>,
+
+    // Closing brackets of the containers we are in
+    closers: Vec<u8>,
+
+    // Diagnostics with byte offsets, turned into positions at the end
+    found: Vec<(DiagnosticKind, usize, usize)>,
+}
+
+impl<'a> Recovering<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.source.as_bytes().get(self.index).cloned()
+    }
+
+    fn report(&mut self, kind: DiagnosticKind, start: usize, end: usize) {
+        self.found.push((kind, start, end));
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r') = self.peek() {
+            self.index += 1;
+        }
+    }
+
+    // Skip over a run of characters that can't be made sense of.
+    fn skip_stray(&mut self) {
+        let start = self.index;
+
+        self.index += char_at(self.source.as_bytes(), start).len_utf8();
+
+        while self.peek().map_or(false, is_stray) {
+            self.index += 1;
+        }
+
+        let ch = char_at(self.source.as_bytes(), start);
+
+        self.report(DiagnosticKind::UnexpectedCharacter(ch), start, self.index);
+    }
+
+    // Is `ch` closing one of the containers around the current one?
+    fn closes_outer(&self, ch: u8) -> bool {
+        let outer = self.closers.len().saturating_sub(1);
+
+        self.closers[.. outer].contains(&ch)
+    }
+
+    // Read a value at the current index, or return `None` without moving
+    // if there is none to be read.
+    fn value(&mut self) -> Option<JsonValue> {
+        let ch = match self.peek() {
+            Some(ch) => ch,
+            None     => return None,
+        };
+
+        Some(match ch {
+            b'['                     => self.container(b']'),
+            b'{'                     => self.container(b'}'),
+            b'"'                     => self.string(),
+            b'-' | b'0' ... b'9'     => self.number(),
+            ch if is_word_start(ch)  => {
+                let start = self.index;
+
+                match self.word() {
+                    "true"  => true.into(),
+                    "false" => false.into(),
+                    "null"  => JsonValue::Null,
+                    _       => {
+                        self.report(DiagnosticKind::InvalidLiteral, start, self.index);
+                        JsonValue::Null
+                    }
+                }
+            },
+            _ => return None,
+        })
+    }
+
+    fn word(&mut self) -> &'a str {
+        let start = self.index;
+
+        while self.peek().map_or(false, is_word) {
+            self.index += 1;
+        }
+
+        &self.source[start .. self.index]
+    }
+
+    // Find the end of the string starting at the current index, which is
+    // either its closing quote, or the end of the line.
+    fn string_end(&self) -> (usize, bool) {
+        let bytes = self.source.as_bytes();
+        let mut end = self.index + 1;
+
+        while end < bytes.len() {
+            match bytes[end] {
+                b'"'          => return (end + 1, true),
+                b'\n' | b'\r' => break,
+                b'\\' if end + 1 < bytes.len() && bytes[end + 1] != b'\n' => end += 2,
+                _             => end += 1,
+            }
+        }
+
+        (cmp::min(end, bytes.len()), false)
+    }
+
+    fn string(&mut self) -> JsonValue {
+        let start = self.index;
+        let (end, closed) = self.string_end();
+
+        self.index = end;
+
+        if closed {
+            self.parser.index = start;
+
+            if let Ok(value) = self.parser.parse_value() {
+                return value;
+            }
+
+            self.report(DiagnosticKind::InvalidString, start, end);
+
+            return self.source[start + 1 .. end - 1].into();
+        }
+
+        self.report(DiagnosticKind::UnclosedString, start, end);
+
+        let mut closed = String::with_capacity(end - start + 1);
+
+        closed.push_str(&self.source[start .. end]);
+        closed.push('"');
+
+        match Parser::new(&closed).parse() {
+            Ok(value) => value,
+            Err(_)    => {
+                self.report(DiagnosticKind::InvalidString, start, end);
+
+                self.source[start + 1 .. end].into()
+            }
+        }
+    }
+
+    fn number(&mut self) -> JsonValue {
+        let start = self.index;
+
+        self.index += 1;
+
+        loop {
+            match self.peek() {
+                Some(b'0' ... b'9') | Some(b'.') | Some(b'+') | Some(b'-') => self.index += 1,
+
+                // The exponent marker, along with the sign that can follow
+                Some(b'e') | Some(b'E') => {
+                    self.index += 1;
+
+                    match self.peek() {
+                        Some(b'+') | Some(b'-') => self.index += 1,
+                        _                       => {},
+                    }
+                },
+                _ => break,
+            }
+        }
+
+        // Take whatever letters follow along, so that `12px` is a single
+        // invalid number, rather than a number followed by garbage.
+        self.word();
+
+        self.parser.index = start;
+
+        match self.parser.parse_value() {
+            Ok(value) => {
+                if self.parser.index != self.index {
+                    self.report(DiagnosticKind::InvalidNumber, start, self.index);
+                }
+                value
+            },
+            Err(_) => {
+                self.report(DiagnosticKind::InvalidNumber, start, self.index);
+                JsonValue::Null
+            }
+        }
+    }
+
+    // Parse an array or an object, of which `closer` is the closing bracket.
+    fn container(&mut self, closer: u8) -> JsonValue {
+        let open = self.index;
+
+        self.index += 1;
+        self.skip_whitespace();
+
+        // Empty containers don't count towards the depth
+        if self.peek() == Some(closer) {
+            self.index += 1;
+
+            return if closer == b']' {
+                JsonValue::Array(Vec::new())
+            } else {
+                JsonValue::Object(Object::new())
+            };
+        }
+
+        if self.closers.len() >= self.options.depth_limit {
+            self.skip_container();
+            self.report(DiagnosticKind::ExceededDepthLimit, open, self.index);
+
+            return JsonValue::Null;
+        }
+
+        self.closers.push(closer);
+
+        let value = if closer == b']' {
+            self.array(open)
+        } else {
+            self.object(open)
+        };
+
+        self.closers.pop();
+
+        value
+    }
+
+    fn array(&mut self, open: usize) -> JsonValue {
+        let mut array = Vec::with_capacity(2);
+
+        // Offset of the last comma, unless there was a value after it
+        let mut comma = None;
+        let mut after_value = false;
+        let mut last_end = open + 1;
+
+        loop {
+            self.skip_whitespace();
+
+            match self.peek() {
+                None => {
+                    self.report(DiagnosticKind::UnclosedArray, open, open + 1);
+                    break;
+                },
+                Some(b']') => {
+                    if let Some(at) = comma {
+                        self.report(DiagnosticKind::TrailingComma, at, at + 1);
+                    }
+                    self.index += 1;
+                    break;
+                },
+                Some(b'}') if self.closes_outer(b'}') => {
+                    self.report(DiagnosticKind::UnclosedArray, open, open + 1);
+                    break;
+                },
+                Some(b',') => {
+                    if !after_value {
+                        self.report(DiagnosticKind::MissingValue, self.index, self.index);
+                    }
+                    comma = Some(self.index);
+                    after_value = false;
+                    self.index += 1;
+                },
+                Some(_) => {
+                    let start = self.index;
+
+                    match self.value() {
+                        Some(value) => {
+                            if after_value {
+                                self.report(DiagnosticKind::MissingComma, last_end, last_end);
+                            }
+
+                            array.push(value);
+                            comma = None;
+                            after_value = true;
+                            last_end = self.index;
+                        },
+                        None => {
+                            debug_assert_eq!(start, self.index);
+                            self.skip_stray();
+                        }
+                    }
+                }
+            }
+        }
+
+        JsonValue::Array(array)
+    }
+
+    fn object(&mut self, open: usize) -> JsonValue {
+        let mut object = Object::with_capacity(3);
+        let depth = self.closers.len();
+
+        let mut comma = None;
+        let mut after_value = false;
+        let mut last_end = open + 1;
+
+        loop {
+            self.skip_whitespace();
+
+            let ch = match self.peek() {
+                Some(ch) => ch,
+                None     => {
+                    self.report(DiagnosticKind::UnclosedObject, open, open + 1);
+                    break;
+                }
+            };
+
+            match ch {
+                b'}' => {
+                    if let Some(at) = comma {
+                        self.report(DiagnosticKind::TrailingComma, at, at + 1);
+                    }
+                    self.index += 1;
+                    break;
+                },
+                b']' if self.closes_outer(b']') => {
+                    self.report(DiagnosticKind::UnclosedObject, open, open + 1);
+                    break;
+                },
+                b',' => {
+                    if !after_value {
+                        self.report(DiagnosticKind::MissingValue, self.index, self.index);
+                    }
+                    comma = Some(self.index);
+                    after_value = false;
+                    self.index += 1;
+                },
+                b'"' | b'0' ... b'9' | b'A' ... b'Z' | b'a' ... b'z' | b'_' | b'$' => {
+                    if after_value {
+                        self.report(DiagnosticKind::MissingComma, last_end, last_end);
+                    }
+
+                    self.member(&mut object, depth);
+
+                    comma = None;
+                    after_value = true;
+                    last_end = self.index;
+                },
+                _ => self.skip_stray(),
+            }
+        }
+
+        forget_collected(&mut self.collected, depth);
+
+        JsonValue::Object(object)
+    }
+
+    // Parse a key and its value into the object.
+    fn member(&mut self, object: &mut Object, depth: usize) {
+        let key_start = self.index;
+
+        let key = if self.peek() == Some(b'"') {
+            let key = self.string();
+
+            key.as_str().unwrap_or("").to_owned()
+        } else {
+            let key = self.word();
+
+            self.report(DiagnosticKind::UnquotedKey, key_start, self.index);
+            key.into()
+        };
+
+        let key_end = self.index;
+
+        let slot = match insert_key(&self.options, &mut self.collected, object, Text::Transient(&key), depth) {
+            Some(slot) => slot,
+            None       => {
+                self.report(DiagnosticKind::DuplicateKey(key), key_start, key_end);
+                Slot::Discard
+            }
+        };
+
+        self.skip_whitespace();
+
+        if self.peek() == Some(b':') {
+            self.index += 1;
+            self.skip_whitespace();
+        } else {
+            self.report(DiagnosticKind::MissingColon, key_end, key_end);
+        }
+
+        let value = match self.value() {
+            Some(value) => value,
+            None        => {
+                self.report(DiagnosticKind::MissingValue, self.index, self.index);
+                JsonValue::Null
+            }
+        };
+
+        put_value(object, slot, value);
+    }
+
+    // Skip to the end of a container that is too deep, without building
+    // anything out of it.
+    fn skip_container(&mut self) {
+        let bytes = self.source.as_bytes();
+        let mut depth = 1;
+
+        while self.index < bytes.len() {
+            match bytes[self.index] {
+                b'[' | b'{' => depth += 1,
+                b']' | b'}' => {
+                    depth -= 1;
+
+                    if depth == 0 {
+                        self.index += 1;
+                        return;
+                    }
+                },
+                b'"' => {
+                    self.index = self.string_end().0;
+                    continue;
+                },
+                _ => {},
+            }
+
+            self.index += 1;
+        }
+    }
+
+    fn parse(mut self) -> (JsonValue, Vec<Diagnostic>) {
+        let mut root = None;
+
+        loop {
+            self.skip_whitespace();
+
+            if self.peek().is_none() {
+                break;
+            }
+
+            if root.is_some() {
+                // Everything after the root value is reported at once
+                let start = self.index;
+                let ch = char_at(self.source.as_bytes(), start);
+                let end = self.source.trim_right().len();
+
+                self.report(DiagnosticKind::UnexpectedCharacter(ch), start, end);
+                break;
+            }
+
+            root = self.value();
+
+            if root.is_none() {
+                self.skip_stray();
+            }
+        }
+
+        let root = match root {
+            Some(root) => root,
+            None       => {
+                let end = self.source.len();
+
+                self.report(DiagnosticKind::UnexpectedEndOfJson, end, end);
+                JsonValue::Null
+            }
+        };
+
+        let lines = Lines::new(self.source.as_bytes());
+
+        let diagnostics = self.found.into_iter().map(|(kind, start, end)| {
+            Diagnostic {
+                kind: kind,
+                span: Span {
+                    start: lines.position(start),
+                    end: lines.position(end),
+                },
+            }
+        }).collect();
+
+        (root, diagnostics)
+    }
+}
+
+// Offsets of line starts, to find positions in any order.
+struct Lines<'a> {
+    source: &'a [u8],
+    starts: Vec<usize>,
+}
+
+impl<'a> Lines<'a> {
+    fn new(source: &'a [u8]) -> Self {
+        let mut starts = vec![0];
+
+        starts.extend(source.iter().enumerate().filter(|&(_, &ch)| ch == b'\n').map(|(at, _)| at + 1));
+
+        Lines {
+            source: source,
+            starts: starts,
+        }
+    }
+
+    fn position(&self, offset: usize) -> Position {
+        let line = match self.starts.binary_search(&offset) {
+            Ok(line)  => line,
+            Err(line) => line - 1,
+        };
+
+        // Only count first bytes of UTF-8 sequences
+        let column = self.source[self.starts[line] .. offset].iter()
+                                                            .filter(|&&ch| ch & 0xC0 != 0x80)
+                                                            .count();
+
+        Position {
+            offset: offset,
+            line: line + 1,
+            column: column + 1,
+        }
+    }
+}
+
+fn is_word_start(ch: u8) -> bool {
+    match ch {
+        b'A' ... b'Z' | b'a' ... b'z' | b'_' | b'$' => true,
+        _                                           => false,
+    }
+}
+
+fn is_word(ch: u8) -> bool {
+    match ch {
+        b'0' ... b'9' => true,
+        ch            => is_word_start(ch),
+    }
+}
+
+// Characters that can't start anything, and are skipped as a run. Colons
+// are only ever expected right after a key, where they are taken care of.
+fn is_stray(ch: u8) -> bool {
+    match ch {
+        b' ' | b'\t' | b'\n' | b'\r' |
+        b',' | b'[' | b']' | b'{' | b'}' | b'"' | b'-' => false,
+        ch                                             => !is_word(ch),
+    }
+}
+
+pub(super) fn parse_recovering(source: &str, mut options: ParserOptions) -> (JsonValue, Vec<Diagnostic>) {
+    // Only the options that make sense for a forgiving parser are used
+    let scalars = ParserOptions::new().lossless_numbers(options.lossless_numbers);
+
+    // Recursion has to stop somewhere
+    options.depth_limit = cmp::min(options.depth_limit, DEPTH_LIMIT);
+
+    Recovering {
+        source: source,
+        index: 0,
+        parser: Parser::new(source).with_options(scalars),
+        options: options,
+        collected: Vec::new(),
+        closers: Vec::new(),
+        found: Vec::new(),
+    }.parse()
+}
@@ -0,0 +1,197 @@
+// Recycling
+// =========
+//
+// A `JsonValue` owns its strings, arrays and objects, each allocated
+// separately from the global allocator, so a tree can't be carved out of a
+// single block of memory, arena style, without changing the type of every
+// value. What we can do instead is to hold on to the allocations of trees
+// that are no longer needed, and hand them out again to the parser. Once warmed up,
+// parsing documents of similar shape allocates next to nothing.
+
+use object::Object;
+use number::{ Number, RawNumber };
+use short;
+use wtf8::Wtf8String;
+use { JsonValue, Result };
+use super::{ Parser, ParserOptions, Builder, Text };
+
+// Allocations taken apart from recycled values, waiting to be reused.
+pub(super) struct Pool {
+    strings: Vec<String>,
+    arrays: Vec<Vec<JsonValue>>,
+    objects: Vec<Object>,
+
+    // Values yet to be taken apart, kept around so that recycling
+    // doesn't allocate either
+    pending: Vec<JsonValue>,
+}
+
+impl Pool {
+    pub fn new() -> Self {
+        Pool {
+            strings: Vec::new(),
+            arrays: Vec::new(),
+            objects: Vec::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    #[inline]
+    fn string_back(&mut self, mut string: String) {
+        if string.capacity() != 0 {
+            string.clear();
+            self.strings.push(string);
+        }
+    }
+
+    // Take the value apart, without recursion, keeping every allocation
+    // that can be handed out again.
+    pub fn recycle(&mut self, value: JsonValue) {
+        self.pending.push(value);
+
+        while let Some(value) = self.pending.pop() {
+            match value {
+                JsonValue::String(string) => self.string_back(string),
+                JsonValue::RawNumber(raw) => self.string_back(raw.into_inner()),
+                JsonValue::Array(mut array) => {
+                    if array.capacity() != 0 {
+                        self.pending.extend(array.drain(..));
+                        self.arrays.push(array);
+                    }
+                },
+                JsonValue::Object(mut object) => {
+                    for (_, value) in object.iter_mut() {
+                        self.pending.push(value.take());
+                    }
+
+                    object.clear();
+                    self.objects.push(object);
+                },
+                _ => {}
+            }
+        }
+    }
+}
+
+impl<'a> Builder<'a> for Pool {
+    type Value = JsonValue;
+
+    #[inline]
+    fn string<'b>(&mut self, text: Text<'a, 'b>) -> JsonValue {
+        if let Text::Wtf8(_, bytes) = text {
+            return JsonValue::Wtf8(Wtf8String::from_bytes(bytes.into()));
+        }
+
+        let value = text.as_str();
+
+        if value.len() <= short::MAX_LEN {
+            return value.into();
+        }
+
+        match self.strings.pop() {
+            Some(mut string) => {
+                string.push_str(value);
+                JsonValue::String(string)
+            },
+            None => JsonValue::String(value.into()),
+        }
+    }
+
+    #[inline]
+    fn raw_number(&mut self, raw: &'a str, num: Number) -> JsonValue {
+        let string = match self.strings.pop() {
+            Some(mut string) => {
+                string.push_str(raw);
+                string
+            },
+            None => raw.into(),
+        };
+
+        JsonValue::RawNumber(RawNumber::from_parts(string, num))
+    }
+
+    #[inline]
+    fn array(&mut self) -> Vec<JsonValue> {
+        self.arrays.pop().unwrap_or_else(|| Vec::with_capacity(2))
+    }
+
+    #[inline]
+    fn object(&mut self) -> Object {
+        self.objects.pop().unwrap_or_else(|| Object::with_capacity(3))
+    }
+}
+
+/// A reusable store of allocations for parsing many short-lived documents,
+/// such as one per request on a server. Values parsed with a `Recycler` are
+/// regular `JsonValue`s, and once they are no longer needed they can be
+/// handed back with `recycle`. Their strings, arrays and objects are then
+/// reused by the following parses instead of being freed and allocated
+/// all over again, which takes the global allocator, and any contention
+/// on it, out of the loop.
+///
+/// Everything the `Recycler` holds on to is freed at once when it's dropped,
+/// or with `clear`. Keeping one `Recycler` per thread works best, as it is
+/// meant to be used mutably.
+///
+/// ```
+/// let mut recycler = json::Recycler::new();
+///
+/// for source in &[r#"{"id":1,"tags":["a","b"]}"#, r#"{"id":2,"tags":["c"]}"#] {
+///     let data = recycler.parse(source).unwrap();
+///
+///     assert!(data["tags"].is_array());
+///
+///     recycler.recycle(data);
+/// }
+/// ```
+pub struct Recycler {
+    pool: Pool,
+    options: ParserOptions,
+}
+
+impl Recycler {
+    /// Create an empty `Recycler`, parsing with default options.
+    pub fn new() -> Self {
+        Recycler::with_options(ParserOptions::new())
+    }
+
+    /// Create an empty `Recycler`, parsing with the given options.
+    pub fn with_options(options: ParserOptions) -> Self {
+        Recycler {
+            pool: Pool::new(),
+            options: options,
+        }
+    }
+
+    /// Parse a `&str`, see `json::parse`, using allocations recycled so
+    /// far wherever possible.
+    pub fn parse(&mut self, source: &str) -> Result<JsonValue> {
+        Parser::new(source).with_options(self.options).parse_with(&mut self.pool)
+    }
+
+    /// Recycle the `target` value, then parse the source into it. On
+    /// error the `target` is left as `Null`.
+    pub fn parse_into(&mut self, source: &str, target: &mut JsonValue) -> Result<()> {
+        self.recycle(target.take());
+
+        *target = try!(self.parse(source));
+
+        Ok(())
+    }
+
+    /// Take the value apart, keeping its allocations for later parses.
+    pub fn recycle(&mut self, value: JsonValue) {
+        self.pool.recycle(value);
+    }
+
+    /// Free everything recycled so far.
+    pub fn clear(&mut self) {
+        self.pool = Pool::new();
+    }
+}
+
+impl Default for Recycler {
+    fn default() -> Self {
+        Recycler::new()
+    }
+}
@@ -0,0 +1,312 @@
+// Source spans
+// ============
+//
+// Validators want to point at the exact spot in the source where a bad value
+// sits. Rather than wrapping every `JsonValue` in the tree, which would make
+// the regular parser slower for everyone, spans are kept in a side table
+// keyed by JSON Pointer (RFC 6901). The tree is built out of `EventReader`
+// events, which know where each token starts and ends.
+
+use std::collections::BTreeMap;
+use object::Object;
+use number::RawNumber;
+use { JsonValue, Result };
+use super::{ Parser, EventReader, Event, ParserOptions, LoneSurrogates, Slot, Error };
+use super::{ Text, insert_key, put_value, forget_collected, is_json_number };
+
+/// A position in the source. Lines and columns start at 1, columns are
+/// counted in characters, same as in `Error::UnexpectedCharacter`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Range of the source a value or a key was parsed from. The `end` is
+/// exclusive, pointing right past the last byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+/// Side table of `Span`s for every value and object key of a parsed tree,
+/// keyed by JSON Pointer. The root value has an empty pointer, `""`.
+///
+/// Only values that are kept in the tree have spans, so when a key is
+/// repeated, its span and the spans of everything in its value belong to
+/// the occurrence that wins. With `DuplicateKeys::Collect`, that is the
+/// last one, rather than the collected array.
+///
+/// ```
+/// let (data, spans) = json::parse_with_spans("{\n  \"port\": \"eighty\"\n}").unwrap();
+///
+/// assert!(data["port"].is_string());
+///
+/// let span = spans.get("/port").unwrap();
+///
+/// assert_eq!((span.start.line, span.start.column), (2, 11));
+/// assert_eq!((span.start.offset, span.end.offset), (12, 20));
+///
+/// let key = spans.key("/port").unwrap();
+///
+/// assert_eq!((key.start.line, key.start.column), (2, 3));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spans {
+    values: BTreeMap<String, Span>,
+    keys: BTreeMap<String, Span>,
+}
+
+impl Spans {
+    /// Span of the value at the JSON Pointer.
+    pub fn get(&self, pointer: &str) -> Option<Span> {
+        self.values.get(pointer).cloned()
+    }
+
+    /// Span of the object key, including its quotes, for the value at the
+    /// JSON Pointer.
+    pub fn key(&self, pointer: &str) -> Option<Span> {
+        self.keys.get(pointer).cloned()
+    }
+
+    /// Number of values in the table.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+}
+
+// Turns byte offsets into positions. Offsets have to be asked for in
+// increasing order, so the source is only scanned once.
+struct Cursor<'a> {
+    source: &'a [u8],
+    position: Position,
+}
+
+impl<'a> Cursor<'a> {
+    fn at(&mut self, offset: usize) -> Position {
+        for &byte in &self.source[self.position.offset .. offset] {
+            if byte == b'\n' {
+                self.position.line += 1;
+                self.position.column = 1;
+            } else if byte & 0xC0 != 0x80 {
+                // Only count first bytes of UTF-8 sequences
+                self.position.column += 1;
+            }
+        }
+
+        self.position.offset = offset;
+        self.position
+    }
+}
+
+// A container being built, same idea as `StackBlock`.
+struct Frame {
+    value: JsonValue,
+    start: Position,
+
+    // Length of the pointer to this container
+    base: usize,
+
+    // Where the value of the last key read goes, objects only
+    slot: Slot,
+}
+
+// Events borrow the reader, so they are turned into these before asking
+// the reader for offsets.
+enum Token {
+    // Start of an object or an array, with the empty container
+    Start(JsonValue),
+    End,
+    Key(String),
+    Value(JsonValue),
+}
+
+// Append a pointer token, escaping `~` and `/` as RFC 6901 says.
+fn push_token(pointer: &mut String, token: &str) {
+    pointer.push('/');
+
+    for ch in token.chars() {
+        match ch {
+            '~' => pointer.push_str("~0"),
+            '/' => pointer.push_str("~1"),
+            ch  => pointer.push(ch),
+        }
+    }
+}
+
+// Forget the spans of everything inside the value at the pointer, all of
+// which start with the pointer followed by `/`, which `0` comes right after.
+fn forget_children(spans: &mut BTreeMap<String, Span>, pointer: &str) {
+    let children: Vec<String> = spans.range(format!("{}/", pointer) .. format!("{}0", pointer))
+                                     .map(|(child, _)| child.clone())
+                                     .collect();
+
+    for child in children {
+        spans.remove(&child);
+    }
+}
+
+pub(super) fn parse_with_spans(source: &str, options: ParserOptions) -> Result<(JsonValue, Spans)> {
+    let mut reader = EventReader::with_options(source, options);
+    let mut cursor = Cursor {
+        source: source.as_bytes(),
+        position: Position { offset: 0, line: 1, column: 1 },
+    };
+    let mut spans = Spans {
+        values: BTreeMap::new(),
+        keys: BTreeMap::new(),
+    };
+    let mut stack: Vec<Frame> = Vec::with_capacity(3);
+    let mut collected = Vec::new();
+    let mut pointer = String::new();
+
+    // Pointers only repeat with repeated keys, spans should then belong
+    // to the value that is kept. Values that are not kept have no spans,
+    // while one is being read this is the depth of the object it is in.
+    let mut discarding = None;
+
+    loop {
+        let token = match try!(reader.next_event()) {
+            Some(Event::StartObject)    => Token::Start(JsonValue::Object(Object::new())),
+            Some(Event::StartArray)     => Token::Start(JsonValue::Array(Vec::new())),
+            Some(Event::EndObject)      |
+            Some(Event::EndArray)       => Token::End,
+            Some(Event::Key(key))       => Token::Key(key.into()),
+            Some(Event::String(string)) => Token::Value(string.into()),
+            Some(Event::Number(number)) => Token::Value(JsonValue::Number(number)),
+            Some(Event::Boolean(value)) => Token::Value(JsonValue::Boolean(value)),
+            Some(Event::Null)           => Token::Value(JsonValue::Null),
+            None                        => break,
+        };
+        let (start, end) = reader.offsets();
+        let depth = stack.len().saturating_sub(1);
+
+        let value = match token {
+            Token::Key(key) => {
+                let frame = stack.last_mut().expect("Keys are only read in objects");
+
+                if let JsonValue::Object(ref mut object) = frame.value {
+                    frame.slot = match insert_key(&options, &mut collected, object, Text::Transient(&key), depth) {
+                        Some(slot) => slot,
+                        None       => {
+                            let start = cursor.at(start);
+
+                            return Err(Error::DuplicateKey {
+                                key: key,
+                                line: start.line,
+                                column: start.column,
+                            });
+                        }
+                    };
+                }
+
+                push_token(&mut pointer, &key);
+
+                if discarding.is_none() {
+                    if let Slot::Discard = frame.slot {
+                        discarding = Some(depth);
+                        continue;
+                    }
+
+                    // The value of a repeated key replaces the previous one,
+                    // which takes the spans inside it along
+                    if spans.keys.contains_key(&pointer) {
+                        forget_children(&mut spans.values, &pointer);
+                        forget_children(&mut spans.keys, &pointer);
+                    }
+
+                    let span = Span {
+                        start: cursor.at(start),
+                        end: cursor.at(end),
+                    };
+                    spans.keys.insert(pointer.clone(), span);
+                }
+                continue;
+            },
+            Token::End => {
+                let frame = stack.pop().expect("Containers are closed once");
+
+                if let JsonValue::Object(_) = frame.value {
+                    forget_collected(&mut collected, stack.len());
+                }
+
+                if discarding.is_none() {
+                    let span = Span {
+                        start: frame.start,
+                        end: cursor.at(end),
+                    };
+                    spans.values.insert(pointer.clone(), span);
+                }
+                frame.value
+            },
+            Token::Start(container) => {
+                if let Some(&Frame { value: JsonValue::Array(ref array), .. }) = stack.last() {
+                    push_token(&mut pointer, &array.len().to_string());
+                }
+
+                stack.push(Frame {
+                    value: container,
+                    start: cursor.at(start),
+                    base: pointer.len(),
+                    slot: Slot::Discard,
+                });
+                continue;
+            },
+            Token::Value(value) => {
+                // Members of arrays are pointed at by their index, members
+                // of objects had their key pushed already.
+                if let Some(&Frame { value: JsonValue::Array(ref array), .. }) = stack.last() {
+                    push_token(&mut pointer, &array.len().to_string());
+                }
+
+                let raw = &source[start .. end];
+
+                let value = match value {
+                    JsonValue::Number(num) if options.lossless_numbers
+                        && (!options.relaxed || is_json_number(raw.as_bytes())) => {
+                        JsonValue::RawNumber(RawNumber::from_parts(raw.into(), num))
+                    },
+
+                    // Events only carry valid UTF-8, so strings with lone
+                    // surrogates to preserve are read from the source again
+                    JsonValue::Short(_) | JsonValue::String(_)
+                        if options.lone_surrogates == LoneSurrogates::Preserve && raw.contains('\\') => {
+                        try!(Parser::new(raw).with_options(options).parse())
+                    },
+                    value => value,
+                };
+
+                if discarding.is_none() {
+                    let span = Span {
+                        start: cursor.at(start),
+                        end: cursor.at(end),
+                    };
+                    spans.values.insert(pointer.clone(), span);
+                }
+                value
+            },
+        };
+
+        // Done with the value that is not kept
+        if discarding.map_or(false, |depth| depth + 1 == stack.len()) {
+            discarding = None;
+        }
+
+        match stack.last_mut() {
+            Some(frame) => {
+                pointer.truncate(frame.base);
+
+                match frame.value {
+                    JsonValue::Object(ref mut object) => put_value(object, frame.slot, value),
+                    JsonValue::Array(ref mut array)   => array.push(value),
+                    _                                 => unreachable!(),
+                }
+            },
+            None => return Ok((value, spans)),
+        }
+    }
+
+    Err(Error::UnexpectedEndOfJson)
+}
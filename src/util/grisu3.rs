@@ -0,0 +1,121 @@
+// A port of the Grisu3 algorithm by Florian Loitsch, as found in the
+// `double-conversion` library:
+// https://github.com/google/double-conversion
+// http://www.cs.tufts.edu/~nr/cs257/archive/florian-loitsch/printf.pdf
+//
+// Unlike Grisu2, which always produces digits that read back as the same
+// number, but not always the shortest or the closest ones, Grisu3 knows
+// when it can't guarantee either, in which case it gives up and returns
+// `None`. That happens for about 0.5% of all numbers.
+//
+// Same as with `grisu2`, the digits are produced as a `u64` mantissa with
+// a decimal exponent instead of being written to a string.
+//
+// Copyright 2010 the V8 project authors. All rights reserved.
+//
+// Licensed under the BSD 3-clause license of `double-conversion`:
+// https://github.com/google/double-conversion/blob/master/LICENSE
+
+use util::diyfp::{ self, DiyFp };
+
+// Move the last digit down for as long as that brings the number closer to
+// `w`, then check that the number is certainly the closest one within the
+// safe interval. All the distances are scaled, `unit` being the error of
+// the scaled boundaries.
+#[inline]
+fn round_weed(buffer: &mut u64, distance_too_high_w: u64, unsafe_interval: u64, mut rest: u64, ten_kappa: u64, unit: u64) -> bool {
+    let small_distance = distance_too_high_w - unit;
+    let big_distance = distance_too_high_w + unit;
+
+    while rest < small_distance && unsafe_interval - rest >= ten_kappa &&
+          (rest + ten_kappa < small_distance ||
+           small_distance - rest >= rest + ten_kappa - small_distance) {
+        *buffer -= 1;
+        rest += ten_kappa;
+    }
+
+    // If moving down once more could still be closer, going by the other
+    // end of the error, there is no telling which one is the closest
+    if rest < big_distance && unsafe_interval - rest >= ten_kappa &&
+       (rest + ten_kappa < big_distance ||
+        big_distance - rest > rest + ten_kappa - big_distance) {
+        return false;
+    }
+
+    2 * unit <= rest && rest <= unsafe_interval - 4 * unit
+}
+
+#[inline]
+fn digit_gen(low: DiyFp, w: DiyFp, high: DiyFp, k: i16) -> Option<(u64, i16)> {
+    let mut unit = 1;
+    let too_low = DiyFp::new(low.f - unit, low.e);
+    let too_high = DiyFp::new(high.f + unit, high.e);
+    let mut unsafe_interval = too_high - too_low;
+    let one = DiyFp::new(1u64 << -w.e, w.e);
+    let mut integrals = (too_high.f >> -one.e) as u32;
+    let mut fractionals = too_high.f & (one.f - 1);
+    let mut divisor = 1;
+    let mut kappa = 1;
+
+    while integrals / divisor >= 10 {
+        divisor *= 10;
+        kappa += 1;
+    }
+
+    let mut buffer = 0;
+
+    while kappa > 0 {
+        buffer = buffer * 10 + (integrals / divisor) as u64;
+        integrals %= divisor;
+        kappa -= 1;
+
+        let rest = ((integrals as u64) << -one.e) + fractionals;
+
+        if rest < unsafe_interval.f {
+            let ten_kappa = (divisor as u64) << -one.e;
+
+            if round_weed(&mut buffer, (too_high - w).f, unsafe_interval.f, rest, ten_kappa, unit) {
+                return Some((buffer, k + kappa));
+            }
+
+            return None;
+        }
+
+        divisor /= 10;
+    }
+
+    loop {
+        fractionals *= 10;
+        unit *= 10;
+        unsafe_interval.f *= 10;
+
+        buffer = buffer * 10 + (fractionals >> -one.e);
+        fractionals &= one.f - 1;
+        kappa -= 1;
+
+        if fractionals < unsafe_interval.f {
+            if round_weed(&mut buffer, (too_high - w).f * unit, unsafe_interval.f, fractionals, one.f, unit) {
+                return Some((buffer, k + kappa));
+            }
+
+            return None;
+        }
+    }
+}
+
+// The shortest digits that read back as `float`, and of those the closest
+// to it, if they can be found. `float` has to be positive and finite.
+#[inline]
+pub fn convert(float: f64) -> Option<(u64, i16)> {
+    if float == 0.0 {
+        return Some((0, 0));
+    }
+    unsafe {
+        let v = DiyFp::from_f64(float);
+        let (w_m, w_p) = v.normalized_boundaries();
+        let (c_mk, k) = diyfp::get_cached_power(w_p.e);
+        let w = v.normalize() * c_mk;
+
+        digit_gen(w_m * c_mk, w, w_p * c_mk, k as i16)
+    }
+}
@@ -1,3 +1,5 @@
 pub mod diyfp;
 pub mod grisu2;
+pub mod grisu3;
 pub mod print_dec;
+pub mod scan;
@@ -0,0 +1,292 @@
+// Scanning for the end of runs of plain string bytes and whitespace, which
+// is where both the parser and the generator spend most of their time on
+// large payloads. On x86_64 this is done 16 bytes at a time with SSE2,
+// which every x86_64 CPU has, or 32 bytes at a time with AVX2 when it is
+// detected at runtime. Everywhere else, and for short inputs, 8 bytes at a
+// time are checked in a `u64` (SWAR, SIMD within a register).
+//
+// Plain string bytes are the ones that need no escaping: anything but
+// control characters, the double quote and the backslash. Whitespace is
+// the same set the parser has always skipped, 0x09 to 0x0D and space.
+
+/// Number of bytes at the start of `bytes` that can appear in a string
+/// as they are.
+#[inline]
+pub fn string_run(bytes: &[u8]) -> usize {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if bytes.len() >= 16 {
+            return x86::string_run(bytes);
+        }
+    }
+
+    swar::string_run(bytes)
+}
+
+/// Number of whitespace bytes at the start of `bytes`.
+#[inline]
+pub fn whitespace_run(bytes: &[u8]) -> usize {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if bytes.len() >= 16 {
+            return x86::whitespace_run(bytes);
+        }
+    }
+
+    swar::whitespace_run(bytes)
+}
+
+mod swar {
+    use std::ptr;
+
+    const LOW: u64 = 0x7F7F7F7F7F7F7F7F;
+    const HIGH: u64 = 0x8080808080808080;
+
+    #[inline(always)]
+    fn splat(byte: u8) -> u64 {
+        (byte as u64) * 0x0101010101010101
+    }
+
+    // First 8 bytes as a little endian word, so that the first byte is the
+    // lowest one.
+    #[inline(always)]
+    fn load(bytes: &[u8]) -> u64 {
+        let bytes = &bytes[.. 8];
+
+        u64::from_le(unsafe { ptr::read_unaligned(bytes.as_ptr() as *const u64) })
+    }
+
+    // Each of these sets the high bit of every byte of the result for which
+    // the test holds, and clears everything else. None of them lets a carry
+    // cross from one byte to the next, so every byte is exact.
+
+    // Bytes that are ASCII and at least `min`, `min` being at most 0x80.
+    #[inline(always)]
+    fn at_least(word: u64, min: u8) -> u64 {
+        ((word & LOW) + splat(0x80 - min)) & !word & HIGH
+    }
+
+    #[inline(always)]
+    fn equal(word: u64, byte: u8) -> u64 {
+        let diff = word ^ splat(byte);
+
+        !(((diff & LOW) + LOW) | diff) & HIGH
+    }
+
+    // Index of the first byte with its high bit set in `mask`.
+    #[inline(always)]
+    fn first(mask: u64) -> usize {
+        (mask.trailing_zeros() / 8) as usize
+    }
+
+    #[inline(always)]
+    fn special(word: u64) -> u64 {
+        let control = !at_least(word, 0x20) & !word & HIGH;
+
+        control | equal(word, b'"') | equal(word, b'\\')
+    }
+
+    #[inline(always)]
+    fn not_whitespace(word: u64) -> u64 {
+        let tab_to_cr = at_least(word, 0x09) & !at_least(word, 0x0E);
+
+        !(tab_to_cr | equal(word, b' ')) & HIGH
+    }
+
+    pub fn string_run(bytes: &[u8]) -> usize {
+        let mut index = 0;
+
+        while index + 8 <= bytes.len() {
+            let mask = special(load(&bytes[index ..]));
+
+            if mask != 0 {
+                return index + first(mask);
+            }
+
+            index += 8;
+        }
+
+        while index < bytes.len() {
+            match bytes[index] {
+                0x00 ... 0x1F | b'"' | b'\\' => break,
+                _                            => index += 1,
+            }
+        }
+
+        index
+    }
+
+    pub fn whitespace_run(bytes: &[u8]) -> usize {
+        let mut index = 0;
+
+        while index + 8 <= bytes.len() {
+            let mask = not_whitespace(load(&bytes[index ..]));
+
+            if mask != 0 {
+                return index + first(mask);
+            }
+
+            index += 8;
+        }
+
+        while index < bytes.len() {
+            match bytes[index] {
+                9 ... 13 | 32 => index += 1,
+                _             => break,
+            }
+        }
+
+        index
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use std::arch::x86_64::*;
+
+    // Both expect at least 16 bytes.
+
+    #[inline]
+    pub fn string_run(bytes: &[u8]) -> usize {
+        if bytes.len() >= 32 && is_x86_feature_detected!("avx2") {
+            unsafe { string_run_avx2(bytes) }
+        } else {
+            unsafe { string_run_sse2(bytes) }
+        }
+    }
+
+    #[inline]
+    pub fn whitespace_run(bytes: &[u8]) -> usize {
+        if bytes.len() >= 32 && is_x86_feature_detected!("avx2") {
+            unsafe { whitespace_run_avx2(bytes) }
+        } else {
+            unsafe { whitespace_run_sse2(bytes) }
+        }
+    }
+
+    // Once fewer bytes than a full vector are left, the last vector of the
+    // slice is checked again, overlapping the part already done.
+
+    #[target_feature(enable = "sse2")]
+    unsafe fn string_run_sse2(bytes: &[u8]) -> usize {
+        let quote = _mm_set1_epi8(b'"' as i8);
+        let backslash = _mm_set1_epi8(b'\\' as i8);
+        let control = _mm_set1_epi8(0x1F);
+        let last = bytes.len() - 16;
+        let mut index = 0;
+
+        loop {
+            let chunk = _mm_loadu_si128(bytes.as_ptr().offset(index as isize) as *const __m128i);
+
+            let special = _mm_or_si128(
+                _mm_or_si128(_mm_cmpeq_epi8(chunk, quote), _mm_cmpeq_epi8(chunk, backslash)),
+                _mm_cmpeq_epi8(_mm_min_epu8(chunk, control), chunk)
+            );
+
+            let mask = _mm_movemask_epi8(special) as u32;
+
+            if mask != 0 {
+                return index + mask.trailing_zeros() as usize;
+            }
+
+            if index == last {
+                return bytes.len();
+            }
+
+            index = ::std::cmp::min(index + 16, last);
+        }
+    }
+
+    #[target_feature(enable = "sse2")]
+    unsafe fn whitespace_run_sse2(bytes: &[u8]) -> usize {
+        let space = _mm_set1_epi8(b' ' as i8);
+        let tab = _mm_set1_epi8(0x09);
+        let range = _mm_set1_epi8(0x0E - 0x09 - 1);
+        let last = bytes.len() - 16;
+        let mut index = 0;
+
+        loop {
+            let chunk = _mm_loadu_si128(bytes.as_ptr().offset(index as isize) as *const __m128i);
+
+            let offset = _mm_sub_epi8(chunk, tab);
+            let whitespace = _mm_or_si128(
+                _mm_cmpeq_epi8(chunk, space),
+                _mm_cmpeq_epi8(_mm_min_epu8(offset, range), offset)
+            );
+
+            let mask = !(_mm_movemask_epi8(whitespace) as u32) & 0xFFFF;
+
+            if mask != 0 {
+                return index + mask.trailing_zeros() as usize;
+            }
+
+            if index == last {
+                return bytes.len();
+            }
+
+            index = ::std::cmp::min(index + 16, last);
+        }
+    }
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn string_run_avx2(bytes: &[u8]) -> usize {
+        let quote = _mm256_set1_epi8(b'"' as i8);
+        let backslash = _mm256_set1_epi8(b'\\' as i8);
+        let control = _mm256_set1_epi8(0x1F);
+        let last = bytes.len() - 32;
+        let mut index = 0;
+
+        loop {
+            let chunk = _mm256_loadu_si256(bytes.as_ptr().offset(index as isize) as *const __m256i);
+
+            let special = _mm256_or_si256(
+                _mm256_or_si256(_mm256_cmpeq_epi8(chunk, quote), _mm256_cmpeq_epi8(chunk, backslash)),
+                _mm256_cmpeq_epi8(_mm256_min_epu8(chunk, control), chunk)
+            );
+
+            let mask = _mm256_movemask_epi8(special) as u32;
+
+            if mask != 0 {
+                return index + mask.trailing_zeros() as usize;
+            }
+
+            if index == last {
+                return bytes.len();
+            }
+
+            index = ::std::cmp::min(index + 32, last);
+        }
+    }
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn whitespace_run_avx2(bytes: &[u8]) -> usize {
+        let space = _mm256_set1_epi8(b' ' as i8);
+        let tab = _mm256_set1_epi8(0x09);
+        let range = _mm256_set1_epi8(0x0E - 0x09 - 1);
+        let last = bytes.len() - 32;
+        let mut index = 0;
+
+        loop {
+            let chunk = _mm256_loadu_si256(bytes.as_ptr().offset(index as isize) as *const __m256i);
+
+            let offset = _mm256_sub_epi8(chunk, tab);
+            let whitespace = _mm256_or_si256(
+                _mm256_cmpeq_epi8(chunk, space),
+                _mm256_cmpeq_epi8(_mm256_min_epu8(offset, range), offset)
+            );
+
+            let mask = !(_mm256_movemask_epi8(whitespace) as u32);
+
+            if mask != 0 {
+                return index + mask.trailing_zeros() as usize;
+            }
+
+            if index == last {
+                return bytes.len();
+            }
+
+            index = ::std::cmp::min(index + 32, last);
+        }
+    }
+
+}
@@ -0,0 +1,548 @@
+// A read only counterpart of `JsonValue` borrowing from the source. Most
+// strings in JSON have no escapes in them, so they can be sliced straight
+// out of the source instead of being copied into a new `String`. Only the
+// strings that do have escapes are decoded into owned ones.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::ops::Index;
+use std::{ fmt, slice, usize, u8, u16, u32, u64, isize, i8, i16, i32, i64, f32 };
+
+use number::{ Number, RawNumber };
+use object::Object;
+use iterators::BorrowedMembers;
+use JsonValue;
+
+/// JSON value borrowing its strings, object keys and raw numbers from the
+/// source it was parsed from, created with `json::parse_borrowed`. Strings
+/// with escape sequences in them are the only ones that are allocated.
+///
+/// The read API is the same as that of `JsonValue`, and the value can be
+/// turned into a `JsonValue` with `to_json`, should it need to outlive
+/// the source.
+///
+/// ```
+/// use std::borrow::Cow;
+///
+/// let source = String::from(r#"{"name":"json","quote":"\"hi\"","tags":["a","b"]}"#);
+/// let data = json::parse_borrowed(&source).unwrap();
+///
+/// assert_eq!(data["name"], "json");
+/// assert_eq!(data["tags"].members().count(), 2);
+///
+/// match data["name"] {
+///     json::BorrowedValue::String(Cow::Borrowed(_)) => {},
+///     _ => panic!("Expected a borrowed string"),
+/// }
+///
+/// match data["quote"] {
+///     json::BorrowedValue::String(Cow::Owned(ref quote)) => assert_eq!(quote, "\"hi\""),
+///     _ => panic!("Expected an owned string"),
+/// }
+///
+/// let owned = data.to_json();
+///
+/// drop(data);
+/// drop(source);
+///
+/// assert_eq!(owned["tags"][1], "b");
+/// ```
+#[derive(Debug, Clone)]
+pub enum BorrowedValue<'a> {
+    Null,
+    String(Cow<'a, str>),
+    Number(Number),
+
+    /// A number in its original lexical form, see
+    /// `ParserOptions::lossless_numbers`.
+    RawNumber(RawNumber<&'a str>),
+    Boolean(bool),
+    Object(BorrowedObject<'a>),
+    Array(Vec<BorrowedValue<'a>>),
+}
+
+static NULL: BorrowedValue<'static> = BorrowedValue::Null;
+
+impl<'a> BorrowedValue<'a> {
+    pub fn is_string(&self) -> bool {
+        match *self {
+            BorrowedValue::String(_) => true,
+            _                        => false,
+        }
+    }
+
+    pub fn is_number(&self) -> bool {
+        match *self {
+            BorrowedValue::Number(_)    => true,
+            BorrowedValue::RawNumber(_) => true,
+            _                           => false,
+        }
+    }
+
+    pub fn is_boolean(&self) -> bool {
+        match *self {
+            BorrowedValue::Boolean(_) => true,
+            _                         => false,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        match *self {
+            BorrowedValue::Null => true,
+            _                   => false,
+        }
+    }
+
+    pub fn is_object(&self) -> bool {
+        match *self {
+            BorrowedValue::Object(_) => true,
+            _                        => false,
+        }
+    }
+
+    pub fn is_array(&self) -> bool {
+        match *self {
+            BorrowedValue::Array(_) => true,
+            _                       => false,
+        }
+    }
+
+    /// Checks whether the value is empty, same as `JsonValue::is_empty`.
+    pub fn is_empty(&self) -> bool {
+        match *self {
+            BorrowedValue::Null               => true,
+            BorrowedValue::String(ref value)  => value.is_empty(),
+            BorrowedValue::Number(ref value)  => value.is_empty(),
+            BorrowedValue::RawNumber(raw)     => raw.as_number().is_empty(),
+            BorrowedValue::Boolean(ref value) => !value,
+            BorrowedValue::Array(ref value)   => value.is_empty(),
+            BorrowedValue::Object(ref value)  => value.is_empty(),
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            BorrowedValue::String(ref value) => Some(value),
+            _                                => None
+        }
+    }
+
+    /// Obtain the number, if the value is one, same as with
+    /// `JsonValue::as_number`.
+    pub fn as_number(&self) -> Option<Number> {
+        match *self {
+            BorrowedValue::Number(value)    => Some(value),
+            BorrowedValue::RawNumber(raw)   => Some(raw.as_number()),
+            _                               => None
+        }
+    }
+
+    /// Obtain the original lexical form of a number, if the value was
+    /// parsed with `ParserOptions::lossless_numbers` enabled.
+    pub fn as_raw_number(&self) -> Option<&'a str> {
+        match *self {
+            BorrowedValue::RawNumber(raw) => Some(raw.into_inner()),
+            _                             => None
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        self.as_number().map(|value| value.into())
+    }
+
+    pub fn as_f32(&self) -> Option<f32> {
+        self.as_number().map(|value| value.into())
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        self.as_number().and_then(|value| {
+            if value.is_sign_positive() {
+                Some(value.into())
+            } else {
+                None
+            }
+        })
+    }
+
+    pub fn as_u32(&self) -> Option<u32> {
+        self.as_u64().and_then(|value| number_to_unsigned!(u32, value, u64))
+    }
+
+    pub fn as_u16(&self) -> Option<u16> {
+        self.as_u64().and_then(|value| number_to_unsigned!(u16, value, u64))
+    }
+
+    pub fn as_u8(&self) -> Option<u8> {
+        self.as_u64().and_then(|value| number_to_unsigned!(u8, value, u64))
+    }
+
+    pub fn as_usize(&self) -> Option<usize> {
+        self.as_u64().and_then(|value| number_to_unsigned!(usize, value, u64))
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_number().map(|value| value.into())
+    }
+
+    pub fn as_i32(&self) -> Option<i32> {
+        self.as_i64().and_then(|value| number_to_signed!(i32, value, i64))
+    }
+
+    pub fn as_i16(&self) -> Option<i16> {
+        self.as_i64().and_then(|value| number_to_signed!(i16, value, i64))
+    }
+
+    pub fn as_i8(&self) -> Option<i8> {
+        self.as_i64().and_then(|value| number_to_signed!(i8, value, i64))
+    }
+
+    pub fn as_isize(&self) -> Option<isize> {
+        self.as_i64().and_then(|value| number_to_signed!(isize, value, i64))
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            BorrowedValue::Boolean(ref value) => Some(*value),
+            _                                 => None
+        }
+    }
+
+    /// See `JsonValue::as_fixed_point_u64`.
+    pub fn as_fixed_point_u64(&self, point: u16) -> Option<u64> {
+        self.as_number().and_then(|value| value.as_fixed_point_u64(point))
+    }
+
+    /// See `JsonValue::as_fixed_point_i64`.
+    pub fn as_fixed_point_i64(&self, point: u16) -> Option<i64> {
+        self.as_number().and_then(|value| value.as_fixed_point_i64(point))
+    }
+
+    /// Works on `BorrowedValue::Object` - checks if the object has a key
+    pub fn has_key(&self, key: &str) -> bool {
+        match *self {
+            BorrowedValue::Object(ref object) => object.get(key).is_some(),
+            _                                 => false
+        }
+    }
+
+    /// Returns length of array or object (number of keys), defaults to `0` for
+    /// other types.
+    pub fn len(&self) -> usize {
+        match *self {
+            BorrowedValue::Array(ref vec)     => vec.len(),
+            BorrowedValue::Object(ref object) => object.len(),
+            _                                 => 0
+        }
+    }
+
+    /// Works on `BorrowedValue::Array` - returns an iterator over members.
+    /// Will return an empty iterator if called on non-array types.
+    pub fn members(&self) -> BorrowedMembers<'_, 'a> {
+        match *self {
+            BorrowedValue::Array(ref vec) => vec.iter(),
+            _                             => [].iter()
+        }
+    }
+
+    /// Works on `BorrowedValue::Object` - returns an iterator over key value
+    /// pairs. Will return an empty iterator if called on non-object types.
+    pub fn entries(&self) -> BorrowedEntries<'_, 'a> {
+        match *self {
+            BorrowedValue::Object(ref object) => object.iter(),
+            _                                 => BorrowedEntries::empty()
+        }
+    }
+
+    /// Copy the value into a `JsonValue`, which doesn't borrow anything.
+    pub fn to_json(&self) -> JsonValue {
+        match *self {
+            BorrowedValue::Null               => JsonValue::Null,
+            BorrowedValue::String(ref value)  => JsonValue::from(&**value),
+            BorrowedValue::Number(value)      => JsonValue::Number(value),
+            BorrowedValue::RawNumber(raw)     => {
+                JsonValue::RawNumber(RawNumber::from_parts(raw.as_str().into(), raw.as_number()))
+            },
+            BorrowedValue::Boolean(value)     => JsonValue::Boolean(value),
+            BorrowedValue::Array(ref vec)     => {
+                JsonValue::Array(vec.iter().map(BorrowedValue::to_json).collect())
+            },
+            BorrowedValue::Object(ref object) => {
+                let mut owned = Object::with_capacity(object.len());
+
+                for (key, value) in object.iter() {
+                    owned.insert(key, value.to_json());
+                }
+
+                JsonValue::Object(owned)
+            },
+        }
+    }
+}
+
+/// Compared the same way as `JsonValue`s are.
+impl<'a> PartialEq for BorrowedValue<'a> {
+    fn eq(&self, other: &BorrowedValue<'a>) -> bool {
+        match (self, other) {
+            (&BorrowedValue::Null, &BorrowedValue::Null) => true,
+            (&BorrowedValue::String(ref a), &BorrowedValue::String(ref b))   => a == b,
+            (&BorrowedValue::Boolean(a), &BorrowedValue::Boolean(b))         => a == b,
+            (&BorrowedValue::Object(ref a), &BorrowedValue::Object(ref b))   => a == b,
+            (&BorrowedValue::Array(ref a), &BorrowedValue::Array(ref b))     => a == b,
+            (&BorrowedValue::Number(_), _) |
+            (&BorrowedValue::RawNumber(_), _) => match (self.as_number(), other.as_number()) {
+                (Some(a), Some(b)) => a == b,
+                _                  => false,
+            },
+            _ => false,
+        }
+    }
+}
+
+impl<'a> fmt::Display for BorrowedValue<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.to_json(), f)
+    }
+}
+
+impl<'a> Index<usize> for BorrowedValue<'a> {
+    type Output = BorrowedValue<'a>;
+
+    fn index(&self, index: usize) -> &BorrowedValue<'a> {
+        match *self {
+            BorrowedValue::Array(ref vec) => vec.get(index).unwrap_or(&NULL),
+            _ => &NULL
+        }
+    }
+}
+
+impl<'a, 'b> Index<&'b str> for BorrowedValue<'a> {
+    type Output = BorrowedValue<'a>;
+
+    fn index(&self, index: &str) -> &BorrowedValue<'a> {
+        match *self {
+            BorrowedValue::Object(ref object) => object.get(index).unwrap_or(&NULL),
+            _ => &NULL
+        }
+    }
+}
+
+impl<'a> Index<String> for BorrowedValue<'a> {
+    type Output = BorrowedValue<'a>;
+
+    fn index(&self, index: String) -> &BorrowedValue<'a> {
+        &self[index.as_str()]
+    }
+}
+
+impl<'a, 'b> Index<&'b String> for BorrowedValue<'a> {
+    type Output = BorrowedValue<'a>;
+
+    fn index(&self, index: &String) -> &BorrowedValue<'a> {
+        &self[index.as_str()]
+    }
+}
+
+impl<'a, 'b> PartialEq<&'b str> for BorrowedValue<'a> {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == Some(*other)
+    }
+}
+
+impl<'a> PartialEq<str> for BorrowedValue<'a> {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == Some(other)
+    }
+}
+
+impl<'a> PartialEq<String> for BorrowedValue<'a> {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == Some(other.as_str())
+    }
+}
+
+impl<'a> PartialEq<bool> for BorrowedValue<'a> {
+    fn eq(&self, other: &bool) -> bool {
+        self.as_bool() == Some(*other)
+    }
+}
+
+impl<'a> PartialEq<JsonValue> for BorrowedValue<'a> {
+    fn eq(&self, other: &JsonValue) -> bool {
+        self.to_json() == *other
+    }
+}
+
+macro_rules! implement_number_eq {
+    ($($from:ty),*) => {
+        $(
+            impl<'a> PartialEq<$from> for BorrowedValue<'a> {
+                fn eq(&self, other: &$from) -> bool {
+                    match self.as_number() {
+                        Some(ref value) => value == other,
+                        None            => false
+                    }
+                }
+            }
+        )*
+    }
+}
+
+implement_number_eq!(Number, u8, u16, u32, u64, usize, i8, i16, i32, i64, isize, f32, f64);
+
+// Objects with at least this many keys get a hash map to look them up,
+// below that going through the keys one by one is faster.
+const INDEX_THRESHOLD: usize = 16;
+
+/// Object of a `BorrowedValue`. Keys keep the order they appeared in, and
+/// are unique, same as in `Object`.
+#[derive(Clone)]
+pub struct BorrowedObject<'a> {
+    entries: Vec<(Cow<'a, str>, BorrowedValue<'a>)>,
+    index: Option<HashMap<Cow<'a, str>, usize>>,
+}
+
+impl<'a> BorrowedObject<'a> {
+    /// Create a new, empty object.
+    pub fn new() -> Self {
+        BorrowedObject::with_capacity(0)
+    }
+
+    /// Create a new, empty object with space for `capacity` keys.
+    pub fn with_capacity(capacity: usize) -> Self {
+        BorrowedObject {
+            entries: Vec::with_capacity(capacity),
+            index: None,
+        }
+    }
+
+    /// Insert a value under the key, replacing the value already there,
+    /// which keeps its place in the order of keys.
+    pub fn insert(&mut self, key: Cow<'a, str>, value: BorrowedValue<'a>) {
+        match self.index_of(&key) {
+            Some(index) => self.entries[index].1 = value,
+            None        => { self.push(key, value); },
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&BorrowedValue<'a>> {
+        self.index_of(key).map(|index| &self.entries[index].1)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut BorrowedValue<'a>> {
+        match self.index_of(key) {
+            Some(index) => Some(&mut self.entries[index].1),
+            None        => None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterate over the keys and values, in order.
+    pub fn iter(&self) -> BorrowedEntries<'_, 'a> {
+        BorrowedEntries {
+            inner: self.entries.iter()
+        }
+    }
+
+    pub(crate) fn index_of(&self, key: &str) -> Option<usize> {
+        match self.index {
+            Some(ref index) => index.get(key).cloned(),
+            None            => self.entries.iter().position(|entry| entry.0 == key),
+        }
+    }
+
+    // Add a key that isn't in the object yet, returning its index.
+    pub(crate) fn push(&mut self, key: Cow<'a, str>, value: BorrowedValue<'a>) -> usize {
+        let at = self.entries.len();
+
+        if let Some(ref mut index) = self.index {
+            index.insert(key.clone(), at);
+        }
+
+        self.entries.push((key, value));
+
+        if self.index.is_none() && self.entries.len() >= INDEX_THRESHOLD {
+            self.index = Some(self.entries.iter()
+                                          .enumerate()
+                                          .map(|(at, entry)| (entry.0.clone(), at))
+                                          .collect());
+        }
+
+        at
+    }
+
+    pub(crate) fn value_at_mut(&mut self, index: usize) -> &mut BorrowedValue<'a> {
+        &mut self.entries[index].1
+    }
+}
+
+impl<'a> Default for BorrowedObject<'a> {
+    fn default() -> Self {
+        BorrowedObject::new()
+    }
+}
+
+impl<'a> PartialEq for BorrowedObject<'a> {
+    fn eq(&self, other: &BorrowedObject<'a>) -> bool {
+        if self.len() != other.len() {
+            return false;
+        }
+
+        for (key, value) in self.iter() {
+            match other.get(key) {
+                Some(other_val) => if other_val != value { return false; },
+                None            => return false
+            }
+        }
+
+        true
+    }
+}
+
+impl<'a> fmt::Debug for BorrowedObject<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// Iterator over key value pairs of `BorrowedValue::Object`.
+pub struct BorrowedEntries<'b, 'a: 'b> {
+    inner: slice::Iter<'b, (Cow<'a, str>, BorrowedValue<'a>)>
+}
+
+impl<'b, 'a> BorrowedEntries<'b, 'a> {
+    /// Create an empty iterator that always returns `None`
+    pub fn empty() -> Self {
+        BorrowedEntries {
+            inner: [].iter()
+        }
+    }
+}
+
+impl<'b, 'a> Iterator for BorrowedEntries<'b, 'a> {
+    type Item = (&'b str, &'b BorrowedValue<'a>);
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|entry| (&*entry.0, &entry.1))
+    }
+}
+
+impl<'b, 'a> DoubleEndedIterator for BorrowedEntries<'b, 'a> {
+    #[inline(always)]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|entry| (&*entry.0, &entry.1))
+    }
+}
+
+impl<'b, 'a> ExactSizeIterator for BorrowedEntries<'b, 'a> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
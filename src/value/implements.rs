@@ -5,12 +5,41 @@ use std::collections::{ BTreeMap, HashMap };
 use std::mem;
 
 use short::{ self, Short };
-use number::Number;
+use number::{ Number, RawNumber };
 use object::Object;
 
 use { JsonValue, Null };
 
 macro_rules! implement_eq {
+    // Numbers also compare equal to raw numbers of the same value
+    ($to:ident, $from:ty as num) => {
+        impl PartialEq<$from> for JsonValue {
+            fn eq(&self, other: &$from) -> bool {
+                match self.as_number() {
+                    Some(ref value) => value == other,
+                    None            => false
+                }
+            }
+        }
+
+        impl<'a> PartialEq<$from> for &'a JsonValue {
+            fn eq(&self, other: &$from) -> bool {
+                match self.as_number() {
+                    Some(ref value) => value == other,
+                    None            => false
+                }
+            }
+        }
+
+        impl PartialEq<JsonValue> for $from {
+            fn eq(&self, other: &JsonValue) -> bool {
+                match other.as_number() {
+                    Some(ref value) => value == self,
+                    None            => false
+                }
+            }
+        }
+    };
     ($to:ident, $from:ty) => {
         impl PartialEq<$from> for JsonValue {
             fn eq(&self, other: &$from) -> bool {
@@ -49,7 +78,7 @@ macro_rules! implement {
             }
         }
 
-        implement_eq!($to, $from);
+        implement_eq!($to, $from as num);
     };
     ($to:ident, $from:ty) => {
         impl From<$from> for JsonValue {
@@ -174,6 +203,13 @@ implement!(Number, u32 as num);
 implement!(Number, u64 as num);
 implement!(Number, f32 as num);
 implement!(Number, f64 as num);
-implement!(Number, Number);
+implement!(Number, Number as num);
+
+impl From<RawNumber> for JsonValue {
+    fn from(val: RawNumber) -> JsonValue {
+        JsonValue::RawNumber(val)
+    }
+}
+
 implement!(Object, Object);
 implement!(Boolean, bool);
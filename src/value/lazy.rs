@@ -0,0 +1,510 @@
+// Lazy values
+// ===========
+//
+// A `LazyDocument` is a tape of tokens, one for each value and object key
+// in the source, in the order they appear in. Each token knows where its
+// value is in the source and how many tokens its subtree takes, so that
+// whole arrays and objects can be stepped over when looking for a member.
+// Nothing is decoded until it's asked for.
+//
+// A `LazyValue` is only ever handed out by reference, so that indexing
+// works the same way it does for `JsonValue`. The reference is a pointer
+// to the document, with the position of the value on the tape stored as
+// the length of a slice of zero sized elements. Values that are missing
+// are positioned right past the end of the tape.
+
+use std::borrow::Cow;
+use std::marker::PhantomData;
+use std::ops::{ Index, Deref };
+use std::{ fmt, slice, usize, u8, u16, u32, u64, isize, i8, i16, i32, i64, f32 };
+
+use number::Number;
+use { JsonValue, ParserOptions, DuplicateKeys, LoneSurrogates, Result };
+
+// Offset of the first byte of the value in the source, its length in bytes
+// and the number of tokens in its subtree, itself included. Arrays are
+// followed by their members, objects by their keys, each followed by its
+// value.
+#[derive(Clone, Copy)]
+pub(crate) struct Token {
+    pub start: u32,
+    pub len: u32,
+    pub extent: u32,
+}
+
+/// A document that has been checked to be valid JSON, without decoding any
+/// of it, created with `json::parse_lazy`. Dereferences to the `LazyValue`
+/// at the root of the document.
+///
+/// Parsing this way records where each value is in the source on a compact
+/// tape, which is considerably cheaper than building a `JsonValue`, and
+/// strings and numbers are only decoded when accessed. This pays off when
+/// only a small part of a large document is ever read.
+///
+/// ```
+/// let source = r#"{"meta":{"id":7},"items":[{"name":"a"},{"name":"b"}]}"#;
+/// let data = json::parse_lazy(source).unwrap();
+///
+/// assert_eq!(data["meta"]["id"].as_u32(), Some(7));
+/// assert_eq!(data["items"][1]["name"], "b");
+/// assert_eq!(data["items"].as_json_str(), r#"[{"name":"a"},{"name":"b"}]"#);
+/// assert!(data["missing"].is_null());
+/// ```
+pub struct LazyDocument<'a> {
+    source: &'a str,
+    options: ParserOptions,
+    tape: Vec<Token>,
+}
+
+impl<'a> LazyDocument<'a> {
+    pub(crate) fn new(source: &'a str, options: ParserOptions, tape: Vec<Token>) -> Self {
+        LazyDocument {
+            source: source,
+            options: options,
+            tape: tape,
+        }
+    }
+}
+
+impl<'a> Deref for LazyDocument<'a> {
+    type Target = LazyValue<'a>;
+
+    fn deref(&self) -> &LazyValue<'a> {
+        LazyValue::new(self, 0)
+    }
+}
+
+impl<'a> fmt::Debug for LazyDocument<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+/// A value in a `LazyDocument`. Type checks, such as `is_string`, only look
+/// at the first byte of the value, while strings and numbers are decoded
+/// from the source by `as_str`, `as_number` and friends every time they are
+/// called. Looking up keys and indexes steps over the members of objects
+/// and arrays one by one.
+///
+/// Same as with `JsonValue`, indexing with a missing key or index, or into
+/// a value that is not an object or array, gives a null value.
+#[repr(C)]
+pub struct LazyValue<'a> {
+    document: PhantomData<&'a str>,
+    position: [()],
+}
+
+impl<'a> LazyValue<'a> {
+    fn new<'t>(document: &'t LazyDocument<'a>, position: usize) -> &'t LazyValue<'a> {
+        unsafe {
+            // Slices of zero sized elements can be of any length
+            let position = slice::from_raw_parts(document as *const LazyDocument<'a> as *const (), position);
+
+            &*(position as *const [()] as *const LazyValue<'a>)
+        }
+    }
+
+    fn document(&self) -> &LazyDocument<'a> {
+        unsafe { &*(self as *const LazyValue<'a> as *const LazyDocument<'a>) }
+    }
+
+    // The tokens of the value and its subtree, none if it's missing.
+    fn tape(&self) -> &[Token] {
+        let tape = &self.document().tape;
+        let position = self.position.len();
+
+        match tape.get(position) {
+            Some(token) => &tape[position .. position + token.extent as usize],
+            None        => &[],
+        }
+    }
+
+    /// The part of the source this value was parsed from, which is valid
+    /// JSON on its own.
+    pub fn as_json_str(&self) -> &'a str {
+        let document = self.document();
+        let source: &'a str = document.source;
+
+        match document.tape.get(self.position.len()) {
+            Some(token) => {
+                let start = token.start as usize;
+
+                &source[start .. start + token.len as usize]
+            },
+            None => "null",
+        }
+    }
+
+    fn first_byte(&self) -> u8 {
+        self.as_json_str().as_bytes()[0]
+    }
+
+    pub fn is_string(&self) -> bool {
+        self.first_byte() == b'"'
+    }
+
+    pub fn is_number(&self) -> bool {
+        match self.first_byte() {
+            b'-' | b'0' ... b'9' => true,
+            _                    => false,
+        }
+    }
+
+    pub fn is_boolean(&self) -> bool {
+        match self.first_byte() {
+            b't' | b'f' => true,
+            _           => false,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        self.first_byte() == b'n'
+    }
+
+    pub fn is_object(&self) -> bool {
+        self.first_byte() == b'{'
+    }
+
+    pub fn is_array(&self) -> bool {
+        self.first_byte() == b'['
+    }
+
+    /// Checks whether the value is empty. Returns true for:
+    ///
+    /// - empty string (`""`)
+    /// - number `0`
+    /// - boolean `false`
+    /// - null
+    /// - empty array (`[]`)
+    /// - empty object (`{}`)
+    pub fn is_empty(&self) -> bool {
+        match self.first_byte() {
+            b'"'        => self.as_json_str().len() == 2,
+            b'[' | b'{' => self.tape().len() == 1,
+            b't'        => false,
+            b'f' | b'n' => true,
+            _           => self.as_number().map_or(false, |number| number.is_zero()),
+        }
+    }
+
+    /// Decode the string, borrowing it from the source unless it has
+    /// escape sequences in it.
+    pub fn as_str(&self) -> Option<Cow<'a, str>> {
+        if !self.is_string() {
+            return None;
+        }
+
+        let raw = self.as_json_str();
+        let contents = &raw[1 .. raw.len() - 1];
+
+        if !contents.as_bytes().contains(&b'\\') {
+            return Some(Cow::Borrowed(contents));
+        }
+
+        // Lone surrogates only make it onto the tape if they are allowed
+        match ParserOptions::new().lone_surrogates(LoneSurrogates::Replace).parse(raw) {
+            Ok(value) => value.as_str().map(|string| Cow::Owned(string.into())),
+            Err(_)    => None,
+        }
+    }
+
+    pub fn as_number(&self) -> Option<Number> {
+        if !self.is_number() {
+            return None;
+        }
+
+        match ::parse(self.as_json_str()) {
+            Ok(JsonValue::Number(number)) => Some(number),
+            _                             => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        self.as_number().map(|value| value.into())
+    }
+
+    pub fn as_f32(&self) -> Option<f32> {
+        self.as_number().map(|value| value.into())
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        self.as_number().and_then(|value| {
+            if value.is_sign_positive() {
+                Some(value.into())
+            } else {
+                None
+            }
+        })
+    }
+
+    pub fn as_u32(&self) -> Option<u32> {
+        self.as_u64().and_then(|value| number_to_unsigned!(u32, value, u64))
+    }
+
+    pub fn as_u16(&self) -> Option<u16> {
+        self.as_u64().and_then(|value| number_to_unsigned!(u16, value, u64))
+    }
+
+    pub fn as_u8(&self) -> Option<u8> {
+        self.as_u64().and_then(|value| number_to_unsigned!(u8, value, u64))
+    }
+
+    pub fn as_usize(&self) -> Option<usize> {
+        self.as_u64().and_then(|value| number_to_unsigned!(usize, value, u64))
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_number().map(|value| value.into())
+    }
+
+    pub fn as_i32(&self) -> Option<i32> {
+        self.as_i64().and_then(|value| number_to_signed!(i32, value, i64))
+    }
+
+    pub fn as_i16(&self) -> Option<i16> {
+        self.as_i64().and_then(|value| number_to_signed!(i16, value, i64))
+    }
+
+    pub fn as_i8(&self) -> Option<i8> {
+        self.as_i64().and_then(|value| number_to_signed!(i8, value, i64))
+    }
+
+    pub fn as_isize(&self) -> Option<isize> {
+        self.as_i64().and_then(|value| number_to_signed!(isize, value, i64))
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self.first_byte() {
+            b't' => Some(true),
+            b'f' => Some(false),
+            _    => None,
+        }
+    }
+
+    /// See `JsonValue::as_fixed_point_u64`.
+    pub fn as_fixed_point_u64(&self, point: u16) -> Option<u64> {
+        self.as_number().and_then(|value| value.as_fixed_point_u64(point))
+    }
+
+    /// See `JsonValue::as_fixed_point_i64`.
+    pub fn as_fixed_point_i64(&self, point: u16) -> Option<i64> {
+        self.as_number().and_then(|value| value.as_fixed_point_i64(point))
+    }
+
+    /// Works on objects - looks up the value under the key. Same as when
+    /// parsing into a `JsonValue`, if the key appears more than once the
+    /// last value wins.
+    pub fn get(&self, key: &str) -> Option<&LazyValue<'a>> {
+        let mut found = None;
+
+        for (candidate, value) in self.entries() {
+            if candidate == key {
+                found = Some(value);
+            }
+        }
+
+        found
+    }
+
+    /// Works on objects - checks if the object has a key
+    pub fn has_key(&self, key: &str) -> bool {
+        self.entries().any(|(candidate, _)| candidate == key)
+    }
+
+    /// Returns length of array or object (number of keys), defaults to `0`
+    /// for other types. The members have to be counted one by one, and
+    /// keys that appear more than once are counted each time.
+    pub fn len(&self) -> usize {
+        if self.is_array() {
+            self.members().count()
+        } else {
+            self.entries().count()
+        }
+    }
+
+    /// Works on arrays - returns an iterator over members. Will return an
+    /// empty iterator if called on non-array types.
+    pub fn members(&self) -> LazyMembers<'_, 'a> {
+        self.children(self.is_array())
+    }
+
+    /// Works on objects - returns an iterator over key value pairs, keys
+    /// being decoded as they are reached. Will return an empty iterator if
+    /// called on non-object types.
+    pub fn entries(&self) -> LazyEntries<'_, 'a> {
+        LazyEntries {
+            inner: self.children(self.is_object())
+        }
+    }
+
+    fn children(&self, container: bool) -> LazyMembers<'_, 'a> {
+        let position = self.position.len();
+
+        LazyMembers {
+            document: self.document(),
+            position: position + 1,
+            end: if container { position + self.tape().len() } else { 0 },
+        }
+    }
+
+    fn missing(&self) -> &LazyValue<'a> {
+        let document = self.document();
+
+        LazyValue::new(document, document.tape.len())
+    }
+
+    /// Decode the whole value into a `JsonValue`, with the options the
+    /// document was parsed with. Limits other than the size limit do not
+    /// apply, and duplicate keys are handled as with
+    /// `DuplicateKeys::LastWins`, same as when looking them up.
+    pub fn to_json(&self) -> Result<JsonValue> {
+        self.document().options
+            .depth_limit(usize::MAX)
+            .string_length_limit(usize::MAX)
+            .array_length_limit(usize::MAX)
+            .object_length_limit(usize::MAX)
+            .duplicate_keys(DuplicateKeys::LastWins)
+            .parse(self.as_json_str())
+    }
+}
+
+/// Iterator over members of an array `LazyValue`.
+pub struct LazyMembers<'t, 'a: 't> {
+    document: &'t LazyDocument<'a>,
+    position: usize,
+    end: usize,
+}
+
+impl<'t, 'a> Iterator for LazyMembers<'t, 'a> {
+    type Item = &'t LazyValue<'a>;
+
+    fn next(&mut self) -> Option<&'t LazyValue<'a>> {
+        if self.position >= self.end {
+            return None;
+        }
+
+        let value = LazyValue::new(self.document, self.position);
+
+        self.position += self.document.tape[self.position].extent as usize;
+
+        Some(value)
+    }
+}
+
+/// Iterator over key value pairs of an object `LazyValue`.
+pub struct LazyEntries<'t, 'a: 't> {
+    inner: LazyMembers<'t, 'a>,
+}
+
+impl<'t, 'a> Iterator for LazyEntries<'t, 'a> {
+    type Item = (Cow<'a, str>, &'t LazyValue<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let key = match self.inner.next() {
+            Some(key) => key.as_str().expect("Object keys are strings"),
+            None      => return None,
+        };
+
+        self.inner.next().map(|value| (key, value))
+    }
+}
+
+impl<'a> fmt::Debug for LazyValue<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("LazyValue").field(&self.as_json_str()).finish()
+    }
+}
+
+impl<'a> fmt::Display for LazyValue<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.to_json() {
+            Ok(value) => fmt::Display::fmt(&value, f),
+            Err(_)    => Err(fmt::Error),
+        }
+    }
+}
+
+impl<'a> Index<usize> for LazyValue<'a> {
+    type Output = LazyValue<'a>;
+
+    fn index(&self, index: usize) -> &LazyValue<'a> {
+        self.members().nth(index).unwrap_or_else(|| self.missing())
+    }
+}
+
+impl<'k, 'a> Index<&'k str> for LazyValue<'a> {
+    type Output = LazyValue<'a>;
+
+    fn index(&self, index: &str) -> &LazyValue<'a> {
+        self.get(index).unwrap_or_else(|| self.missing())
+    }
+}
+
+impl<'a> Index<String> for LazyValue<'a> {
+    type Output = LazyValue<'a>;
+
+    fn index(&self, index: String) -> &LazyValue<'a> {
+        &self[index.as_str()]
+    }
+}
+
+impl<'k, 'a> Index<&'k String> for LazyValue<'a> {
+    type Output = LazyValue<'a>;
+
+    fn index(&self, index: &String) -> &LazyValue<'a> {
+        &self[index.as_str()]
+    }
+}
+
+impl<'a> PartialEq<JsonValue> for LazyValue<'a> {
+    fn eq(&self, other: &JsonValue) -> bool {
+        match self.to_json() {
+            Ok(value) => value == *other,
+            Err(_)    => false,
+        }
+    }
+}
+
+impl<'a> PartialEq<str> for LazyValue<'a> {
+    fn eq(&self, other: &str) -> bool {
+        match self.as_str() {
+            Some(value) => value == other,
+            None        => false,
+        }
+    }
+}
+
+impl<'s, 'a> PartialEq<&'s str> for LazyValue<'a> {
+    fn eq(&self, other: &&str) -> bool {
+        *self == **other
+    }
+}
+
+impl<'a> PartialEq<String> for LazyValue<'a> {
+    fn eq(&self, other: &String) -> bool {
+        *self == **other
+    }
+}
+
+impl<'a> PartialEq<bool> for LazyValue<'a> {
+    fn eq(&self, other: &bool) -> bool {
+        self.as_bool() == Some(*other)
+    }
+}
+
+macro_rules! implement_number_eq {
+    ($($from:ty),*) => {
+        $(
+            impl<'a> PartialEq<$from> for LazyValue<'a> {
+                fn eq(&self, other: &$from) -> bool {
+                    match self.as_number() {
+                        Some(ref value) => value == other,
+                        None            => false
+                    }
+                }
+            }
+        )*
+    }
+}
+
+implement_number_eq!(Number, u8, u16, u32, u64, usize, i8, i16, i32, i64, isize, f32, f64);
@@ -5,10 +5,11 @@ use std::{ fmt, mem, usize, u8, u16, u32, u64, isize, i8, i16, i32, i64, f32 };
 use std::io::{ self, Write };
 
 use short::Short;
-use number::Number;
+use number::{ Number, RawNumber };
+use wtf8::Wtf8String;
 use object::Object;
 use iterators::{ Members, MembersMut, Entries, EntriesMut };
-use codegen::{ Generator, PrettyGenerator, DumpGenerator, WriterGenerator, PrettyWriterGenerator };
+use codegen::{ Generator, PrettyGenerator, DumpGenerator, WriterGenerator, PrettyWriterGenerator, CanonicalGenerator, PrettyConfig, EscapeConfig };
 
 mod implements;
 
@@ -34,12 +35,31 @@ macro_rules! number_to_signed {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+// Uses the macros above
+mod borrowed;
+
+pub use self::borrowed::{ BorrowedValue, BorrowedObject, BorrowedEntries };
+
+// And so do lazy values
+mod lazy;
+
+pub use self::lazy::{ LazyDocument, LazyValue, LazyMembers, LazyEntries };
+pub(crate) use self::lazy::Token;
+
+#[derive(Debug, Clone)]
 pub enum JsonValue {
     Null,
     Short(Short),
     String(String),
+
+    /// A string with lone surrogates in it, as kept by
+    /// `LoneSurrogates::Preserve`.
+    Wtf8(Wtf8String),
     Number(Number),
+
+    /// A number kept in its original lexical form, see
+    /// `ParserOptions::lossless_numbers`.
+    RawNumber(RawNumber),
     Boolean(bool),
     Object(Object),
     Array(Vec<JsonValue>),
@@ -62,7 +82,9 @@ impl fmt::Display for JsonValue {
             match *self {
                 JsonValue::Short(ref value)   => value.fmt(f),
                 JsonValue::String(ref value)  => value.fmt(f),
+                JsonValue::Wtf8(ref value)    => value.fmt(f),
                 JsonValue::Number(ref value)  => value.fmt(f),
+                JsonValue::RawNumber(ref raw) => raw.fmt(f),
                 JsonValue::Boolean(ref value) => value.fmt(f),
                 JsonValue::Null               => f.write_str("null"),
                 _                             => f.write_str(&self.dump())
@@ -72,6 +94,30 @@ impl fmt::Display for JsonValue {
 }
 
 
+/// Values are compared the same way they would be with a derived
+/// `PartialEq`, except for numbers, which compare equal to each other when
+/// they are of the same value, whether they are raw numbers or not.
+impl PartialEq for JsonValue {
+    fn eq(&self, other: &JsonValue) -> bool {
+        match (self, other) {
+            (&JsonValue::Null, &JsonValue::Null) => true,
+            (&JsonValue::Short(ref a), &JsonValue::Short(ref b))     => a == b,
+            (&JsonValue::String(ref a), &JsonValue::String(ref b))   => a == b,
+            (&JsonValue::Wtf8(ref a), &JsonValue::Wtf8(ref b))       => a == b,
+            (&JsonValue::Boolean(a), &JsonValue::Boolean(b))         => a == b,
+            (&JsonValue::Object(ref a), &JsonValue::Object(ref b))   => a == b,
+            (&JsonValue::Array(ref a), &JsonValue::Array(ref b))     => a == b,
+            (&JsonValue::Number(_), _) |
+            (&JsonValue::RawNumber(_), _) => match (self.as_number(), other.as_number()) {
+                (Some(a), Some(b)) => a == b,
+                _                  => false,
+            },
+            _ => false,
+        }
+    }
+}
+
+
 static NULL: JsonValue = JsonValue::Null;
 
 impl JsonValue {
@@ -91,7 +137,15 @@ impl JsonValue {
     pub fn dump(&self) -> String {
         let mut gen = DumpGenerator::new();
         gen.write_json(self).expect("Can't fail");
-        gen.consume()
+        unsafe { gen.consume_unchecked() }
+    }
+
+    /// Prints out the value as JSON string, escaping characters in strings
+    /// as set in the `EscapeConfig`.
+    pub fn dump_escaped(&self, escape: EscapeConfig) -> String {
+        let mut gen = DumpGenerator::with_escape(escape);
+        gen.write_json(self).expect("Can't fail");
+        unsafe { gen.consume_unchecked() }
     }
 
     /// Pretty prints out the value as JSON string. Takes an argument that's
@@ -99,7 +153,16 @@ impl JsonValue {
     pub fn pretty(&self, spaces: u16) -> String {
         let mut gen = PrettyGenerator::new(spaces);
         gen.write_json(self).expect("Can't fail");
-        gen.consume()
+        unsafe { gen.consume_unchecked() }
+    }
+
+    /// Pretty prints out the value as JSON string, laid out as set in the
+    /// `PrettyConfig`.
+    pub fn pretty_with(&self, config: &PrettyConfig) -> String {
+        let mut gen = PrettyGenerator::with_config(config.clone());
+        gen.write_json(self).expect("Can't fail");
+        gen.finish().expect("Can't fail");
+        unsafe { gen.consume_unchecked() }
     }
 
     /// Writes the JSON as byte stream into an implementor of `std::io::Write`.
@@ -117,24 +180,78 @@ impl JsonValue {
         gen.write_json(self)
     }
 
+    /// Writes the JSON as byte stream into an implementor of `std::io::Write`,
+    /// escaping characters in strings as set in the `EscapeConfig`.
+    pub fn write_escaped<W: Write>(&self, writer: &mut W, escape: EscapeConfig) -> io::Result<()> {
+        let mut gen = WriterGenerator::with_escape(writer, escape);
+        gen.write_json(self)
+    }
+
     /// Writes the JSON as byte stream into an implementor of `std::io::Write`.
     pub fn write_pretty<W: Write>(&self, writer: &mut W, spaces: u16) -> io::Result<()> {
         let mut gen = PrettyWriterGenerator::new(writer, spaces);
         gen.write_json(self)
     }
 
+    /// Writes the JSON as byte stream into an implementor of `std::io::Write`,
+    /// laid out as set in the `PrettyConfig`.
+    pub fn write_pretty_with<W: Write>(&self, writer: &mut W, config: &PrettyConfig) -> io::Result<()> {
+        let mut gen = PrettyWriterGenerator::with_config(writer, config.clone());
+        try!(gen.write_json(self));
+        gen.finish()
+    }
+
+    /// Prints out the value in the canonical form of RFC 8785, the JSON
+    /// Canonicalization Scheme, meant for hashing and signing: without any
+    /// whitespace, object keys sorted by their UTF-16 code units, and
+    /// numbers laid out the same way as ECMAScript does. The same data
+    /// always comes out as the very same bytes, whichever order the keys
+    /// were inserted in, or however the numbers were written in the source.
+    ///
+    /// RFC 8785 doesn't allow numbers that are not finite, such as `NaN` or
+    /// `1e400`, which is too large for an `f64`. Values holding any have no
+    /// canonical form, and give `None`.
+    ///
+    /// ```
+    /// let data = json::parse(r#"{"b": [1.0, 1e21, 0.0000001], "a": "\u00e9", "\u20ac": 1E2}"#).unwrap();
+    ///
+    /// assert_eq!(data.canonical().unwrap(), r#"{"a":"é","b":[1,1e+21,1e-7],"€":100}"#);
+    /// assert_eq!(json::parse("[1e400]").unwrap().canonical(), None);
+    /// ```
+    pub fn canonical(&self) -> Option<String> {
+        let mut code = Vec::with_capacity(1024);
+
+        if self.write_canonical(&mut code).is_err() {
+            return None;
+        }
+
+        // Original strings were unicode, numbers are all ASCII,
+        // therefore this is safe.
+        Some(unsafe { String::from_utf8_unchecked(code) })
+    }
+
+    /// Writes the canonical form of the JSON, see `canonical`, as byte
+    /// stream into an implementor of `std::io::Write`. Numbers that are not
+    /// finite fail with `io::ErrorKind::InvalidData`.
+    pub fn write_canonical<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let mut gen = CanonicalGenerator::new(writer);
+        gen.write_json(self)
+    }
+
     pub fn is_string(&self) -> bool {
         match *self {
             JsonValue::Short(_)  => true,
             JsonValue::String(_) => true,
+            JsonValue::Wtf8(_)   => true,
             _                    => false,
         }
     }
 
     pub fn is_number(&self) -> bool {
         match *self {
-            JsonValue::Number(_) => true,
-            _                    => false,
+            JsonValue::Number(_)    => true,
+            JsonValue::RawNumber(_) => true,
+            _                       => false,
         }
     }
 
@@ -179,13 +296,18 @@ impl JsonValue {
             JsonValue::Null               => true,
             JsonValue::Short(ref value)   => value.is_empty(),
             JsonValue::String(ref value)  => value.is_empty(),
+            JsonValue::Wtf8(_)            => false,
             JsonValue::Number(ref value)  => value.is_empty(),
+            JsonValue::RawNumber(ref raw) => raw.as_number().is_empty(),
             JsonValue::Boolean(ref value) => !value,
             JsonValue::Array(ref value)   => value.is_empty(),
             JsonValue::Object(ref value)  => value.is_empty(),
         }
     }
 
+    /// Obtain the string, if the value is one. Strings with lone
+    /// surrogates in them, `JsonValue::Wtf8`, are no valid `str` and give
+    /// `None`.
     pub fn as_str(&self) -> Option<&str> {
         match *self {
             JsonValue::Short(ref value)  => Some(value),
@@ -194,10 +316,36 @@ impl JsonValue {
         }
     }
 
+    /// Obtain the number, if the value is one. For raw numbers this is
+    /// the number they were parsed into, with the same loss of precision
+    /// as regular parsing causes.
     pub fn as_number(&self) -> Option<Number> {
         match *self {
-            JsonValue::Number(value) => Some(value),
-            _                        => None
+            JsonValue::Number(value)      => Some(value),
+            JsonValue::RawNumber(ref raw) => Some(raw.as_number()),
+            _                             => None
+        }
+    }
+
+    /// Obtain the original lexical form of a number, if the value was
+    /// parsed with `ParserOptions::lossless_numbers` enabled.
+    ///
+    /// ```
+    /// # use json::ParserOptions;
+    /// let data = ParserOptions::new()
+    ///     .lossless_numbers(true)
+    ///     .parse("[12345678901234567890123, 1.50]")
+    ///     .unwrap();
+    ///
+    /// assert_eq!(data[0].as_raw_number(), Some("12345678901234567890123"));
+    /// assert_eq!(data[1].as_raw_number(), Some("1.50"));
+    /// assert_eq!(data[1], 1.5);
+    /// assert_eq!(data.dump(), "[12345678901234567890123,1.50]");
+    /// ```
+    pub fn as_raw_number(&self) -> Option<&str> {
+        match *self {
+            JsonValue::RawNumber(ref raw) => Some(raw.as_str()),
+            _                             => None
         }
     }
 
@@ -280,10 +428,7 @@ impl JsonValue {
     /// assert_eq!(price_c.as_fixed_point_u64(2), Some(1020));
     /// ```
     pub fn as_fixed_point_u64(&self, point: u16) -> Option<u64> {
-        match *self {
-            JsonValue::Number(ref value) => value.as_fixed_point_u64(point),
-            _                            => None
-        }
+        self.as_number().and_then(|value| value.as_fixed_point_u64(point))
     }
 
     /// Analog to `as_fixed_point_u64`, except returning a signed
@@ -298,10 +443,7 @@ impl JsonValue {
     /// assert_eq!(balance_b.as_fixed_point_i64(2), Some(4200));
     /// ```
     pub fn as_fixed_point_i64(&self, point: u16) -> Option<i64> {
-        match *self {
-            JsonValue::Number(ref value) => value.as_fixed_point_i64(point),
-            _                            => None
-        }
+        self.as_number().and_then(|value| value.as_fixed_point_i64(point))
     }
 
     /// Take over the ownership of the value, leaving `Null` in it's place.
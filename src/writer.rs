@@ -0,0 +1,298 @@
+// Streaming writer
+// ================
+//
+// Write JSON piece by piece, straight into a stream, for data too large to
+// be put into a `JsonValue` first. The writer keeps a stack of the arrays
+// and objects that are open, so it knows where commas, colons and new lines
+// go, and can tell when it's being used in a way that would make for
+// invalid JSON. Everything else is left to the generators.
+
+use std::io::{ self, Write };
+use codegen::{ Generator, WriterGenerator, PrettyWriterGenerator, PrettyConfig };
+use number::Number;
+use JsonValue;
+
+// An array or object that is open: how many members, or keys, it has so
+// far, and whether a key is waiting for its value, objects only.
+struct Frame {
+    object: bool,
+    count: usize,
+    key: bool,
+}
+
+enum Output<W: Write> {
+    Compact(WriterGenerator<W>),
+    Pretty(PrettyWriterGenerator<W>),
+}
+
+// Run the same code with whichever generator is in use, noting down if
+// writing fails.
+macro_rules! generate {
+    ($writer:ident, $gen:ident => $body:expr) => {{
+        let result = match $writer.output {
+            Output::Compact(ref mut $gen) => $body,
+            Output::Pretty(ref mut $gen)  => $body,
+        };
+
+        if result.is_err() {
+            $writer.failed = true;
+        }
+
+        result
+    }}
+}
+
+fn misuse<T>(message: &'static str) -> io::Result<T> {
+    Err(io::Error::new(io::ErrorKind::InvalidInput, message))
+}
+
+/// Writer of a single JSON value into a stream, one piece at a time, without
+/// building a `JsonValue` for the whole of it first. Commas, colons and, for
+/// pretty printing, new lines and indentation are put in as needed.
+///
+/// Using the writer in a way that would make for invalid JSON, such as
+/// writing a key outside of an object, or a value where a key is expected,
+/// fails with an `io::Error` of the `InvalidInput` kind, without writing
+/// anything.
+///
+/// Should writing into the stream fail, whatever has been written of the
+/// value so far is left as it is, and every call after that fails as well,
+/// as nothing can be added to make for valid JSON.
+///
+/// ```
+/// # #[macro_use] extern crate json;
+/// # fn main() {
+/// use json::JsonWriter;
+///
+/// let mut writer = JsonWriter::new(Vec::new());
+///
+/// writer.begin_object().unwrap();
+/// writer.key("rows").unwrap();
+/// writer.begin_array().unwrap();
+///
+/// for id in 1 .. 3 {
+///     writer.value(&object!{ "id" => id }).unwrap();
+/// }
+///
+/// writer.end().unwrap();
+/// writer.key("total").unwrap();
+/// writer.number(2).unwrap();
+/// writer.end().unwrap();
+///
+/// assert!(writer.key("late").is_err());
+/// assert_eq!(writer.finish().unwrap(), br#"{"rows":[{"id":1},{"id":2}],"total":2}"#);
+/// # }
+/// ```
+pub struct JsonWriter<W: Write> {
+    output: Output<W>,
+
+    // Arrays and objects that are open, innermost last
+    stack: Vec<Frame>,
+
+    // Set once the top level value has been started
+    started: bool,
+
+    // Set once writing into the stream has failed
+    failed: bool,
+}
+
+impl<W: Write> JsonWriter<W> {
+    /// Create a new `JsonWriter` writing compact JSON into the stream.
+    pub fn new(writer: W) -> Self {
+        JsonWriter {
+            output: Output::Compact(WriterGenerator::new(writer)),
+            stack: Vec::new(),
+            started: false,
+            failed: false,
+        }
+    }
+
+    /// Create a new `JsonWriter` pretty printing JSON into the stream, laid
+    /// out as set in the `PrettyConfig`. Only values written with `value`
+    /// can be kept on a single line, see `PrettyConfig::max_width`.
+    pub fn pretty(writer: W, config: PrettyConfig) -> Self {
+        JsonWriter {
+            output: Output::Pretty(PrettyWriterGenerator::with_config(writer, config)),
+            stack: Vec::new(),
+            started: false,
+            failed: false,
+        }
+    }
+
+    /// Start an array, to be closed with `end`.
+    pub fn begin_array(&mut self) -> io::Result<()> {
+        try!(self.before_value());
+        try!(generate!(self, gen => gen.begin_array()));
+
+        self.stack.push(Frame {
+            object: false,
+            count: 0,
+            key: false,
+        });
+
+        Ok(())
+    }
+
+    /// Start an object, to be closed with `end`. Each value in the object
+    /// has to be preceded by its key, written with `key`.
+    pub fn begin_object(&mut self) -> io::Result<()> {
+        try!(self.before_value());
+        try!(generate!(self, gen => gen.begin_object()));
+
+        self.stack.push(Frame {
+            object: true,
+            count: 0,
+            key: false,
+        });
+
+        Ok(())
+    }
+
+    /// Write the key of the next value in the current object.
+    pub fn key(&mut self, key: &str) -> io::Result<()> {
+        try!(self.check());
+
+        let first = match self.stack.last_mut() {
+            Some(ref mut frame) if frame.object && !frame.key => {
+                frame.key = true;
+                frame.count += 1;
+                frame.count == 1
+            },
+            Some(ref frame) if frame.object => return misuse("Expected a value after the key"),
+            _ => return misuse("Keys can only be written inside of an object"),
+        };
+
+        try!(self.separate(first));
+
+        generate!(self, gen => gen.write_key(key))
+    }
+
+    /// Write a whole value, such as a row built from a database cursor.
+    pub fn value(&mut self, value: &JsonValue) -> io::Result<()> {
+        try!(self.before_value());
+
+        generate!(self, gen => gen.write_json(value))
+    }
+
+    /// Write a string.
+    pub fn string(&mut self, string: &str) -> io::Result<()> {
+        try!(self.before_value());
+
+        generate!(self, gen => gen.write_string(string))
+    }
+
+    /// Write a number.
+    pub fn number<N: Into<Number>>(&mut self, number: N) -> io::Result<()> {
+        let number = number.into();
+
+        try!(self.before_value());
+
+        generate!(self, gen => gen.write_number(&number))
+    }
+
+    /// Close the innermost array or object that is open.
+    pub fn end(&mut self) -> io::Result<()> {
+        try!(self.check());
+
+        let (object, count) = match self.stack.last() {
+            Some(frame) if frame.key => return misuse("Expected a value after the key"),
+            Some(frame)              => (frame.object, frame.count),
+            None                     => return misuse("There is no array or object to end"),
+        };
+
+        self.stack.pop();
+
+        generate!(self, gen => {
+            let result = if count != 0 {
+                gen.dedent();
+                gen.new_line()
+            } else {
+                Ok(())
+            };
+
+            result.and_then(|_| if object {
+                gen.end_object()
+            } else {
+                gen.end_array()
+            })
+        })
+    }
+
+    /// Check that a whole value has been written, and obtain the underlying
+    /// stream back. For pretty printing, this also writes the trailing new
+    /// line, if there is to be one.
+    pub fn finish(self) -> io::Result<W> {
+        try!(self.check());
+
+        if !self.started {
+            return misuse("Nothing has been written");
+        }
+
+        if !self.stack.is_empty() {
+            return misuse("Not all arrays and objects have been ended");
+        }
+
+        match self.output {
+            Output::Compact(gen)   => Ok(gen.into_inner()),
+            Output::Pretty(mut gen) => {
+                try!(gen.finish());
+
+                Ok(gen.into_inner())
+            },
+        }
+    }
+
+    // Make sure a value can be written at this point, and write whatever
+    // has to come before it.
+    fn before_value(&mut self) -> io::Result<()> {
+        try!(self.check());
+
+        let first = match self.stack.last_mut() {
+            None if self.started => return misuse("Only a single value can be written"),
+            None => {
+                self.started = true;
+
+                return Ok(());
+            },
+            Some(ref mut frame) if frame.object => {
+                if !frame.key {
+                    return misuse("Expected a key");
+                }
+
+                frame.key = false;
+
+                return Ok(());
+            },
+            Some(ref mut frame) => {
+                frame.count += 1;
+                frame.count == 1
+            },
+        };
+
+        self.separate(first)
+    }
+
+    // Put the next member of an array or object on a new line, after a comma
+    // unless it's the first one.
+    fn separate(&mut self, first: bool) -> io::Result<()> {
+        generate!(self, gen => {
+            let result = if first {
+                gen.indent();
+                Ok(())
+            } else {
+                gen.write_char(b',')
+            };
+
+            result.and_then(|_| gen.new_line())
+        })
+    }
+
+    // Fail once writing has, as the output is then incomplete.
+    fn check(&self) -> io::Result<()> {
+        if self.failed {
+            return Err(io::Error::new(io::ErrorKind::Other, "Writing has failed before"));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,151 @@
+//! Strings with lone surrogates in them, see `LoneSurrogates::Preserve`.
+
+use std::{ fmt, str };
+
+/// A string that can hold UTF-16 surrogates that are not part of a pair,
+/// on top of all that a regular `String` can. Such strings can't be
+/// decoded into a valid `str`, so they are kept as WTF-8, which is UTF-8
+/// extended with the three byte encoding of surrogates.
+///
+/// These are only ever created by the parser, and they are written back
+/// out with each lone surrogate escaped as it was read.
+///
+/// ```
+/// use json::{ ParserOptions, LoneSurrogates, JsonValue };
+///
+/// let options = ParserOptions::new().lone_surrogates(LoneSurrogates::Preserve);
+/// let data = options.parse(r#""a\uD83D!""#).unwrap();
+///
+/// if let JsonValue::Wtf8(ref string) = data {
+///     assert_eq!(string.to_string_lossy(), "a\u{FFFD}!");
+///     assert_eq!(string.encode_utf16(), vec![0x61, 0xD83D, 0x21]);
+/// } else {
+///     panic!("Should be a WTF-8 string");
+/// }
+///
+/// assert_eq!(data.dump(), r#""a\ud83d!""#);
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct Wtf8String {
+    bytes: Vec<u8>,
+}
+
+impl Wtf8String {
+    // The bytes have to be valid WTF-8, with at least one lone surrogate.
+    pub(crate) fn from_bytes(bytes: Vec<u8>) -> Self {
+        Wtf8String {
+            bytes: bytes,
+        }
+    }
+
+    /// Obtain the WTF-8 encoded bytes of the string.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Copy the string into a `String`, with each lone surrogate replaced
+    /// by U+FFFD, the same as `LoneSurrogates::Replace` does.
+    pub fn to_string_lossy(&self) -> String {
+        self.to_string()
+    }
+
+    /// Encode the string as UTF-16 code units, lone surrogates included.
+    pub fn encode_utf16(&self) -> Vec<u16> {
+        let mut units = Vec::with_capacity(self.bytes.len());
+
+        for piece in self.pieces() {
+            match piece {
+                Piece::Str(text)       => units.extend(text.encode_utf16()),
+                Piece::Surrogate(unit) => units.push(unit),
+            }
+        }
+
+        units
+    }
+
+    // Split the string into runs of valid UTF-8 and the lone surrogates
+    // between them.
+    pub(crate) fn pieces(&self) -> Pieces {
+        Pieces {
+            bytes: &self.bytes,
+        }
+    }
+}
+
+pub(crate) enum Piece<'a> {
+    Str(&'a str),
+    Surrogate(u16),
+}
+
+pub(crate) struct Pieces<'a> {
+    bytes: &'a [u8],
+}
+
+// Surrogates are encoded as ED A0 to ED BF followed by one more byte, in
+// valid UTF-8 ED can only be followed by 80 to 9F.
+#[inline]
+fn is_surrogate(bytes: &[u8]) -> bool {
+    bytes.len() >= 3 && bytes[0] == 0xED && bytes[1] >= 0xA0
+}
+
+impl<'a> Iterator for Pieces<'a> {
+    type Item = Piece<'a>;
+
+    fn next(&mut self) -> Option<Piece<'a>> {
+        if self.bytes.is_empty() {
+            return None;
+        }
+
+        if is_surrogate(self.bytes) {
+            let unit = 0xD000 | (self.bytes[1] as u16 & 0x3F) << 6 | (self.bytes[2] as u16 & 0x3F);
+
+            self.bytes = &self.bytes[3 ..];
+
+            return Some(Piece::Surrogate(unit));
+        }
+
+        let mut end = 1;
+
+        while end < self.bytes.len() && !is_surrogate(&self.bytes[end ..]) {
+            end += 1;
+        }
+
+        let (text, rest) = self.bytes.split_at(end);
+
+        self.bytes = rest;
+
+        // Everything but the surrogates is valid UTF-8
+        Some(Piece::Str(unsafe { str::from_utf8_unchecked(text) }))
+    }
+}
+
+/// Lone surrogates are written as U+FFFD, see `to_string_lossy`.
+impl fmt::Display for Wtf8String {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for piece in self.pieces() {
+            match piece {
+                Piece::Str(text)    => try!(f.write_str(text)),
+                Piece::Surrogate(_) => try!(f.write_str("\u{FFFD}")),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Wtf8String {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(f.write_str("\""));
+
+        for piece in self.pieces() {
+            match piece {
+                Piece::Str(text) => for ch in text.chars() {
+                    try!(write!(f, "{}", ch.escape_default()));
+                },
+                Piece::Surrogate(unit) => try!(write!(f, "\\u{{{:x}}}", unit)),
+            }
+        }
+
+        f.write_str("\"")
+    }
+}
@@ -0,0 +1,119 @@
+#[macro_use]
+extern crate json;
+
+use std::borrow::Cow;
+use json::{ parse, parse_borrowed, BorrowedValue, ParserOptions, DuplicateKeys, Error };
+
+fn is_borrowed(value: &BorrowedValue) -> bool {
+    match *value {
+        BorrowedValue::String(Cow::Borrowed(_)) => true,
+        _                                       => false,
+    }
+}
+
+#[test]
+fn borrowed_matches_owned() {
+    let sources = [
+        r#"{"a":[1,2.5,-3e2,{"b":null}],"c":"é\n\"","d":{},"e":[],"f":true,"g":false}"#,
+        "  [ \"x\" , 0.1, 1e500, -0 ]  ",
+        "\"just a string\"",
+        "12345678901234567890",
+    ];
+
+    for source in sources.iter() {
+        assert_eq!(parse_borrowed(source).unwrap().to_json(), parse(source).unwrap());
+    }
+}
+
+#[test]
+fn borrowed_strings_point_into_source() {
+    let source = r#"{"plain":"text","escaped":"tab\there","uni\u0063ode":"\u00e9"}"#;
+    let data = parse_borrowed(source).unwrap();
+
+    assert!(is_borrowed(&data["plain"]));
+    assert!(!is_borrowed(&data["escaped"]));
+    assert_eq!(data["escaped"], "tab\there");
+    assert_eq!(data["unicode"], "é");
+
+    let plain = data["plain"].as_str().unwrap();
+    let range = source.as_ptr() as usize .. source.as_ptr() as usize + source.len();
+
+    assert!(range.contains(&(plain.as_ptr() as usize)));
+
+    let keys: Vec<_> = data.entries().map(|(key, _)| key).collect();
+
+    assert_eq!(keys, vec!["plain", "escaped", "unicode"]);
+}
+
+#[test]
+fn borrowed_read_api() {
+    let data = parse_borrowed(r#"{"n":-42,"f":1.5,"b":true,"list":[1,"two",null],"empty":{}}"#).unwrap();
+
+    assert_eq!(data["n"].as_i32(), Some(-42));
+    assert_eq!(data["n"].as_u32(), None);
+    assert_eq!(data["f"].as_f64(), Some(1.5));
+    assert_eq!(data["f"].as_fixed_point_u64(2), Some(150));
+    assert_eq!(data["b"].as_bool(), Some(true));
+    assert_eq!(data["list"].len(), 3);
+    assert_eq!(data["list"][1], "two");
+    assert!(data["list"][2].is_null());
+    assert!(data["list"][3].is_null());
+    assert!(data["missing"]["deeper"].is_null());
+    assert!(data["empty"].is_empty());
+    assert!(data.has_key("list"));
+    assert!(!data["list"].has_key("list"));
+    assert_eq!(data["list"].members().filter(|member| member.is_number()).count(), 1);
+    assert_eq!(data.entries().count(), 5);
+    assert_eq!(data["n"].entries().count(), 0);
+    assert_eq!(data.to_string(), data.to_json().dump());
+}
+
+#[test]
+fn borrowed_large_objects() {
+    let mut source = String::from("{");
+
+    for index in 0 .. 100 {
+        source.push_str(&format!("\"key{}\":{},", index % 50, index));
+    }
+
+    source.push_str("\"last\":true}");
+
+    let data = parse_borrowed(&source).unwrap();
+
+    assert_eq!(data.len(), 51);
+    assert_eq!(data["key0"], 50);
+    assert_eq!(data["key49"], 99);
+    assert_eq!(data.to_json(), parse(&source).unwrap());
+}
+
+#[test]
+fn borrowed_with_options() {
+    let source = r#"{"a":1,"a":[2],"big":1.000000000000000000001}"#;
+
+    let options = ParserOptions::new().duplicate_keys(DuplicateKeys::Collect);
+
+    assert_eq!(options.parse_borrowed(source).unwrap().to_json(), options.parse(source).unwrap());
+
+    let options = ParserOptions::new().duplicate_keys(DuplicateKeys::FirstWins).lossless_numbers(true);
+    let data = options.parse_borrowed(source).unwrap();
+
+    assert_eq!(data["a"], 1);
+    assert_eq!(data["big"].as_raw_number(), Some("1.000000000000000000001"));
+
+    let options = ParserOptions::new().duplicate_keys(DuplicateKeys::Error);
+
+    assert_eq!(options.parse_borrowed(source).unwrap_err(), options.parse(source).unwrap_err());
+
+    let options = ParserOptions::new().relaxed(true);
+    let data = options.parse_borrowed("{a: 'b', /* c */ list: [+1, .5,],}").unwrap();
+
+    assert_eq!(data.to_json(), object!{ "a" => "b", "list" => array![1, 0.5] });
+}
+
+#[test]
+fn borrowed_errors() {
+    assert_eq!(parse_borrowed("[1,"), Err(Error::UnexpectedEndOfJson));
+    assert_eq!(parse_borrowed("[1] x").unwrap_err(), parse("[1] x").unwrap_err());
+    assert_eq!(parse_borrowed("\"\\x\"").unwrap_err(), parse("\"\\x\"").unwrap_err());
+    assert_eq!(ParserOptions::new().depth_limit(1).parse_borrowed("[[1]]"), Err(Error::ExceededDepthLimit));
+}
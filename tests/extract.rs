@@ -0,0 +1,74 @@
+#[macro_use]
+extern crate json;
+
+use json::{ parse, extract, ParserOptions, Error };
+
+#[test]
+fn extract_values() {
+    let source = r#"{
+        "user": {"id": 7, "name": "Ann", "tags": ["a", "b"]},
+        "skipped": [{"deep": [1, 2, {"x": "]}"}]}, "\"quoted\" \\ {"],
+        "items": [{"price": 9.5}, {"price": 3, "note": null}],
+        "a/b": 1,
+        "m~n": 2,
+        "": "empty key"
+    }"#;
+
+    let found = extract(source, &[
+        "/user/id",
+        "/items/1/price",
+        "/items/2",
+        "/user",
+        "/user/tags/1",
+        "/a~1b",
+        "/m~0n",
+        "/",
+        "/items/01",
+        "/items/-",
+        "missing slash",
+        "/user/id/deeper",
+    ]).unwrap();
+
+    assert_eq!(found, vec![
+        Some(7.into()),
+        Some(3.into()),
+        None,
+        Some(object!{ "id" => 7, "name" => "Ann", "tags" => array!["a", "b"] }),
+        Some("b".into()),
+        Some(1.into()),
+        Some(2.into()),
+        Some("empty key".into()),
+        None,
+        None,
+        None,
+        None,
+    ]);
+}
+
+#[test]
+fn extract_root_and_scalars() {
+    assert_eq!(extract("[1, [2, 3]]", &["", "/1/0"]).unwrap(), vec![Some(parse("[1, [2, 3]]").unwrap()), Some(2.into())]);
+    assert_eq!(extract("  42 ", &["", "/0"]).unwrap(), vec![Some(42.into()), None]);
+    assert_eq!(extract("{}", &[]).unwrap(), vec![]);
+}
+
+#[test]
+fn extract_last_key_wins() {
+    let source = r#"{"a":{"b":1},"c":[],"a":{"c":2}}"#;
+
+    assert_eq!(extract(source, &["/a/b", "/a/c", "/a"]).unwrap(), vec![
+        None,
+        Some(2.into()),
+        Some(parse(source).unwrap()["a"].clone()),
+    ]);
+}
+
+#[test]
+fn extract_errors() {
+    assert_eq!(extract("[1, 2", &["/0"]), Err(Error::UnexpectedEndOfJson));
+    assert_eq!(extract(r#"{"a": [1, {"b": "}"#, &["/z"]), Err(Error::UnexpectedEndOfJson));
+    assert_eq!(extract(r#"{"a": 1} x"#, &["/a"]).unwrap_err(), parse(r#"{"a": 1} x"#).unwrap_err());
+    assert_eq!(extract(r#"{"a": [1,,]}"#, &["/a/0"]).unwrap_err(), parse(r#"{"a": [1,,]}"#).unwrap_err());
+    assert_eq!(extract(r#"{"a": {"b": tru}}"#, &["/a/b"]).unwrap_err(), parse(r#"{"a": {"b": tru}}"#).unwrap_err());
+    assert_eq!(ParserOptions::new().depth_limit(1).extract("[[[1]]]", &["/0/0/0"]), Err(Error::ExceededDepthLimit));
+}
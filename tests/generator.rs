@@ -0,0 +1,178 @@
+#[macro_use]
+extern crate json;
+
+use std::io::{ self, Write };
+use json::{ Generator, DumpGenerator, PrettyGenerator, WriterGenerator, JsonValue };
+use json::number::Number;
+
+// Hide the values of secret keys, however deep they are
+struct Redacting {
+    inner: DumpGenerator,
+}
+
+impl Generator for Redacting {
+    type T = Vec<u8>;
+
+    fn get_writer(&mut self) -> &mut Vec<u8> {
+        self.inner.get_writer()
+    }
+
+    fn write_min(&mut self, slice: &[u8], min: u8) -> io::Result<()> {
+        self.inner.write_min(slice, min)
+    }
+
+    fn write_entry(&mut self, key: &str, value: &JsonValue) -> io::Result<()> {
+        try!(self.write_key(key));
+
+        if key == "password" {
+            self.write(b"\"***\"")
+        } else {
+            self.write_json(value)
+        }
+    }
+}
+
+// Mark every piece with what it is, pretty printed
+struct Marking {
+    inner: PrettyGenerator,
+}
+
+impl Generator for Marking {
+    type T = Vec<u8>;
+
+    fn get_writer(&mut self) -> &mut Vec<u8> {
+        self.inner.get_writer()
+    }
+
+    fn write_min(&mut self, slice: &[u8], min: u8) -> io::Result<()> {
+        self.inner.write_min(slice, min)
+    }
+
+    fn new_line(&mut self) -> io::Result<()> {
+        self.inner.new_line()
+    }
+
+    fn indent(&mut self) {
+        self.inner.indent()
+    }
+
+    fn dedent(&mut self) {
+        self.inner.dedent()
+    }
+
+    fn write_key(&mut self, key: &str) -> io::Result<()> {
+        try!(write!(self.get_writer(), "<k>{}</k>", key));
+        self.write_colon()
+    }
+
+    fn write_string(&mut self, string: &str) -> io::Result<()> {
+        try!(self.write(b"<s>"));
+        try!(self.inner.write_string(string));
+        self.write(b"</s>")
+    }
+
+    fn write_number(&mut self, num: &Number) -> io::Result<()> {
+        try!(self.write(b"<n>"));
+        try!(self.inner.write_number(num));
+        self.write(b"</n>")
+    }
+
+    fn begin_array(&mut self) -> io::Result<()> {
+        self.write(b"<a>")
+    }
+
+    fn end_array(&mut self) -> io::Result<()> {
+        self.write(b"</a>")
+    }
+
+    fn begin_object(&mut self) -> io::Result<()> {
+        self.write(b"<o>")
+    }
+
+    fn end_object(&mut self) -> io::Result<()> {
+        self.write(b"</o>")
+    }
+}
+
+// Stream failing once more than `limit` bytes are written into it
+struct Limited {
+    written: Vec<u8>,
+    limit: usize,
+}
+
+impl Write for Limited {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.written.len() + buf.len() > self.limit {
+            return Err(io::Error::new(io::ErrorKind::Other, "Output too large"));
+        }
+
+        self.written.extend_from_slice(buf);
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn generator_redacting() {
+    let data = object!{
+        "user" => "ferris",
+        "password" => "hunter2",
+        "nested" => array![object!{ "password" => array![1, 2] }, object!{}]
+    };
+
+    let mut gen = Redacting { inner: DumpGenerator::new() };
+
+    gen.write_json(&data).unwrap();
+
+    assert_eq!(gen.inner.consume().unwrap(), r#"{"user":"ferris","password":"***","nested":[{"password":"***"},{}]}"#);
+}
+
+#[test]
+fn generator_marking() {
+    let data = object!{
+        "id" => 1,
+        "tags" => array!["a"],
+        "empty" => object!{}
+    };
+
+    let mut gen = Marking { inner: PrettyGenerator::new(1) };
+
+    gen.write_json(&data).unwrap();
+
+    assert_eq!(gen.inner.consume().unwrap(), "<o>\n <k>id</k>: <n>1</n>,\n <k>tags</k>: <a>\n  <s>\"a\"</s>\n </a>,\n <k>empty</k>: <o></o>\n</o>");
+}
+
+#[test]
+fn generator_size_limited() {
+    let data = array!["lorem", "ipsum", "dolor", "sit", "amet"];
+
+    let mut gen = WriterGenerator::new(Limited { written: Vec::new(), limit: 20 });
+
+    assert_eq!(gen.write_json(&data).unwrap_err().kind(), io::ErrorKind::Other);
+    assert_eq!(gen.into_inner().written, br#"["lorem","ipsum",""#);
+
+    let mut gen = WriterGenerator::new(Limited { written: Vec::new(), limit: 100 });
+
+    gen.write_json(&data).unwrap();
+
+    assert_eq!(String::from_utf8(gen.into_inner().written).unwrap(), data.dump());
+}
+
+#[test]
+fn generator_consume_invalid_utf8() {
+    let mut gen = DumpGenerator::new();
+
+    gen.write(&[0xFF]).unwrap();
+
+    assert!(gen.consume().is_err());
+
+    let mut gen = PrettyGenerator::new(2);
+
+    gen.write_json(&array!["é"]).unwrap();
+
+    assert_eq!(gen.consume().unwrap(), "[\n  \"é\"\n]");
+}
@@ -0,0 +1,133 @@
+#[macro_use]
+extern crate json;
+
+use std::io::{ self, BufReader, Read };
+use json::{ JsonSeqReader, JsonSeqWriter, ParserOptions, Error };
+
+#[test]
+fn json_seq_read_records() {
+    let source = b"\x1e{\"a\":1}\n\x1e\x1e\n\x1e[1,2]\n\x1e\"last\"";
+    let values: Vec<_> = JsonSeqReader::new(&source[..]).map(Result::unwrap).collect();
+
+    assert_eq!(values, vec![
+        object!{ "a" => 1 },
+        array![1, 2],
+        "last".into(),
+    ]);
+}
+
+#[test]
+fn json_seq_read_truncated() {
+    let source = b"\x1e[1,2\x1e123\n\x1e123\x1etrue\x1enull \x1e{\"ok\":true}";
+    let results: Vec<_> = JsonSeqReader::new(&source[..]).collect();
+
+    assert_eq!(results, vec![
+        Err(Error::UnexpectedEndOfJson),
+        Ok(123.into()),
+        Err(Error::UnexpectedEndOfJson),
+        Err(Error::UnexpectedEndOfJson),
+        Ok(json::Null),
+        Ok(object!{ "ok" => true }),
+    ]);
+}
+
+#[test]
+fn json_seq_read_garbage_before_first_record() {
+    let source = b"  junk\x1e1\n";
+    let results: Vec<_> = JsonSeqReader::new(&source[..]).collect();
+
+    assert_eq!(results, vec![
+        Err(Error::UnexpectedCharacter { ch: 'j', line: 1, column: 3 }),
+        Ok(1.into()),
+    ]);
+}
+
+#[test]
+fn json_seq_read_unicode_garbage_before_first_record() {
+    let source = "\n  ☃\x1e1\n";
+    let results: Vec<_> = JsonSeqReader::new(source.as_bytes()).collect();
+
+    assert_eq!(results, vec![
+        Err(Error::UnexpectedCharacter { ch: '☃', line: 2, column: 3 }),
+        Ok(1.into()),
+    ]);
+}
+
+#[test]
+fn json_seq_read_with_options() {
+    let options = ParserOptions::new().array_length_limit(1);
+    let source = b"\x1e[1]\n\x1e[1,2]\n";
+    let results: Vec<_> = JsonSeqReader::with_options(&source[..], options).collect();
+
+    assert_eq!(results, vec![
+        Ok(array![1]),
+        Err(Error::ExceededArrayLengthLimit),
+    ]);
+}
+
+#[test]
+fn json_seq_read_record_limit() {
+    let long = format!("[{}]", vec!["1"; 1000].join(","));
+    let source = format!("\x1e[1]\n\x1e{}\n\x1e[2]\n\x1e{}", long, long);
+    let options = ParserOptions::new().size_limit(64);
+    let results: Vec<_> = JsonSeqReader::with_options(source.as_bytes(), options).collect();
+
+    assert_eq!(results, vec![
+        Ok(array![1]),
+        Err(Error::ExceededSizeLimit),
+        Ok(array![2]),
+        Err(Error::ExceededSizeLimit),
+    ]);
+
+    // Records are read whole without a limit
+    assert_eq!(JsonSeqReader::new(source.as_bytes()).filter(|value| value.is_ok()).count(), 4);
+}
+
+#[test]
+fn json_seq_read_failure() {
+    struct Broken(bool);
+
+    impl Read for Broken {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0 {
+                return Err(io::Error::new(io::ErrorKind::ConnectionReset, "gone"));
+            }
+            self.0 = true;
+            buf[.. 7].copy_from_slice(b"\x1etrue\n\x1e");
+            Ok(7)
+        }
+    }
+
+    let mut records = JsonSeqReader::new(BufReader::new(Broken(false)));
+
+    assert_eq!(records.next(), Some(Ok(true.into())));
+
+    match records.next() {
+        Some(Err(Error::FailedReading { kind, .. })) => assert_eq!(kind, io::ErrorKind::ConnectionReset),
+        other => panic!("Unexpected {:?}", other),
+    }
+
+    assert_eq!(records.next(), None);
+}
+
+#[test]
+fn json_seq_round_trip() {
+    let values = vec![
+        object!{ "text" => "record\u{1e}separator", "n" => 1.5 },
+        array![json::Null, false],
+        json::from(-7),
+    ];
+
+    let mut writer = JsonSeqWriter::new(Vec::new());
+    writer.write_all(&values).unwrap();
+    writer.flush().unwrap();
+
+    let output = writer.into_inner();
+
+    assert_eq!(output.iter().filter(|&&ch| ch == 0x1E).count(), 3);
+    assert_eq!(output.last(), Some(&b'\n'));
+
+    let parsed: Vec<_> = JsonSeqReader::new(&output[..]).map(Result::unwrap).collect();
+
+    assert_eq!(parsed, values);
+}
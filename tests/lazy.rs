@@ -0,0 +1,133 @@
+#[macro_use]
+extern crate json;
+
+use std::borrow::Cow;
+use json::{ parse, parse_lazy, ParserOptions, LoneSurrogates, DuplicateKeys, JsonValue, Error };
+
+#[test]
+fn lazy_matches_parse() {
+    let sources = [
+        r#"{"a":[1,2.5,-3e2,{"b":null}],"c":"é\n\"","d":{},"e":[],"f":true,"g":false}"#,
+        "  [ \"x\" , 0.1, 1e500, -0, 12345678901234567890 ]  ",
+        "\"just a string\"",
+        "-12.5E+3",
+        "null",
+    ];
+
+    for source in sources.iter() {
+        let data = parse_lazy(source).unwrap();
+
+        assert_eq!(data.to_json().unwrap(), parse(source).unwrap());
+        assert_eq!(*data, parse(source).unwrap());
+    }
+}
+
+#[test]
+fn lazy_access() {
+    let source = r#"{
+        "id": 42,
+        "name": "plain",
+        "quote": "say \"hi\"",
+        "ratio": -0.5,
+        "flags": [true, false, null],
+        "nested": {"deep": [[1], [2, [3]]], "empty": {}},
+        "id": 43
+    }"#;
+
+    let data = parse_lazy(source).unwrap();
+
+    assert!(data.is_object());
+    assert_eq!(data["id"], 43);
+    assert_eq!(data["ratio"].as_f64(), Some(-0.5));
+    assert_eq!(data["ratio"].as_u64(), None);
+    assert_eq!(data["name"], "plain");
+    assert_eq!(data["quote"], "say \"hi\"");
+    assert_eq!(data["flags"][0], true);
+    assert_eq!(data["flags"][1].as_bool(), Some(false));
+    assert!(data["flags"][2].is_null());
+    assert!(data["flags"][3].is_null());
+    assert_eq!(data["nested"]["deep"][1][1][0], 3);
+    assert!(data["nested"]["empty"].is_empty());
+    assert_eq!(data["nested"]["deep"].as_json_str(), "[[1], [2, [3]]]");
+    assert!(data["missing"]["deeper"][0].is_null());
+    assert!(data.has_key("quote"));
+    assert!(!data["flags"].has_key("quote"));
+    assert_eq!(data["flags"].len(), 3);
+    assert_eq!(data["flags"].members().filter(|member| member.is_boolean()).count(), 2);
+    assert_eq!(data["flags"].to_string(), "[true,false,null]");
+
+    match data["name"].as_str() {
+        Some(Cow::Borrowed("plain")) => {},
+        other                        => panic!("Expected a borrowed string, got {:?}", other),
+    }
+
+    let keys: Vec<_> = data.entries().map(|(key, _)| key.into_owned()).collect();
+
+    assert_eq!(keys, vec!["id", "name", "quote", "ratio", "flags", "nested", "id"]);
+
+    // Slices of the source outlive the document
+    let deep = parse_lazy(source).unwrap()["nested"]["deep"].as_json_str();
+
+    assert_eq!(deep, "[[1], [2, [3]]]");
+}
+
+#[test]
+fn lazy_escaped_keys() {
+    let data = parse_lazy(r#"{"uni\u0063ode":1,"tab\tbed":[2]}"#).unwrap();
+
+    assert_eq!(data["unicode"], 1);
+    assert_eq!(data["tab\tbed"][0], 2);
+}
+
+#[test]
+fn lazy_errors() {
+    let invalid = [
+        "", "[1,", "[1 2]", "{\"a\" 1}", "{\"a\":1,}", "[1,]", "01", "-", "1.", "1e", "1e+",
+        "[\"\\x\"]", "\"\x01\"", "tru", "{1:2}", "[1] x", "[1}", "{\"a\":1]",
+    ];
+
+    for source in invalid.iter() {
+        assert_eq!(parse_lazy(source).is_ok(), false, "{:?}", source);
+        assert_eq!(parse_lazy(source).unwrap_err(), parse(source).unwrap_err(), "{:?}", source);
+    }
+}
+
+#[test]
+fn lazy_limits() {
+    assert_eq!(ParserOptions::new().depth_limit(1).parse_lazy("[[1]]").unwrap_err(), Error::ExceededDepthLimit);
+    assert_eq!(ParserOptions::new().array_length_limit(2).parse_lazy("[1,2,3]").unwrap_err(), Error::ExceededArrayLengthLimit);
+    assert_eq!(ParserOptions::new().object_length_limit(0).parse_lazy(r#"{"a":1}"#).unwrap_err(), Error::ExceededObjectLengthLimit);
+    assert!(ParserOptions::new().depth_limit(1).parse_lazy("[[]]").is_ok());
+    assert_eq!(ParserOptions::new().parse_lazy("[[]]").unwrap()[0], array![]);
+}
+
+#[test]
+fn lazy_decodes_with_options() {
+    let relaxed = ParserOptions::new().relaxed(true);
+    let data = relaxed.parse_lazy("[1, /* two */ 2 // end\n]").unwrap();
+
+    assert_eq!(data.to_json().unwrap(), array![1, 2]);
+    assert_eq!(data.to_string(), "[1,2]");
+    assert_eq!(data[1], 2);
+
+    let source = r#"["\uD800", {"a": 1, "a": 2}]"#;
+
+    let data = ParserOptions::new().lone_surrogates(LoneSurrogates::Replace).parse_lazy(source).unwrap();
+
+    assert_eq!(data.to_json().unwrap(), array!["\u{FFFD}", object!{ "a" => 2 }]);
+    assert_eq!(data[0], "\u{FFFD}");
+
+    let options = ParserOptions::new()
+        .lone_surrogates(LoneSurrogates::Preserve)
+        .duplicate_keys(DuplicateKeys::Error);
+    let data = options.parse_lazy(source).unwrap();
+
+    match data.to_json().unwrap()[0] {
+        JsonValue::Wtf8(ref string) => assert_eq!(string.encode_utf16(), vec![0xD800]),
+        ref other                   => panic!("Expected a WTF-8 string, got {:?}", other),
+    }
+
+    assert_eq!(data.to_string(), r#"["\ud800",{"a":2}]"#);
+    assert_eq!(data[1]["a"], 2);
+    assert!(data[2].to_json().unwrap().is_null());
+}
@@ -0,0 +1,106 @@
+#[macro_use]
+extern crate json;
+
+use std::io::{ self, BufRead, BufReader, Read };
+use json::{ NdjsonReader, NdjsonWriter, ParserOptions, Error };
+
+#[test]
+fn ndjson_read_lines() {
+    let source = "{\"a\":1}\r\n  \n[1,2]\n\"last\"";
+    let values: Vec<_> = NdjsonReader::new(source.as_bytes())
+                             .map(|(line, value)| (line, value.unwrap()))
+                             .collect();
+
+    assert_eq!(values, vec![
+        (1, object!{ "a" => 1 }),
+        (3, array![1, 2]),
+        (4, "last".into()),
+    ]);
+}
+
+#[test]
+fn ndjson_read_errors() {
+    let source = "1\n[1,\n{\"a\" 2}\n3\n";
+    let results: Vec<_> = NdjsonReader::new(source.as_bytes()).collect();
+
+    assert_eq!(results.len(), 4);
+    assert_eq!(results[1], (2, Err(Error::UnexpectedEndOfJson)));
+    assert_eq!(results[2], (3, Err(Error::UnexpectedCharacter { ch: '2', line: 1, column: 6 })));
+    assert_eq!(results[3], (4, Ok(3.into())));
+}
+
+#[test]
+fn ndjson_read_with_options() {
+    let options = ParserOptions::new().array_length_limit(1);
+    let results: Vec<_> = NdjsonReader::with_options(&b"[1]\n[1,2]\n"[..], options).collect();
+
+    assert_eq!(results[0], (1, Ok(array![1])));
+    assert_eq!(results[1], (2, Err(Error::ExceededArrayLengthLimit)));
+}
+
+#[test]
+fn ndjson_read_failure() {
+    struct Broken(bool);
+
+    impl Read for Broken {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0 {
+                return Err(io::Error::new(io::ErrorKind::ConnectionReset, "gone"));
+            }
+            self.0 = true;
+            buf[.. 6].copy_from_slice(b"true\nf");
+            Ok(6)
+        }
+    }
+
+    let reader = BufReader::new(Broken(false));
+    let mut lines = NdjsonReader::new(reader);
+
+    assert_eq!(lines.next(), Some((1, Ok(true.into()))));
+
+    match lines.next() {
+        Some((2, Err(Error::FailedReading { kind, .. }))) => assert_eq!(kind, io::ErrorKind::ConnectionReset),
+        other => panic!("Unexpected {:?}", other),
+    }
+
+    assert_eq!(lines.next(), None);
+}
+
+#[test]
+fn ndjson_round_trip() {
+    let values = vec![
+        object!{ "text" => "line\nbreak", "n" => 1.5 },
+        array![json::Null, true],
+        json::from("plain"),
+    ];
+
+    let mut writer = NdjsonWriter::new(Vec::new());
+    writer.write_all(&values).unwrap();
+    writer.flush().unwrap();
+
+    let output = writer.into_inner();
+
+    assert_eq!(output.lines().count(), 3);
+
+    let parsed: Vec<_> = NdjsonReader::new(&output[..]).map(|(_, value)| value.unwrap()).collect();
+
+    assert_eq!(parsed, values);
+}
+
+#[test]
+fn ndjson_read_line_limit() {
+    let long = format!("[{}]", vec!["1"; 1000].join(","));
+    let source = format!("{}\n{}\n[2]\n{}", "[1]", long, long);
+    let options = ParserOptions::new().size_limit(64);
+    let results: Vec<_> = NdjsonReader::with_options(source.as_bytes(), options).collect();
+
+    assert_eq!(results, vec![
+        (1, Ok(array![1])),
+        (2, Err(Error::ExceededSizeLimit)),
+        (3, Ok(array![2])),
+        (4, Err(Error::ExceededSizeLimit)),
+    ]);
+
+    // Lines are read whole without a limit
+    assert_eq!(NdjsonReader::new(source.as_bytes()).filter(|&(_, ref value)| value.is_ok()).count(), 4);
+}
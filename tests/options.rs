@@ -0,0 +1,392 @@
+#[macro_use]
+extern crate json;
+
+use json::{ ParserOptions, DuplicateKeys, LoneSurrogates, PushParser, EventReader, JsonValue, Error };
+use json::number::{ Number, RawNumber };
+
+// Run the source through every entry point that takes options, making sure
+// they all agree on the outcome.
+fn check(options: ParserOptions, source: &str) -> Result<(), Error> {
+    let parsed = options.parse(source).map(|_| ());
+
+    assert_eq!(options.parse_bytes(source.as_bytes()).map(|_| ()), parsed);
+    assert_eq!(options.parse_reader(source.as_bytes()).map(|_| ()), parsed);
+
+    let mut parser = PushParser::with_options(options);
+    let pushed = source.as_bytes()
+                       .chunks(1)
+                       .map(|chunk| parser.feed(chunk))
+                       .collect::<Result<Vec<()>, Error>>()
+                       .and_then(|_| parser.finish().map(|_| ()));
+
+    assert_eq!(pushed, parsed);
+
+    let mut reader = EventReader::with_options(source, options);
+    let read = loop {
+        match reader.next_event() {
+            Ok(Some(_)) => continue,
+            Ok(None)    => break Ok(()),
+            Err(error)  => break Err(error),
+        }
+    };
+
+    assert_eq!(read, parsed);
+
+    parsed
+}
+
+#[test]
+fn default_options() {
+    assert_eq!(ParserOptions::new(), ParserOptions::default());
+    assert_eq!(check(ParserOptions::new(), r#"{"foo":[1,2,3],"bar":"baz"}"#), Ok(()));
+}
+
+#[test]
+fn depth_limit() {
+    let options = ParserOptions::new().depth_limit(2);
+
+    assert_eq!(check(options, "[[1]]"), Ok(()));
+    assert_eq!(check(options, "[{\"a\":[]}]"), Ok(()));
+    assert_eq!(check(options, "[[[1]]]"), Err(Error::ExceededDepthLimit));
+    assert_eq!(check(options, "[{\"a\":{\"b\":1}}]"), Err(Error::ExceededDepthLimit));
+}
+
+#[test]
+fn size_limit() {
+    let options = ParserOptions::new().size_limit(7);
+
+    assert_eq!(check(options, "[1,2,3]"), Ok(()));
+    assert_eq!(check(options, "[1,2,3] "), Err(Error::ExceededSizeLimit));
+}
+
+#[test]
+fn string_length_limit() {
+    let options = ParserOptions::new().string_length_limit(3);
+
+    assert_eq!(check(options, r#"["foo"]"#), Ok(()));
+    assert_eq!(check(options, r#"["fooo"]"#), Err(Error::ExceededStringLengthLimit));
+    assert_eq!(check(options, r#"["fo\no"]"#), Err(Error::ExceededStringLengthLimit));
+    assert_eq!(check(options, r#"{"fooo":1}"#), Err(Error::ExceededStringLengthLimit));
+}
+
+#[test]
+fn array_length_limit() {
+    let options = ParserOptions::new().array_length_limit(2);
+
+    assert_eq!(check(options, "[[1,2],[3,4]]"), Ok(()));
+    assert_eq!(check(options, "[[1,2,3]]"), Err(Error::ExceededArrayLengthLimit));
+    assert_eq!(check(ParserOptions::new().array_length_limit(0), "[]"), Ok(()));
+    assert_eq!(check(ParserOptions::new().array_length_limit(0), "[1]"), Err(Error::ExceededArrayLengthLimit));
+}
+
+#[test]
+fn object_length_limit() {
+    let options = ParserOptions::new().object_length_limit(2);
+
+    assert_eq!(check(options, r#"{"a":1,"b":{"c":3,"d":4}}"#), Ok(()));
+    assert_eq!(check(options, r#"{"a":1,"b":2,"c":3}"#), Err(Error::ExceededObjectLengthLimit));
+    assert_eq!(check(ParserOptions::new().object_length_limit(0), "{}"), Ok(()));
+    assert_eq!(check(ParserOptions::new().object_length_limit(0), r#"{"a":1}"#), Err(Error::ExceededObjectLengthLimit));
+}
+
+#[test]
+fn relaxed_accepts_json5() {
+    let options = ParserOptions::new().relaxed(true);
+    let source = r#"
+        // line comment
+        {
+            unquoted: 'single \' quoted',
+            $dollar_1: "double",
+            'quoted key': /* block ** comment */ 0x1F,
+            numbers: [+1, -.5, 2., 0XfF, -0x10, Infinity, -Infinity, NaN,],
+            nested: { a: [], },
+        } // trailing comment
+    "#;
+
+    assert_eq!(check(options, source), Ok(()));
+
+    let data = options.parse(source).unwrap();
+
+    assert_eq!(data["unquoted"], "single ' quoted");
+    assert_eq!(data["$dollar_1"], "double");
+    assert_eq!(data["quoted key"], 31);
+    assert_eq!(data["numbers"][0], 1);
+    assert_eq!(data["numbers"][1], -0.5);
+    assert_eq!(data["numbers"][2], 2);
+    assert_eq!(data["numbers"][3], 255);
+    assert_eq!(data["numbers"][4], -16);
+    assert_eq!(data["numbers"][5].as_f64(), Some(std::f64::INFINITY));
+    assert_eq!(data["numbers"][6].as_f64(), Some(std::f64::NEG_INFINITY));
+    assert!(data["numbers"][7].as_f64().unwrap().is_nan());
+    assert_eq!(data["numbers"].len(), 8);
+    assert_eq!(data["nested"]["a"].len(), 0);
+}
+
+#[test]
+fn relaxed_hexadecimal_overflow() {
+    let options = ParserOptions::new().relaxed(true);
+    let data = options.parse("0x10000000000000000").unwrap();
+
+    assert_eq!(data.as_f64(), Some(18446744073709551616.0));
+}
+
+#[test]
+fn relaxed_still_rejects() {
+    let options = ParserOptions::new().relaxed(true);
+
+    for source in &["[1,,2]", "[,]", "{,}", "{a:1,,}", "/* open", "[1] /x", "0x", "+-1", ".", "[.e1]", "{1a:1}", "'\n'"] {
+        assert!(check(options, source).is_err(), "{}", source);
+    }
+}
+
+#[test]
+fn strict_rejects_json5() {
+    let options = ParserOptions::new();
+
+    for source in &["// c\n1", "[1,]", "{\"a\":1,}", "'a'", "{a:1}", "0x1", "+1", ".5", "1.", "Infinity", "NaN", "[\"\\'\"]"] {
+        assert!(check(options, source).is_err(), "{}", source);
+        assert!(json::parse(source).is_err(), "{}", source);
+    }
+}
+
+#[test]
+fn lossless_numbers() {
+    let options = ParserOptions::new().lossless_numbers(true);
+    let source = r#"{"id":123456789012345678901234567890,"price":-0.1000000000000000000001,"exp":1E+400,"zero":0}"#;
+
+    assert_eq!(check(options, source), Ok(()));
+
+    let data = options.parse(source).unwrap();
+
+    assert_eq!(data.dump(), source);
+    assert_eq!(data["id"].as_raw_number(), Some("123456789012345678901234567890"));
+    assert!(data["price"].is_number());
+    assert!(data["zero"].is_empty());
+    assert_eq!(data["zero"], 0);
+    assert_eq!(data["price"].as_f64(), Some(-0.1));
+
+    let mut parser = PushParser::with_options(options);
+    parser.feed(&source.as_bytes()[.. 20]).unwrap();
+    parser.feed(&source.as_bytes()[20 ..]).unwrap();
+
+    assert_eq!(parser.finish().unwrap().dump(), source);
+}
+
+#[test]
+fn lossless_numbers_equality() {
+    let options = ParserOptions::new().lossless_numbers(true);
+    let source = "[1.0, 1e23, 0.50, -0]";
+    let raw = options.parse(source).unwrap();
+    let parsed = json::parse(source).unwrap();
+
+    assert_eq!(raw, parsed);
+    assert_eq!(parsed, raw);
+    assert_eq!(raw, array![1, Number::from_parts(true, 1, 23), 0.5, 0]);
+    assert_eq!(raw[2], options.parse("5e-1").unwrap());
+    assert!(raw[0] != JsonValue::from("1.0"));
+
+    let owned = RawNumber::new(String::from("1.0")).unwrap();
+
+    assert_eq!(JsonValue::from(owned), 1);
+    assert!(RawNumber::new("1.").is_none());
+    assert!(RawNumber::new("0x10").is_none());
+    assert!(RawNumber::new("[1]").is_none());
+    assert!(RawNumber::new("1\n").is_none());
+}
+
+#[test]
+fn lossless_numbers_relaxed() {
+    let options = ParserOptions::new().lossless_numbers(true).relaxed(true);
+    let data = options.parse("[0xFF, +1, .5, 5., 1.e2, -Infinity, 10.50]").unwrap();
+
+    assert_eq!(data.dump(), "[255,1,0.5,5,100,-1e32767,10.50]");
+}
+
+#[test]
+fn duplicate_keys_last_wins() {
+    let data = json::parse(r#"{"a":1,"b":2,"a":3}"#).unwrap();
+
+    assert_eq!(data["a"], 3);
+    assert_eq!(data["b"], 2);
+    assert_eq!(data.len(), 2);
+}
+
+#[test]
+fn duplicate_keys_first_wins() {
+    let options = ParserOptions::new().duplicate_keys(DuplicateKeys::FirstWins);
+    let source = r#"{"a":1,"b":{"c":true,"c":false},"a":[2]}"#;
+
+    assert_eq!(check(options, source), Ok(()));
+
+    let data = options.parse(source).unwrap();
+
+    assert_eq!(data["a"], 1);
+    assert_eq!(data["b"]["c"], true);
+
+    let mut parser = PushParser::with_options(options);
+    parser.feed(source.as_bytes()).unwrap();
+
+    assert_eq!(parser.finish().unwrap(), data);
+}
+
+#[test]
+fn duplicate_keys_error() {
+    let options = ParserOptions::new().duplicate_keys(DuplicateKeys::Error);
+    let source = "{\n  \"a\": 1,\n  \"b\": [{\"a\": 2}],\n  \"a\": 3\n}";
+    let error = || Err(Error::DuplicateKey {
+        key: "a".into(),
+        line: 4,
+        column: 3,
+    });
+
+    assert_eq!(options.parse(source), error());
+    assert_eq!(options.parse_bytes(source.as_bytes()), error());
+    assert_eq!(options.parse_reader(source.as_bytes()), error());
+    assert_eq!(options.relaxed(true).parse("{a:1,'a':2}").unwrap_err(), Error::DuplicateKey {
+        key: "a".into(),
+        line: 1,
+        column: 6,
+    });
+}
+
+#[test]
+fn duplicate_keys_collect() {
+    let options = ParserOptions::new().duplicate_keys(DuplicateKeys::Collect);
+    let source = r#"[{"a":[1],"b":2,"a":3,"a":{"c":4,"c":5}},{"a":[6],"a":7}]"#;
+
+    assert_eq!(check(options, source), Ok(()));
+
+    let data = options.parse(source).unwrap();
+
+    assert_eq!(data.dump(), r#"[{"a":[[1],3,{"c":[4,5]}],"b":2},{"a":[[6],7]}]"#);
+
+    let mut parser = PushParser::with_options(options);
+    parser.feed(source.as_bytes()).unwrap();
+
+    assert_eq!(parser.finish().unwrap(), data);
+}
+
+#[test]
+fn lone_surrogates() {
+    let source = r#"["\uD83D", "\uDE00", "a\uD83DA", "😀", "\uDBFF𐏿", "\uD800x"]"#;
+
+    assert!(json::parse(source).is_err());
+    assert_eq!(json::parse(r#""\uDE00""#), Err(Error::FailedUtf8Parsing));
+
+    let options = ParserOptions::new().lone_surrogates(LoneSurrogates::Error);
+
+    assert_eq!(options.parse(source), json::parse(source));
+
+    let options = ParserOptions::new().lone_surrogates(LoneSurrogates::Replace);
+
+    assert_eq!(options.parse(source).unwrap(), array![
+        "\u{FFFD}",
+        "\u{FFFD}",
+        "a\u{FFFD}A",
+        "\u{1F600}",
+        "\u{FFFD}\u{103FF}",
+        "\u{FFFD}x"
+    ]);
+
+    let options = ParserOptions::new().lone_surrogates(LoneSurrogates::Preserve);
+    let data = options.parse(source).unwrap();
+
+    let units: Vec<Vec<u16>> = data.members().map(|value| match *value {
+        JsonValue::Wtf8(ref string) => string.encode_utf16(),
+        _                           => value.as_str().unwrap().encode_utf16().collect(),
+    }).collect();
+
+    assert_eq!(units, vec![
+        vec![0xD83D],
+        vec![0xDE00],
+        vec![0x61, 0xD83D, 0x41],
+        vec![0xD83D, 0xDE00],
+        vec![0xDBFF, 0xD800, 0xDFFF],
+        vec![0xD800, 0x78]
+    ]);
+    assert_eq!(data[0].to_string(), "\u{FFFD}");
+    assert!(data[3].as_str().is_some());
+    assert_eq!(data.dump(), r#"["\ud83d","\ude00","a\ud83dA","😀","\udbff𐏿","\ud800x"]"#);
+    assert_eq!(options.parse(&data.dump()).unwrap(), data);
+    assert_eq!(options.parse_bytes(data.dump().as_bytes()).unwrap(), data);
+    assert_eq!(options.parse_reader(data.dump().as_bytes()).unwrap(), data);
+
+    // Keys can only be valid strings
+    let source = r#"{"\uD83D": "\uD83D", "a\uDC00": 1}"#;
+    let data = options.parse(source).unwrap();
+
+    assert_eq!(data.dump(), "{\"\u{FFFD}\":\"\\ud83d\",\"a\u{FFFD}\":1}");
+    assert_eq!(options.parse_bytes(source.as_bytes()).unwrap(), data);
+    assert_eq!(options.parse_reader(source.as_bytes()).unwrap(), data);
+
+    let mut parser = PushParser::with_options(options);
+
+    for byte in source.as_bytes() {
+        parser.feed(&[*byte]).unwrap();
+    }
+
+    assert_eq!(parser.finish().unwrap(), data);
+
+    // Private use characters are no lone surrogates
+    let data = array!["\u{10F83D}", "\u{10FFFD}"];
+
+    assert_eq!(data.dump(), "[\"\u{10F83D}\",\"\u{10FFFD}\"]");
+    assert_eq!(options.parse(&data.dump()).unwrap(), data);
+    assert_eq!(json::parse(&data.dump()).unwrap(), data);
+}
+
+fn encode(source: &str, encoding: json::Encoding, bom: bool) -> Vec<u8> {
+    use json::Encoding::*;
+
+    let text = if bom { format!("\u{FEFF}{}", source) } else { source.into() };
+
+    match encoding {
+        Utf8    => text.into_bytes(),
+        Utf16Le => text.encode_utf16().flat_map(|unit| vec![unit as u8, (unit >> 8) as u8]).collect(),
+        Utf16Be => text.encode_utf16().flat_map(|unit| vec![(unit >> 8) as u8, unit as u8]).collect(),
+        Utf32Le => text.chars().flat_map(|ch| (ch as u32).to_le_bytes().to_vec()).collect(),
+        Utf32Be => text.chars().flat_map(|ch| (ch as u32).to_be_bytes().to_vec()).collect(),
+    }
+}
+
+#[test]
+fn detect_encoding() {
+    use json::Encoding::*;
+
+    let options = ParserOptions::new().detect_encoding(true);
+
+    for source in ["{\"a\":[1,\"é😀\"]}", "1", "[]", " \"x\" "].iter() {
+        let expected = json::parse(source).unwrap();
+
+        for &encoding in [Utf8, Utf16Le, Utf16Be, Utf32Le, Utf32Be].iter() {
+            for &bom in [false, true].iter() {
+                let bytes = encode(source, encoding, bom);
+
+                assert_eq!(options.parse_bytes(&bytes).unwrap(), expected, "{} {:?}", encoding, bytes);
+                assert_eq!(options.parse_reader(&bytes[..]).unwrap(), expected, "{} {:?}", encoding, bytes);
+
+                let mut parser = PushParser::with_options(options);
+
+                for byte in bytes.iter() {
+                    parser.feed(&[*byte]).unwrap();
+                }
+
+                assert_eq!(parser.finish().unwrap(), expected, "{} {:?}", encoding, bytes);
+            }
+        }
+    }
+
+    assert_eq!(options.parse("\u{FEFF}[1]").unwrap(), array![1]);
+    assert!(json::parse("\u{FEFF}[1]").is_err());
+    assert!(json::parse_bytes(&encode("[1]", Utf16Le, false)).is_err());
+}
+
+#[test]
+fn detect_encoding_errors() {
+    let options = ParserOptions::new().detect_encoding(true);
+
+    assert_eq!(options.parse_bytes(b"\xFF\xFE[\x00\x00\xD8]\x00"), Err(Error::FailedDecoding(json::Encoding::Utf16Le)));
+    assert_eq!(options.parse_bytes(b"\x00[\x001\x00"), Err(Error::FailedDecoding(json::Encoding::Utf16Be)));
+    assert_eq!(options.parse_bytes(b"[\x00\x00\x00\x00\x00\x11\x00"), Err(Error::FailedDecoding(json::Encoding::Utf32Le)));
+    assert_eq!(Error::FailedDecoding(json::Encoding::Utf16Be).to_string(), "Failed to decode the source as UTF-16BE");
+}
@@ -0,0 +1,75 @@
+#[macro_use]
+extern crate json;
+
+use json::{ parse, parse_parallel, parse_ndjson_parallel, ParserOptions, Error };
+
+fn records(count: usize) -> Vec<String> {
+    (0 .. count).map(|id| {
+        format!(r#"{{"id":{},"name":"item, \"{}\" ]","tags":["a","b"],"nested":{{"ok":true,"ratio":{}.5}}}}"#, id, id, id)
+    }).collect()
+}
+
+#[test]
+fn parallel_matches_parse() {
+    let source = format!("[\n  {}\n]\n", records(3000).join(" ,\n  "));
+    let options = ParserOptions::new().threads(4);
+
+    let data = options.parse_parallel(&source).unwrap();
+
+    assert_eq!(data.len(), 3000);
+    assert_eq!(data[2345]["name"], "item, \"2345\" ]");
+    assert_eq!(data, parse(&source).unwrap());
+    assert_eq!(parse_parallel(&source).unwrap(), data);
+}
+
+#[test]
+fn parallel_small_and_other_values() {
+    let sources = ["[]", "[1]", "{\"a\":[1,2]}", "\"string\"", "  42  "];
+
+    for source in sources.iter() {
+        assert_eq!(ParserOptions::new().threads(4).parse_parallel(source), parse(source));
+    }
+}
+
+#[test]
+fn parallel_errors() {
+    let valid = format!("[{}]", records(3000).join(","));
+    let options = ParserOptions::new().threads(4);
+
+    let invalid = [
+        valid.replace("\"id\":2900,", "\"id\":2900"),
+        valid.replace("true,\"ratio\":1500", "tru,\"ratio\":1500"),
+        valid.replace("\"id\":1000", "\"id\":1e"),
+        format!("{} x", valid),
+        format!("[{}", valid),
+        valid[.. valid.len() - 1].into(),
+    ];
+
+    for source in invalid.iter() {
+        assert_eq!(options.parse_parallel(source).unwrap_err(), parse(source).unwrap_err());
+    }
+
+    assert_eq!(options.depth_limit(2).parse_parallel(&valid).unwrap_err(), Error::ExceededDepthLimit);
+    assert_eq!(options.array_length_limit(10).parse_parallel(&valid).unwrap_err(), Error::ExceededArrayLengthLimit);
+    assert!(options.depth_limit(3).parse_parallel(&valid).is_ok());
+}
+
+#[test]
+fn parallel_ndjson() {
+    let lines = records(3000);
+    let source = format!("{}\r\n\n", lines.join("\n"));
+    let data = ParserOptions::new().threads(4).parse_ndjson_parallel(&source).unwrap();
+
+    assert_eq!(data.len(), 3000);
+    assert_eq!(data[2999]["id"], 2999);
+    assert_eq!(data[7], parse(&lines[7]).unwrap());
+
+    assert_eq!(parse_ndjson_parallel("").unwrap(), array![]);
+    assert_eq!(parse_ndjson_parallel("\n 1 \n\n[2]").unwrap(), array![1, array![2]]);
+    assert_eq!(parse_ndjson_parallel("1\n2 3\n").unwrap_err(), Error::UnexpectedCharacter {
+        ch: '3',
+        line: 2,
+        column: 3,
+    });
+    assert_eq!(parse_ndjson_parallel("1\n[2,\n3]\n").unwrap_err(), Error::UnexpectedEndOfJson);
+}
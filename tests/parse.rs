@@ -337,3 +337,151 @@ fn does_not_panic_on_single_zero() {
 
     parse(source).unwrap();
 }
+
+#[test]
+fn parse_bytes_matches_parse() {
+    let source = r#"{"foo":["bar", "ąę\u0041", 10.5, null]}"#;
+
+    assert_eq!(json::parse_bytes(source.as_bytes()).unwrap(), parse(source).unwrap());
+}
+
+#[test]
+fn parse_bytes_invalid_utf8_in_string() {
+    assert_eq!(json::parse_bytes(b"[\"foo\xc3\"]"), Err(json::Error::FailedUtf8Parsing));
+    assert_eq!(json::parse_bytes(b"[\"\\n\xff\"]"), Err(json::Error::FailedUtf8Parsing));
+}
+
+#[test]
+fn parse_bytes_invalid_utf8_outside_string() {
+    assert_eq!(json::parse_bytes(b"[1, \xff]"), Err(json::Error::UnexpectedCharacter {
+        ch: '\u{FFFD}',
+        line: 1,
+        column: 5,
+    }));
+}
+
+struct SlowReader<'a> {
+    source: &'a [u8],
+    interrupted: bool,
+}
+
+impl<'a> std::io::Read for SlowReader<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        // Interrupt every other read, and only ever read 3 bytes at a time
+        self.interrupted = !self.interrupted;
+
+        if self.interrupted {
+            return Err(std::io::ErrorKind::Interrupted.into());
+        }
+
+        let len = std::cmp::min(3, std::cmp::min(buf.len(), self.source.len()));
+
+        buf[.. len].copy_from_slice(&self.source[.. len]);
+        self.source = &self.source[len ..];
+
+        Ok(len)
+    }
+}
+
+#[test]
+fn parse_reader_in_chunks() {
+    let source = r#"{"foo": [1, 2, 3], "bar": "żółć"}"#;
+    let reader = SlowReader { source: source.as_bytes(), interrupted: false };
+
+    assert_eq!(json::parse_reader(reader).unwrap(), parse(source).unwrap());
+}
+
+#[test]
+fn parse_reader_io_error() {
+    struct Broken;
+
+    impl std::io::Read for Broken {
+        fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::new(std::io::ErrorKind::ConnectionReset, "reset"))
+        }
+    }
+
+    assert_eq!(json::parse_reader(Broken), Err(json::Error::FailedReading {
+        kind: std::io::ErrorKind::ConnectionReset,
+        message: "reset".into(),
+    }));
+}
+
+#[test]
+fn parse_many_concatenated() {
+    let source = r#"{"a":1}{"b":2}[3]"four"true null"#;
+    let values: Vec<_> = json::parse_many(source).map(|result| result.unwrap()).collect();
+
+    assert_eq!(values, vec![
+        (object!{ "a" => 1 }, 7),
+        (object!{ "b" => 2 }, 14),
+        (array![3], 17),
+        ("four".into(), 23),
+        (true.into(), 27),
+        (Null, 32),
+    ]);
+}
+
+#[test]
+fn parse_many_whitespace() {
+    let values: Vec<_> = json::parse_many(" 1 2.5\n-3 \n").map(|result| result.unwrap().0).collect();
+
+    assert_eq!(values, vec![JsonValue::from(1), 2.5.into(), (-3).into()]);
+    assert_eq!(json::parse_many("  \n ").count(), 0);
+}
+
+#[test]
+fn parse_many_stops_on_error() {
+    let mut values = json::parse_many("[1] [2 {}");
+
+    assert_eq!(values.next(), Some(Ok((array![1], 3))));
+    assert!(values.next().unwrap().is_err());
+    assert_eq!(values.next(), None);
+}
+
+#[test]
+fn parse_many_with_options() {
+    let options = json::ParserOptions::new().relaxed(true);
+    let values: Vec<_> = options.parse_many("{a:1} // one\n/* two */ {b:2,}").map(|result| result.unwrap().0).collect();
+
+    assert_eq!(values, vec![object!{ "a" => 1 }, object!{ "b" => 2 }]);
+}
+
+#[test]
+fn parse_strings_of_every_length() {
+    // Long enough to go through every way strings are scanned, in bulk
+    // and byte by byte, with the escape at every possible position.
+    for len in 0 .. 80 {
+        let plain = "é".repeat(len / 2) + &"a".repeat(len % 2);
+
+        assert_eq!(parse(&format!("\"{}\"", plain)).unwrap(), plain.as_str());
+
+        for at in 0 .. plain.len() + 1 {
+            if !plain.is_char_boundary(at) {
+                continue;
+            }
+
+            let source = format!("\"{}\\n{}\"", &plain[.. at], &plain[at ..]);
+            let expected = format!("{}\n{}", &plain[.. at], &plain[at ..]);
+
+            assert_eq!(parse(&source).unwrap(), expected.as_str());
+
+            let source = format!("\"{}\n{}\"", &plain[.. at], &plain[at ..]);
+
+            assert!(parse(&source).is_err());
+        }
+    }
+}
+
+#[test]
+fn parse_whitespace_of_every_length() {
+    let whitespace = " \t\n\r\x0B\x0C";
+
+    for len in 0 .. 80 {
+        let run: String = whitespace.chars().cycle().take(len).collect();
+        let source = format!("{0}[{0}1{0},{0}\"a\"{0}]{0}", run);
+
+        assert_eq!(parse(&source).unwrap(), array![1, "a"]);
+        assert!(parse(&format!("[{}x]", run)).is_err());
+    }
+}
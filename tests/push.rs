@@ -0,0 +1,121 @@
+#[macro_use]
+extern crate json;
+
+use json::{ parse, PushParser, Error, Null };
+
+fn feed_in_chunks(source: &[u8], size: usize) -> json::Result<json::JsonValue> {
+    let mut parser = PushParser::new();
+
+    for chunk in source.chunks(size) {
+        try!(parser.feed(chunk));
+    }
+
+    parser.finish()
+}
+
+#[test]
+fn push_parse_any_chunk_size() {
+    let source = r#"
+        {
+            "foo": [1, -2.5, 3e2, true, false, null],
+            "bar": { "baz": "Hello \"world\"!\n", "emoji": "\ud83d\ude00 😀" },
+            "empty": [{}, []]
+        }
+    "#;
+
+    let expected = parse(source).unwrap();
+
+    for size in 1..source.len() + 1 {
+        assert_eq!(feed_in_chunks(source.as_bytes(), size).unwrap(), expected);
+    }
+}
+
+#[test]
+fn push_parse_top_level_number() {
+    let mut parser = PushParser::new();
+
+    parser.feed(b"12").unwrap();
+    parser.feed(b"34").unwrap();
+
+    assert_eq!(parser.finish().unwrap(), 1234);
+}
+
+#[test]
+fn push_parse_top_level_literals() {
+    assert_eq!(feed_in_chunks(b"true", 1).unwrap(), true);
+    assert_eq!(feed_in_chunks(b"false", 2).unwrap(), false);
+    assert_eq!(feed_in_chunks(b" null ", 3).unwrap(), Null);
+}
+
+#[test]
+fn push_parse_split_utf8() {
+    let source = "[\"żółć\"]".as_bytes();
+
+    assert_eq!(feed_in_chunks(source, 1).unwrap(), array!["żółć"]);
+}
+
+#[test]
+fn push_parse_incomplete() {
+    assert_eq!(feed_in_chunks(b"[1, 2", 2), Err(Error::UnexpectedEndOfJson));
+    assert_eq!(feed_in_chunks(b"{\"foo\"", 2), Err(Error::UnexpectedEndOfJson));
+    assert_eq!(feed_in_chunks(b"", 1), Err(Error::UnexpectedEndOfJson));
+    assert_eq!(feed_in_chunks(b"-", 1), Err(Error::UnexpectedEndOfJson));
+}
+
+#[test]
+fn push_parse_trailing_characters() {
+    let mut parser = PushParser::new();
+
+    parser.feed(b"[1]  \n").unwrap();
+
+    assert_eq!(parser.feed(b" x"), Err(Error::UnexpectedCharacter {
+        ch: 'x',
+        line: 2,
+        column: 2,
+    }));
+}
+
+#[test]
+fn push_parse_error_positions() {
+    assert_eq!(feed_in_chunks(b"[1,\n  2,\n  trux]", 1), Err(Error::UnexpectedCharacter {
+        ch: 'x',
+        line: 3,
+        column: 6,
+    }));
+
+    assert_eq!(feed_in_chunks(b"[1, -}", 2), Err(Error::UnexpectedCharacter {
+        ch: '}',
+        line: 1,
+        column: 6,
+    }));
+
+    assert_eq!(feed_in_chunks(b"{\"a\": \"\\x\"}", 3), Err(Error::UnexpectedCharacter {
+        ch: 'x',
+        line: 1,
+        column: 9,
+    }));
+}
+
+#[test]
+fn push_parse_mismatched_brackets() {
+    assert!(feed_in_chunks(b"[1}", 1).is_err());
+    assert!(feed_in_chunks(b"{\"a\":1]", 1).is_err());
+    assert!(feed_in_chunks(b"[1,]", 1).is_err());
+    assert!(feed_in_chunks(b"{\"a\":1,}", 1).is_err());
+}
+
+#[test]
+fn push_parse_invalid_utf8() {
+    assert_eq!(feed_in_chunks(b"[\"\xff\"]", 1), Err(Error::FailedUtf8Parsing));
+}
+
+#[test]
+fn push_parse_depth_limit() {
+    let mut source = String::new();
+
+    for _ in 0..600 {
+        source.push('[');
+    }
+
+    assert_eq!(feed_in_chunks(source.as_bytes(), 64), Err(Error::ExceededDepthLimit));
+}
@@ -0,0 +1,128 @@
+extern crate json;
+
+use json::{ parse, EventReader, Event, JsonValue, Error };
+
+// Build a `JsonValue` out of events, to compare with `json::parse`
+fn build(source: &str) -> json::Result<JsonValue> {
+    let mut reader = EventReader::new(source);
+    let mut stack: Vec<(JsonValue, Option<String>)> = Vec::new();
+    let mut root = None;
+
+    while let Some(event) = try!(reader.next_event()) {
+        let value = match event {
+            Event::StartObject => {
+                stack.push((JsonValue::new_object(), None));
+                continue;
+            },
+            Event::StartArray  => {
+                stack.push((JsonValue::new_array(), None));
+                continue;
+            },
+            Event::Key(key)    => {
+                stack.last_mut().unwrap().1 = Some(key.to_string());
+                continue;
+            },
+            Event::EndObject | Event::EndArray => stack.pop().unwrap().0,
+            Event::String(s)   => s.into(),
+            Event::Number(n)   => n.into(),
+            Event::Boolean(b)  => b.into(),
+            Event::Null        => JsonValue::Null,
+        };
+
+        match stack.last_mut() {
+            Some(&mut (ref mut container, ref mut key)) => match key.take() {
+                Some(key) => container[key] = value,
+                None      => container.push(value).unwrap(),
+            },
+            None => root = Some(value),
+        }
+    }
+
+    Ok(root.unwrap())
+}
+
+#[test]
+fn reader_events() {
+    let mut reader = EventReader::new(r#"[{"a":null},[],-1.5,true,"x\ty"]"#);
+    let mut events = Vec::new();
+
+    while let Some(event) = reader.next_event().unwrap() {
+        events.push(format!("{:?}", event));
+    }
+
+    assert_eq!(events, vec![
+        "StartArray",
+        "StartObject",
+        "Key(\"a\")",
+        "Null",
+        "EndObject",
+        "StartArray",
+        "EndArray",
+        format!("Number({:?})", json::number::Number::from(-1.5)).as_str(),
+        "Boolean(true)",
+        "String(\"x\\ty\")",
+        "EndArray",
+    ]);
+}
+
+#[test]
+fn reader_matches_parse() {
+    let source = r#"
+        {
+            "foo": [1, 2e10, -0.5, {"nested": [[], {}]}],
+            "bar": "escaped \"quotes\" and é",
+            "baz": false,
+            "qux": null
+        }
+    "#;
+
+    assert_eq!(build(source).unwrap(), parse(source).unwrap());
+}
+
+#[test]
+fn reader_top_level_scalar() {
+    let mut reader = EventReader::new(" 42 ");
+
+    assert_eq!(reader.next_event().unwrap(), Some(Event::Number(42.into())));
+    assert_eq!(reader.next_event().unwrap(), None);
+    assert_eq!(reader.next_event().unwrap(), None);
+}
+
+#[test]
+fn reader_errors() {
+    assert_eq!(build("[1,2"), Err(Error::UnexpectedEndOfJson));
+    assert_eq!(build("[1 2]"), parse("[1 2]"));
+    assert_eq!(build("{\"a\":1]"), parse("{\"a\":1]"));
+    assert_eq!(build("[1] x"), parse("[1] x"));
+    assert_eq!(build("{\"a\" 1}"), parse("{\"a\" 1}"));
+}
+
+#[test]
+fn reader_depth_limit() {
+    let source: String = (0..600).map(|_| '[').collect();
+
+    assert_eq!(build(&source), Err(Error::ExceededDepthLimit));
+}
+
+#[test]
+fn reader_skips_without_tree() {
+    let source = r#"{"skip":[1,2,3,{"deep":true}],"id":"wanted"}"#;
+    let mut reader = EventReader::new(source);
+    let mut depth = 0;
+    let mut found = None;
+
+    while let Some(event) = reader.next_event().unwrap() {
+        match event {
+            Event::StartObject | Event::StartArray => depth += 1,
+            Event::EndObject | Event::EndArray     => depth -= 1,
+            Event::Key("id") if depth == 1         => {
+                if let Some(Event::String(id)) = reader.next_event().unwrap() {
+                    found = Some(id.to_string());
+                }
+            },
+            _ => {}
+        }
+    }
+
+    assert_eq!(found, Some("wanted".to_string()));
+}
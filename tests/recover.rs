@@ -0,0 +1,171 @@
+#[macro_use]
+extern crate json;
+
+use json::{ parse, parse_recovering, ParserOptions, DuplicateKeys, DiagnosticKind };
+use json::DiagnosticKind::*;
+
+// Kinds of diagnostics, along with the source text each one points at
+fn problems<'a>(source: &'a str) -> (json::JsonValue, Vec<(DiagnosticKind, &'a str)>) {
+    let (data, diagnostics) = parse_recovering(source);
+
+    let found = diagnostics.into_iter().map(|diagnostic| {
+        let span = diagnostic.span;
+
+        (diagnostic.kind, &source[span.start.offset .. span.end.offset])
+    }).collect();
+
+    (data, found)
+}
+
+#[test]
+fn recover_valid_json() {
+    let sources = [
+        r#"{"a":[1,2.5,-3e2,{"b":null}],"c":"é\n","d":{},"e":[],"f":true}"#,
+        "  [ \"x\" , false ]  ",
+        "\"just a string\"",
+        "12345678901234567890",
+        "[1e+5, 2E-3, -4.5e+10, 6e7]",
+        r#"{"a":1.5e-3,"b":[0E+0]}"#,
+    ];
+
+    for source in sources.iter() {
+        let (data, diagnostics) = parse_recovering(source);
+
+        assert_eq!(diagnostics, vec![]);
+        assert_eq!(data, parse(source).unwrap());
+    }
+}
+
+#[test]
+fn recover_missing_punctuation() {
+    let (data, found) = problems(r#"{"a" 1 "b": [1 2,, 3,] "c":}"#);
+
+    assert_eq!(data, object!{
+        "a" => 1,
+        "b" => array![1, 2, 3],
+        "c" => json::Null
+    });
+
+    assert_eq!(found, vec![
+        (MissingColon, ""),
+        (MissingComma, ""),
+        (MissingComma, ""),
+        (MissingValue, ""),
+        (TrailingComma, ","),
+        (MissingComma, ""),
+        (MissingValue, ""),
+    ]);
+}
+
+#[test]
+fn recover_stray_characters() {
+    let (data, found) = problems("@@ [1, ##, 2 :: ] ok");
+
+    assert_eq!(data, array![1, 2]);
+    assert_eq!(found, vec![
+        (UnexpectedCharacter('@'), "@@"),
+        (UnexpectedCharacter('#'), "##"),
+        (MissingValue, ""),
+        (UnexpectedCharacter(':'), "::"),
+        (UnexpectedCharacter('o'), "ok"),
+    ]);
+}
+
+#[test]
+fn recover_unclosed_strings() {
+    let source = "[\"one\n, \"two\", \"th\\tree\",\n \"bad\\q\"]";
+    let (data, found) = problems(source);
+
+    assert_eq!(data, array!["one", "two", "th\tree", "bad\\q"]);
+    assert_eq!(found, vec![
+        (UnclosedString, "\"one"),
+        (InvalidString, "\"bad\\q\""),
+    ]);
+}
+
+#[test]
+fn recover_unclosed_containers() {
+    let (data, found) = problems(r#"{"list": [1, {"a": 2], "next": [3"#);
+
+    assert_eq!(data, object!{
+        "list" => array![1, object!{ "a" => 2 }],
+        "next" => array![3]
+    });
+
+    assert_eq!(found, vec![
+        (UnclosedObject, "{"),
+        (UnclosedArray, "["),
+        (UnclosedObject, "{"),
+    ]);
+}
+
+#[test]
+fn recover_bad_scalars() {
+    let (data, found) = problems("[12px, -, 1.5.5, nul, True, 007]");
+
+    assert_eq!(data, array![12, json::Null, 1.5, json::Null, json::Null, 0]);
+    assert_eq!(found, vec![
+        (InvalidNumber, "12px"),
+        (InvalidNumber, "-"),
+        (InvalidNumber, "1.5.5"),
+        (InvalidLiteral, "nul"),
+        (InvalidLiteral, "True"),
+        (InvalidNumber, "007"),
+    ]);
+}
+
+#[test]
+fn recover_keys() {
+    let (data, found) = problems(r#"{name: "x", "name": "y", $ref: 1, "z"}"#);
+
+    assert_eq!(data, object!{ "name" => "y", "$ref" => 1, "z" => json::Null });
+    assert_eq!(found, vec![
+        (UnquotedKey, "name"),
+        (UnquotedKey, "$ref"),
+        (MissingColon, ""),
+        (MissingValue, ""),
+    ]);
+}
+
+#[test]
+fn recover_empty_and_trailing() {
+    let (data, found) = problems("  ");
+
+    assert!(data.is_null());
+    assert_eq!(found, vec![(UnexpectedEndOfJson, "")]);
+
+    let (data, found) = problems("[1] [2] \n");
+
+    assert_eq!(data, array![1]);
+    assert_eq!(found, vec![(UnexpectedCharacter('['), "[2]")]);
+}
+
+#[test]
+fn recover_positions() {
+    let (_, diagnostics) = parse_recovering("{\n  \"é\": 1\n  \"b\": 2\n");
+
+    assert_eq!(diagnostics.len(), 2);
+
+    let missing = diagnostics[0].span;
+
+    assert_eq!(diagnostics[0].kind, MissingComma);
+    assert_eq!((missing.start.line, missing.start.column), (2, 9));
+    assert_eq!(diagnostics[1].to_string(), "Unclosed object at (1:1)");
+}
+
+#[test]
+fn recover_with_options() {
+    let source = r#"{"a": 1, "a": 2, "deep": [[[[1]]], []], "n": 1.10}"#;
+    let options = ParserOptions::new()
+        .depth_limit(3)
+        .duplicate_keys(DuplicateKeys::Error)
+        .lossless_numbers(true);
+
+    let (data, diagnostics) = options.parse_recovering(source);
+    let kinds: Vec<_> = diagnostics.into_iter().map(|diagnostic| diagnostic.kind).collect();
+
+    assert_eq!(data["a"], 1);
+    assert_eq!(data["deep"], array![array![json::Null], json::JsonValue::new_array()]);
+    assert_eq!(data["n"].as_raw_number(), Some("1.10"));
+    assert_eq!(kinds, vec![DuplicateKey("a".into()), ExceededDepthLimit]);
+}
@@ -0,0 +1,118 @@
+#[macro_use]
+extern crate json;
+
+use json::{ parse, parse_into, Recycler, JsonValue, ParserOptions, Error };
+
+fn array_ptr(value: &JsonValue) -> *const JsonValue {
+    match *value {
+        JsonValue::Array(ref array) => array.as_ptr(),
+        _                           => panic!("not an array"),
+    }
+}
+
+fn string_ptrs(value: &JsonValue) -> Vec<*const u8> {
+    value.members().filter_map(|member| match *member {
+        JsonValue::String(ref string) => Some(string.as_ptr()),
+        _                             => None,
+    }).collect()
+}
+
+#[test]
+fn recycler_parses_like_parse() {
+    let sources = [
+        r#"{"a":[1,2.5,-3e2,{"b":null}],"c":"a string long enough to be allocated","d":{},"e":[]}"#,
+        r#"[true,false,null,"short",[[[]]]]"#,
+        "12345678901234567890",
+    ];
+
+    let mut recycler = Recycler::new();
+
+    for _ in 0 .. 3 {
+        for source in sources.iter() {
+            let data = recycler.parse(source).unwrap();
+
+            assert_eq!(data, parse(source).unwrap());
+
+            recycler.recycle(data);
+        }
+    }
+}
+
+#[test]
+fn recycler_reuses_allocations() {
+    let source = r#"["first string that does not fit inline","second string that does not fit inline"]"#;
+
+    let mut recycler = Recycler::new();
+    let data = recycler.parse(source).unwrap();
+    let array = array_ptr(&data);
+    let mut strings = string_ptrs(&data);
+
+    recycler.recycle(data);
+
+    let data = recycler.parse(source).unwrap();
+    let mut reused = string_ptrs(&data);
+
+    strings.sort();
+    reused.sort();
+
+    assert_eq!(array_ptr(&data), array);
+    assert_eq!(reused, strings);
+    assert_eq!(data, parse(source).unwrap());
+}
+
+#[test]
+fn recycler_recycles_deep_values() {
+    let mut source = String::new();
+
+    for _ in 0 .. 100000 {
+        source.push('[');
+    }
+
+    for _ in 0 .. 100000 {
+        source.push(']');
+    }
+
+    let mut recycler = Recycler::with_options(ParserOptions::new().depth_limit(usize::max_value()));
+
+    // Dropping a value this deep would overflow the stack, taking it apart
+    // in the recycler doesn't
+    for _ in 0 .. 2 {
+        let data = recycler.parse(&source).unwrap();
+
+        recycler.recycle(data);
+    }
+
+    recycler.clear();
+}
+
+#[test]
+fn parse_into_replaces_target() {
+    let mut data = object!{ "old" => "value from before" };
+
+    parse_into(r#"{"items":[1,"two",{"three":3}]}"#, &mut data).unwrap();
+
+    assert_eq!(data, object!{ "items" => array![1, "two", object!{ "three" => 3 }] });
+
+    let list = data["items"].take();
+    let ptr = array_ptr(&list);
+
+    data = list;
+    parse_into("[4,5,6]", &mut data).unwrap();
+
+    assert_eq!(data, array![4, 5, 6]);
+    assert_eq!(array_ptr(&data), ptr);
+}
+
+#[test]
+fn parse_into_errors() {
+    let mut data = array![1, 2, 3];
+
+    assert_eq!(parse_into("[1,", &mut data), Err(Error::UnexpectedEndOfJson));
+    assert!(data.is_null());
+
+    let options = ParserOptions::new().lossless_numbers(true);
+
+    options.parse_into("[1.000000000000000000001]", &mut data).unwrap();
+
+    assert_eq!(data[0].as_raw_number(), Some("1.000000000000000000001"));
+}
@@ -0,0 +1,170 @@
+extern crate json;
+
+use json::{ parse, parse_with_spans, ParserOptions, DuplicateKeys, LoneSurrogates, EventReader, Event, Error };
+
+// Pull the source text out of the span at the pointer
+fn text<'a>(source: &'a str, spans: &json::Spans, pointer: &str) -> &'a str {
+    let span = spans.get(pointer).expect(pointer);
+
+    &source[span.start.offset .. span.end.offset]
+}
+
+#[test]
+fn spans_match_parse() {
+    let source = r#"{"a":[1,{"b":null}],"c":"d","e":{}}"#;
+    let (data, spans) = parse_with_spans(source).unwrap();
+
+    assert_eq!(data, parse(source).unwrap());
+    assert_eq!(spans.len(), 7);
+}
+
+#[test]
+fn spans_of_values() {
+    let source = r#"{ "list": [1, -2.5e3, "x\"y"], "nested": { "ok": true }, "none": null }"#;
+    let (_, spans) = parse_with_spans(source).unwrap();
+
+    assert_eq!(text(source, &spans, ""), source);
+    assert_eq!(text(source, &spans, "/list"), r#"[1, -2.5e3, "x\"y"]"#);
+    assert_eq!(text(source, &spans, "/list/0"), "1");
+    assert_eq!(text(source, &spans, "/list/1"), "-2.5e3");
+    assert_eq!(text(source, &spans, "/list/2"), r#""x\"y""#);
+    assert_eq!(text(source, &spans, "/nested"), r#"{ "ok": true }"#);
+    assert_eq!(text(source, &spans, "/nested/ok"), "true");
+    assert_eq!(text(source, &spans, "/none"), "null");
+    assert_eq!(spans.get("/missing"), None);
+}
+
+#[test]
+fn spans_of_keys() {
+    let source = r#"{"a/b":{"c~d":1}}"#;
+    let (_, spans) = parse_with_spans(source).unwrap();
+    let key = spans.key("/a~1b/c~0d").unwrap();
+
+    assert_eq!(&source[key.start.offset .. key.end.offset], r#""c~d""#);
+    assert_eq!(text(source, &spans, "/a~1b/c~0d"), "1");
+    assert_eq!(spans.key(""), None);
+}
+
+#[test]
+fn spans_lines_and_columns() {
+    let source = "{\n  \"name\": \"é\",\n  \"list\": [\n    true\n  ]\n}\n";
+    let (_, spans) = parse_with_spans(source).unwrap();
+
+    let name = spans.get("/name").unwrap();
+
+    assert_eq!((name.start.line, name.start.column), (2, 11));
+    assert_eq!((name.end.line, name.end.column), (2, 14));
+
+    let list = spans.get("/list").unwrap();
+
+    assert_eq!((list.start.line, list.start.column), (3, 11));
+    assert_eq!((list.end.line, list.end.column), (5, 4));
+
+    let member = spans.get("/list/0").unwrap();
+
+    assert_eq!((member.start.line, member.start.column), (4, 5));
+}
+
+#[test]
+fn spans_with_options() {
+    let source = r#"{"a":{"x":1},"a":[2]}"#;
+    let options = ParserOptions::new().duplicate_keys(DuplicateKeys::FirstWins);
+    let (data, spans) = options.parse_with_spans(source).unwrap();
+
+    assert_eq!(data, options.parse(source).unwrap());
+    assert_eq!(text(source, &spans, "/a"), r#"{"x":1}"#);
+
+    let options = ParserOptions::new().duplicate_keys(DuplicateKeys::Error);
+
+    assert_eq!(options.parse_with_spans(source).unwrap_err(), Error::DuplicateKey {
+        key: "a".into(),
+        line: 1,
+        column: 14,
+    });
+
+    let options = ParserOptions::new().lossless_numbers(true);
+    let (data, _) = options.parse_with_spans("[1.000000000000000000001]").unwrap();
+
+    assert_eq!(data[0].as_raw_number(), Some("1.000000000000000000001"));
+}
+
+#[test]
+fn spans_of_repeated_keys() {
+    let source = r#"{"a":{"b":{"c":1},"d":2},"ab":3,"a":[4],"e":1,"e":{"f":[5]}}"#;
+
+    let (data, spans) = parse_with_spans(source).unwrap();
+
+    assert_eq!(data, parse(source).unwrap());
+    assert_eq!(text(source, &spans, "/a"), "[4]");
+    assert_eq!(text(source, &spans, "/a/0"), "4");
+    assert_eq!(text(source, &spans, "/ab"), "3");
+    assert_eq!(text(source, &spans, "/e/f/0"), "5");
+    assert_eq!(spans.get("/a/b"), None);
+    assert_eq!(spans.key("/a/b/c"), None);
+    assert_eq!(spans.len(), 7);
+
+    let options = ParserOptions::new().duplicate_keys(DuplicateKeys::Collect);
+    let (data, spans) = options.parse_with_spans(source).unwrap();
+
+    assert_eq!(data, options.parse(source).unwrap());
+    assert_eq!(text(source, &spans, "/a"), "[4]");
+    assert_eq!(spans.get("/a/b"), None);
+    assert_eq!(spans.len(), 7);
+
+    let options = ParserOptions::new().duplicate_keys(DuplicateKeys::FirstWins);
+    let (data, spans) = options.parse_with_spans(source).unwrap();
+
+    assert_eq!(data, options.parse(source).unwrap());
+    assert_eq!(text(source, &spans, "/a"), r#"{"b":{"c":1},"d":2}"#);
+    assert_eq!(text(source, &spans, "/a/b/c"), "1");
+    assert_eq!(text(source, &spans, "/e"), "1");
+    assert_eq!(spans.get("/a/0"), None);
+    assert_eq!(spans.get("/e/f"), None);
+    assert_eq!(spans.key("/e/f"), None);
+    assert_eq!(spans.len(), 7);
+
+    let (key, value) = (spans.key("/e").unwrap(), spans.get("/e").unwrap());
+
+    assert_eq!(&source[key.start.offset .. value.end.offset], r#""e":1"#);
+}
+
+#[test]
+fn spans_preserve_lone_surrogates() {
+    let source = r#"{"a":["\uD800", "b\\u", 'c\uDC00'],"d\uD800":1}"#;
+    let options = ParserOptions::new().relaxed(true).lone_surrogates(LoneSurrogates::Preserve);
+    let (data, spans) = options.parse_with_spans(source).unwrap();
+
+    assert_eq!(data, options.parse(source).unwrap());
+    assert_eq!(data["a"][0].dump(), r#""\ud800""#);
+    assert_eq!(text(source, &spans, "/a/2"), r#"'c\uDC00'"#);
+    assert_eq!(text(source, &spans, "/d\u{FFFD}"), "1");
+}
+
+#[test]
+fn spans_errors() {
+    assert_eq!(parse_with_spans("[1,").unwrap_err(), Error::UnexpectedEndOfJson);
+    assert_eq!(parse_with_spans("[1 2]").unwrap_err(), parse("[1 2]").unwrap_err());
+}
+
+#[test]
+fn reader_offsets() {
+    let source = r#" {"a" : [true]} "#;
+    let mut reader = EventReader::new(source);
+    let mut tokens = Vec::new();
+
+    while let Some(event) = reader.next_event().unwrap() {
+        let is_key = match event { Event::Key(_) => true, _ => false };
+        let (start, end) = reader.offsets();
+
+        tokens.push((is_key, &source[start .. end]));
+    }
+
+    assert_eq!(tokens, vec![
+        (false, "{"),
+        (true, "\"a\""),
+        (false, "["),
+        (false, "true"),
+        (false, "]"),
+        (false, "}"),
+    ]);
+}
@@ -3,7 +3,7 @@ extern crate json;
 
 use std::collections::{ HashMap, BTreeMap };
 use std::f64;
-use json::{ parse, stringify, stringify_pretty, JsonValue, Null };
+use json::{ parse, stringify, stringify_pretty, JsonValue, Null, ParserOptions, PrettyConfig, EscapeConfig, LoneSurrogates };
 
 #[test]
 fn stringify_null() {
@@ -243,3 +243,184 @@ fn stringify_pretty_object() {
     assert_eq!(stringify_pretty(object, 2),
                "{\n  \"name\": \"Urlich\",\n  \"age\": 50,\n  \"parents\": {\n    \"mother\": \"Helga\",\n    \"father\": \"Brutus\"\n  },\n  \"cars\": [\n    \"Golf\",\n    \"Mercedes\",\n    \"Porsche\"\n  ]\n}");
 }
+
+#[test]
+fn stringify_pretty_config() {
+    let data = object!{
+        "name" => "tab\tbed",
+        "empty" => object!{},
+        "point" => array![1.5, -2, 1e30],
+        "meta" => object!{ "tags" => array!["a", "b"], "ok" => true }
+    };
+
+    let config = PrettyConfig::new().tabs().newline("\r\n").space_before_colon(true);
+
+    assert_eq!(data.pretty_with(&config),
+               "{\r\n\t\"name\" : \"tab\\tbed\",\r\n\t\"empty\" : {},\r\n\t\"point\" : [\r\n\t\t1.5,\r\n\t\t-2,\r\n\t\t1e30\r\n\t],\r\n\t\"meta\" : {\r\n\t\t\"tags\" : [\r\n\t\t\t\"a\",\r\n\t\t\t\"b\"\r\n\t\t],\r\n\t\t\"ok\" : true\r\n\t}\r\n}");
+
+    assert_eq!(data.pretty_with(&PrettyConfig::new().spaces(2)), data.pretty(2));
+    assert_eq!(data.pretty_with(&PrettyConfig::new()), data.pretty(4));
+    assert_eq!(array![1].pretty_with(&PrettyConfig::new().trailing_newline(true)), "[\n    1\n]\n");
+}
+
+#[test]
+fn stringify_pretty_max_width() {
+    let data = object!{
+        "name" => "shape",
+        "points" => array![array![0, 0], array![10, 0], array![10, 10]],
+        "meta" => object!{ "closed" => true, "label" => "\"quoted\"" }
+    };
+
+    let expected = [
+        (0, "{\n  \"name\": \"shape\",\n  \"points\": [\n    [\n      0,\n      0\n    ],\n    [\n      10,\n      0\n    ],\n    [\n      10,\n      10\n    ]\n  ],\n  \"meta\": {\n    \"closed\": true,\n    \"label\": \"\\\"quoted\\\"\"\n  }\n}"),
+        (20, "{\n  \"name\": \"shape\",\n  \"points\": [\n    [0, 0],\n    [10, 0],\n    [10, 10]\n  ],\n  \"meta\": {\n    \"closed\": true,\n    \"label\": \"\\\"quoted\\\"\"\n  }\n}"),
+        (40, "{\n  \"name\": \"shape\",\n  \"points\": [[0, 0], [10, 0], [10, 10]],\n  \"meta\": {\n    \"closed\": true,\n    \"label\": \"\\\"quoted\\\"\"\n  }\n}"),
+        (53, "{\n  \"name\": \"shape\",\n  \"points\": [[0, 0], [10, 0], [10, 10]],\n  \"meta\": { \"closed\": true, \"label\": \"\\\"quoted\\\"\" }\n}"),
+        (200, "{ \"name\": \"shape\", \"points\": [[0, 0], [10, 0], [10, 10]], \"meta\": { \"closed\": true, \"label\": \"\\\"quoted\\\"\" } }"),
+    ];
+
+    for &(width, pretty) in expected.iter() {
+        let config = PrettyConfig::new().spaces(2).max_width(width);
+        let mut written = Vec::new();
+
+        data.write_pretty_with(&mut written, &config).unwrap();
+
+        assert_eq!(data.pretty_with(&config), pretty, "{}", width);
+        assert_eq!(String::from_utf8(written).unwrap(), pretty, "{}", width);
+        assert_eq!(parse(pretty).unwrap(), data);
+    }
+}
+
+#[test]
+fn stringify_escape_config() {
+    let data = array!["a/b", "<p>&amp;</p>", "line\u{2028}para\u{2029}", "é€😀", "\"\\\n\u{1}", "plain"];
+
+    assert_eq!(data.dump_escaped(EscapeConfig::new()), data.dump());
+    assert_eq!(data.dump_escaped(EscapeConfig::new().slash(true)),
+               "[\"a\\/b\",\"<p>&amp;<\\/p>\",\"line\u{2028}para\u{2029}\",\"é€😀\",\"\\\"\\\\\\n\\u0001\",\"plain\"]");
+    assert_eq!(data.dump_escaped(EscapeConfig::new().html(true)),
+               r#"["a/b","\u003cp\u003e\u0026amp;\u003c/p\u003e","line\u2028para\u2029","é€😀","\"\\\n\u0001","plain"]"#);
+    assert_eq!(data.dump_escaped(EscapeConfig::new().ascii_only(true)),
+               r#"["a/b","<p>&amp;</p>","line\u2028para\u2029","\u00e9\u20ac\ud83d\ude00","\"\\\n\u0001","plain"]"#);
+
+    let all = EscapeConfig::new().ascii_only(true).html(true).slash(true);
+    let dumped = data.dump_escaped(all);
+
+    assert!(dumped.is_ascii());
+    assert!(!dumped.contains('<') && !dumped.contains('&') && !dumped.replace("\\/", "").contains('/'));
+    assert_eq!(parse(&dumped).unwrap(), data);
+
+    let mut written = Vec::new();
+
+    data.write_escaped(&mut written, all).unwrap();
+
+    assert_eq!(String::from_utf8(written).unwrap(), dumped);
+}
+
+#[test]
+fn stringify_escape_config_lone_surrogates() {
+    let data = ParserOptions::new().lone_surrogates(LoneSurrogates::Preserve).parse(r#"["\ud83d!"]"#).unwrap();
+
+    assert_eq!(data.dump_escaped(EscapeConfig::new().html(true)), r#"["\ud83d!"]"#);
+    assert_eq!(data.dump_escaped(EscapeConfig::new().ascii_only(true)), r#"["\ud83d!"]"#);
+}
+
+#[test]
+fn stringify_pretty_max_width_lone_surrogates() {
+    let data = ParserOptions::new().lone_surrogates(LoneSurrogates::Preserve).parse(r#"["a\udc00"]"#).unwrap();
+
+    // Each lone surrogate is six characters wide once escaped
+    assert_eq!(data.pretty_with(&PrettyConfig::new().max_width(11)), r#"["a\udc00"]"#);
+    assert_eq!(data.pretty_with(&PrettyConfig::new().max_width(10)), "[\n    \"a\\udc00\"\n]");
+}
+
+#[test]
+fn stringify_pretty_escape_config() {
+    let data = object!{ "html" => array!["<b>", "é"] };
+    let config = PrettyConfig::new().spaces(2).escape(EscapeConfig::new().html(true).ascii_only(true));
+
+    assert_eq!(data.pretty_with(&config), "{\n  \"html\": [\n    \"\\u003cb\\u003e\",\n    \"\\u00e9\"\n  ]\n}");
+
+    // Escapes count towards the width
+    assert_eq!(data.pretty_with(&config.clone().max_width(37)), "{\n  \"html\": [\"\\u003cb\\u003e\", \"\\u00e9\"]\n}");
+    assert_eq!(data.pretty_with(&config.max_width(36)), "{\n  \"html\": [\n    \"\\u003cb\\u003e\",\n    \"\\u00e9\"\n  ]\n}");
+}
+
+#[test]
+fn stringify_escapes_of_every_length() {
+    // Escapes at every position of strings long enough to be scanned in
+    // bulk, including control characters that are escaped as `\u00XX`.
+    for len in 0 .. 80 {
+        let plain = "é".repeat(len / 2) + &"a".repeat(len % 2);
+
+        assert_eq!(stringify(plain.as_str()), format!("\"{}\"", plain));
+
+        for at in 0 .. plain.len() + 1 {
+            if !plain.is_char_boundary(at) {
+                continue;
+            }
+
+            for &(ch, escaped) in [('"', "\\\""), ('\\', "\\\\"), ('\t', "\\t"), ('\u{1f}', "\\u001f")].iter() {
+                let string = format!("{}{}{}{}", &plain[.. at], ch, &plain[at ..], ch);
+                let expected = format!("\"{}{}{}{}\"", &plain[.. at], escaped, &plain[at ..], escaped);
+
+                assert_eq!(stringify(string.as_str()), expected);
+                assert_eq!(parse(&expected).unwrap(), string.as_str());
+            }
+        }
+    }
+}
+
+#[test]
+fn canonical_rfc_8785_example() {
+    let data = parse(r#"{
+        "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+        "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+        "literals": [null, true, false]
+    }"#).unwrap();
+
+    assert_eq!(data.canonical().unwrap(), r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#);
+}
+
+#[test]
+fn canonical_key_order() {
+    let data = parse(r#"{"\u20ac":1,"\r":2,"\ufb33":3,"1":4,"\ud83d\ude00":5,"\u0080":6,"\u00f6":7,"nested":{"b":[{"z":0,"y":1}],"a":null}}"#).unwrap();
+
+    assert_eq!(data.canonical().unwrap(), "{\"\\r\":2,\"1\":4,\"nested\":{\"a\":null,\"b\":[{\"y\":1,\"z\":0}]},\"\u{80}\":6,\"ö\":7,\"€\":1,\"😀\":5,\"\u{fb33}\":3}");
+}
+
+#[test]
+fn canonical_numbers() {
+    let cases = [
+        ("0", "0"), ("-0", "0"), ("-0.0", "0"), ("1.0", "1"), ("-1.5", "-1.5"),
+        ("1e20", "100000000000000000000"), ("1e21", "1e+21"), ("123456789e13", "1.23456789e+21"),
+        ("0.000001", "0.000001"), ("0.0000001", "1e-7"), ("123e-20", "1.23e-18"),
+        ("9007199254740993", "9007199254740992"), ("18446744073709551615", "18446744073709552000"),
+        ("5e-324", "5e-324"), ("1.7976931348623157e308", "1.7976931348623157e+308"),
+        ("0.1", "0.1"), ("295147905179352830000", "295147905179352830000"),
+        ("1e23", "1e+23"), ("-3.460447456121841e16", "-34604474561218410"),
+    ];
+
+    for &(source, expected) in cases.iter() {
+        assert_eq!(parse(source).unwrap().canonical().unwrap(), expected, "{}", source);
+        assert_eq!(ParserOptions::new().lossless_numbers(true).parse(source).unwrap().canonical().unwrap(), expected, "{}", source);
+    }
+
+    assert_eq!(JsonValue::from(0.1 + 0.2).canonical().unwrap(), "0.30000000000000004");
+
+    // Numbers that are not finite have no canonical form
+    assert_eq!(parse("[1e400]").unwrap().canonical(), None);
+    assert_eq!(ParserOptions::new().lossless_numbers(true).parse("[1e400]").unwrap().canonical(), None);
+    assert_eq!(JsonValue::from(f64::NAN).canonical(), None);
+    assert_eq!(array![1, -f64::INFINITY].write_canonical(&mut Vec::new()).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn canonical_write() {
+    let data = object!{ "b" => 2, "a" => array![1.5, "x"] };
+    let mut out = Vec::new();
+
+    data.write_canonical(&mut out).unwrap();
+
+    assert_eq!(out, br#"{"a":[1.5,"x"],"b":2}"#);
+}
@@ -0,0 +1,173 @@
+#[macro_use]
+extern crate json;
+
+use std::io::ErrorKind;
+use json::{ JsonWriter, PrettyConfig, JsonValue };
+
+// Write the same document as `expected` piece by piece.
+fn write_document<W: std::io::Write>(writer: &mut JsonWriter<W>) {
+    writer.begin_object().unwrap();
+    writer.key("name").unwrap();
+    writer.string("export \"all\"").unwrap();
+    writer.key("empty").unwrap();
+    writer.begin_array().unwrap();
+    writer.end().unwrap();
+    writer.key("rows").unwrap();
+    writer.begin_array().unwrap();
+
+    for id in 0 .. 3 {
+        writer.value(&object!{ "id" => id, "tags" => array!["a"] }).unwrap();
+    }
+
+    writer.begin_object().unwrap();
+    writer.end().unwrap();
+    writer.number(-1.5).unwrap();
+    writer.value(&JsonValue::Null).unwrap();
+    writer.end().unwrap();
+    writer.end().unwrap();
+}
+
+fn expected() -> JsonValue {
+    object!{
+        "name" => "export \"all\"",
+        "empty" => array![],
+        "rows" => array![
+            object!{ "id" => 0, "tags" => array!["a"] },
+            object!{ "id" => 1, "tags" => array!["a"] },
+            object!{ "id" => 2, "tags" => array!["a"] },
+            object!{},
+            -1.5,
+            JsonValue::Null
+        ]
+    }
+}
+
+#[test]
+fn writer_compact() {
+    let mut writer = JsonWriter::new(Vec::new());
+
+    write_document(&mut writer);
+
+    assert_eq!(String::from_utf8(writer.finish().unwrap()).unwrap(), expected().dump());
+}
+
+#[test]
+fn writer_pretty() {
+    let configs = [
+        PrettyConfig::new(),
+        PrettyConfig::new().tabs().newline("\r\n").space_before_colon(true).trailing_newline(true),
+        PrettyConfig::new().spaces(2).max_width(30),
+    ];
+
+    for config in configs.iter() {
+        let mut writer = JsonWriter::pretty(Vec::new(), config.clone());
+
+        write_document(&mut writer);
+
+        assert_eq!(String::from_utf8(writer.finish().unwrap()).unwrap(), expected().pretty_with(config));
+    }
+}
+
+#[test]
+fn writer_single_values() {
+    let mut writer = JsonWriter::new(Vec::new());
+
+    writer.number(42).unwrap();
+
+    assert_eq!(writer.finish().unwrap(), b"42");
+
+    let mut writer = JsonWriter::pretty(Vec::new(), PrettyConfig::new());
+
+    writer.begin_array().unwrap();
+    writer.end().unwrap();
+
+    assert_eq!(writer.finish().unwrap(), b"[]");
+}
+
+#[test]
+fn writer_misuse() {
+    let mut writer = JsonWriter::new(Vec::new());
+
+    assert_eq!(writer.key("a").unwrap_err().kind(), ErrorKind::InvalidInput);
+    assert_eq!(writer.end().unwrap_err().kind(), ErrorKind::InvalidInput);
+
+    writer.begin_object().unwrap();
+
+    assert_eq!(writer.string("a").unwrap_err().kind(), ErrorKind::InvalidInput);
+    assert_eq!(writer.begin_array().unwrap_err().kind(), ErrorKind::InvalidInput);
+
+    writer.key("a").unwrap();
+
+    assert_eq!(writer.key("b").unwrap_err().kind(), ErrorKind::InvalidInput);
+    assert_eq!(writer.end().unwrap_err().kind(), ErrorKind::InvalidInput);
+
+    writer.begin_array().unwrap();
+
+    assert_eq!(writer.key("c").unwrap_err().kind(), ErrorKind::InvalidInput);
+
+    writer.number(1).unwrap();
+    writer.end().unwrap();
+    writer.end().unwrap();
+
+    assert_eq!(writer.number(2).unwrap_err().kind(), ErrorKind::InvalidInput);
+    assert_eq!(writer.end().unwrap_err().kind(), ErrorKind::InvalidInput);
+
+    // Nothing was written by any of the failed calls
+    assert_eq!(writer.finish().unwrap(), br#"{"a":[1]}"#);
+
+    assert_eq!(JsonWriter::new(Vec::new()).finish().unwrap_err().kind(), ErrorKind::InvalidInput);
+
+    let mut writer = JsonWriter::new(Vec::new());
+
+    writer.begin_array().unwrap();
+
+    assert_eq!(writer.finish().unwrap_err().kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn writer_failed_stream() {
+    #[derive(Debug)]
+    struct Full(usize);
+
+    impl std::io::Write for Full {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if self.0 < buf.len() {
+                return Err(std::io::Error::new(ErrorKind::WriteZero, "full"));
+            }
+            self.0 -= buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut writer = JsonWriter::new(Full(4));
+
+    writer.begin_array().unwrap();
+    writer.number(1).unwrap();
+
+    assert_eq!(writer.string("too long").unwrap_err().kind(), ErrorKind::WriteZero);
+
+    // The writer doesn't pick up where it failed
+    assert_eq!(writer.number(2).unwrap_err().kind(), ErrorKind::Other);
+    assert_eq!(writer.end().unwrap_err().kind(), ErrorKind::Other);
+    assert_eq!(writer.finish().unwrap_err().kind(), ErrorKind::Other);
+}
+
+#[test]
+fn writer_into_borrowed_stream() {
+    let mut out = Vec::new();
+
+    {
+        let mut writer = JsonWriter::new(&mut out);
+
+        writer.begin_array().unwrap();
+        writer.string("borrowed").unwrap();
+        writer.end().unwrap();
+        writer.finish().unwrap();
+    }
+
+    assert_eq!(out, br#"["borrowed"]"#);
+}